@@ -1,5 +1,69 @@
-use std::process::Stdio;
-use tokio::process::Command;
+use crate::commands::device::{
+    app_data_dir, link_registry_entries, load_registry, register_device_internal,
+    rename_registry_entry, sync_polling, update_registry_entry, DeviceInfo,
+};
+use crate::error::{AppError, AppResult};
+use crate::services::adb_client::{run_blocking, AdbTarget};
+use crate::services::adb_executor::SharedAdbExecutor;
+use crate::services::auto_reconnect::AutoReconnectService;
+use crate::services::mdns::MdnsServiceType;
+use crate::services::mdns_discovery::{discover_devices, DiscoveredDevice, MdnsDiscoveryService};
+use crate::services::qr_pairing::{QrPairingService, QrPairingSession};
+use crate::types::health::{ErrorCode, HealthPollingConfig, ReconnectionState};
+use serde::Serialize;
+use std::net::{SocketAddr, TcpStream};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::State;
+
+/// Default port for `adb tcpip`
+const DEFAULT_TCPIP_PORT: u16 = 5555;
+
+/// How long adbd gets to come back up in TCP mode
+const TCPIP_READY_TIMEOUT: Duration = Duration::from_secs(15);
+const TCPIP_PROBE_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Why an `adb pair` attempt failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PairingErrorCode {
+    /// Pairing code is not six digits
+    InvalidCode,
+    /// Device rejected the code (or dropped the connection mid-handshake)
+    WrongCode,
+    /// Pairing port not reachable
+    ConnectionFailed,
+    /// Handshake did not complete in time
+    Timeout,
+    /// ADB server too old to support pairing (platform-tools < 30)
+    Unsupported,
+    /// Paired, but the follow-up `adb connect` failed
+    ConnectFailed,
+    /// Paired and connected, but registering the device failed
+    RegisterFailed,
+    Unknown,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PairWirelessResponse {
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_code: Option<PairingErrorCode>,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub device: Option<DeviceInfo>,
+}
+
+impl PairWirelessResponse {
+    fn failure(error_code: PairingErrorCode, message: String) -> Self {
+        Self {
+            success: false,
+            error_code: Some(error_code),
+            message,
+            device: None,
+        }
+    }
+}
 
 /// Check if ADB connect output indicates success.
 fn is_connect_success(stdout: &str) -> bool {
@@ -11,47 +75,398 @@ fn format_adb_address(ip: &str, port: u16) -> String {
     format!("{}:{}", ip, port)
 }
 
-#[tauri::command]
-pub async fn connect_wireless_device(ip: String, port: u16) -> Result<(), String> {
-    let addr = format_adb_address(&ip, port);
-    let output = Command::new("adb")
-        .args(["connect", &addr])
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .output()
-        .await
-        .map_err(|e| format!("Failed to execute adb connect: {}", e))?;
-
-    if output.status.success() {
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        if is_connect_success(&stdout) {
-            Ok(())
-        } else {
-            Err(format!("Connection failed: {}", stdout))
+/// Extract the IPv4 address from `ip -f inet addr show wlan0` output.
+fn parse_inet_addr(output: &str) -> Option<String> {
+    output.lines().find_map(|line| {
+        let mut parts = line.split_whitespace();
+        if parts.next()? != "inet" {
+            return None;
         }
+        let ip = parts.next()?.split('/').next()?;
+        (!ip.starts_with("127.")).then(|| ip.to_string())
+    })
+}
+
+/// Extract the source address from `ip route` output, preferring Wi-Fi routes.
+fn parse_route_src(output: &str) -> Option<String> {
+    let route_src = |line: &str| {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        parts
+            .iter()
+            .position(|p| *p == "src")
+            .and_then(|i| parts.get(i + 1))
+            .filter(|ip| !ip.starts_with("127."))
+            .map(|ip| ip.to_string())
+    };
+    output
+        .lines()
+        .filter(|line| line.contains("dev wlan"))
+        .find_map(route_src)
+        .or_else(|| output.lines().find_map(route_src))
+}
+
+/// Look up the device's Wi-Fi IP address over USB.
+async fn detect_device_ip(adb: &SharedAdbExecutor, serial: &str) -> AppResult<String> {
+    let query = |command: &'static str| {
+        let adb = adb.clone();
+        let target = AdbTarget::Serial(serial.to_string());
+        run_blocking(move || adb.shell(&target, command).map_err(AppError::from))
+    };
+
+    if let Ok(output) = query("ip -f inet addr show wlan0").await {
+        if let Some(ip) = parse_inet_addr(&output) {
+            return Ok(ip);
+        }
+    }
+    let output = query("ip route").await?;
+    parse_route_src(&output).ok_or_else(|| {
+        AppError::new(
+            ErrorCode::Offline,
+            "Could not determine the device's Wi-Fi IP address; is Wi-Fi on?",
+        )
+        .with_details(output.trim())
+    })
+}
+
+/// Wait until adbd accepts TCP connections on `addr`.
+fn wait_for_port(addr: SocketAddr, timeout: Duration) -> AppResult<()> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if TcpStream::connect_timeout(&addr, TCPIP_PROBE_INTERVAL).is_ok() {
+            return Ok(());
+        }
+        if Instant::now() >= deadline {
+            return Err(AppError::new(
+                ErrorCode::Timeout,
+                format!(
+                    "Device did not start listening on {} within {}s",
+                    addr,
+                    timeout.as_secs()
+                ),
+            ));
+        }
+        std::thread::sleep(TCPIP_PROBE_INTERVAL);
+    }
+}
+
+/// Check that a pairing code is exactly six digits.
+fn is_valid_pairing_code(code: &str) -> bool {
+    code.len() == 6 && code.chars().all(|c| c.is_ascii_digit())
+}
+
+/// Classify `adb pair` output.
+/// Returns the device GUID (if reported) on success.
+pub(crate) fn parse_pair_output(
+    output: &str,
+) -> Result<Option<String>, (PairingErrorCode, String)> {
+    let text = output.trim();
+    let lower = text.to_lowercase();
+
+    if lower.contains("successfully paired") {
+        let guid = text
+            .split("[guid=")
+            .nth(1)
+            .and_then(|rest| rest.split(']').next())
+            .map(|guid| guid.to_string());
+        return Ok(guid);
+    }
+
+    let code = if lower.contains("wrong password") {
+        PairingErrorCode::WrongCode
+    } else if lower.contains("timed out") || lower.contains("timeout") {
+        PairingErrorCode::Timeout
+    } else if lower.contains("unknown host service") || lower.contains("unknown command") {
+        PairingErrorCode::Unsupported
+    } else if lower.contains("unable to start pairing client")
+        || lower.contains("unable to connect")
+        || lower.contains("connection refused")
+        || lower.contains("no route to host")
+    {
+        PairingErrorCode::ConnectionFailed
+    } else {
+        PairingErrorCode::Unknown
+    };
+
+    let message = if text.is_empty() {
+        "Pairing failed with no output".to_string()
     } else {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        Err(format!("ADB connect failed: {}", stderr))
+        text.to_string()
+    };
+    Err((code, message))
+}
+
+/// Run `adb connect` for an address. Shared by the connect and pairing commands.
+pub(crate) async fn connect_address(adb: &SharedAdbExecutor, addr: String) -> AppResult<()> {
+    let target = addr.clone();
+    let adb = adb.clone();
+    let stdout = run_blocking(move || adb.connect(&target).map_err(AppError::from))
+        .await
+        .map_err(|e| e.context("ADB connect failed"))?;
+
+    if is_connect_success(&stdout) {
+        Ok(())
+    } else {
+        Err(
+            AppError::new(ErrorCode::Offline, format!("Connection to {} failed", addr))
+                .with_details(stdout.trim()),
+        )
     }
 }
 
 #[tauri::command]
-pub async fn disconnect_wireless_device(ip: String, port: u16) -> Result<(), String> {
+pub async fn connect_wireless_device(
+    ip: String,
+    port: u16,
+    adb: State<'_, SharedAdbExecutor>,
+) -> AppResult<()> {
+    connect_address(&adb, format_adb_address(&ip, port)).await
+}
+
+#[tauri::command]
+pub async fn disconnect_wireless_device(
+    ip: String,
+    port: u16,
+    adb: State<'_, SharedAdbExecutor>,
+) -> AppResult<()> {
     let addr = format_adb_address(&ip, port);
-    let output = Command::new("adb")
-        .args(["disconnect", &addr])
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .output()
+    let adb = adb.inner().clone();
+    run_blocking(move || adb.disconnect(&addr).map_err(AppError::from))
         .await
-        .map_err(|e| format!("Failed to execute adb disconnect: {}", e))?;
+        .map(|_| ())
+        .map_err(|e| e.context("ADB disconnect failed"))
+}
 
-    if output.status.success() {
-        Ok(())
-    } else {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        Err(format!("ADB disconnect failed: {}", stderr))
+/// Pair with an Android 11+ device (`adb pair ip:port code`), then connect
+/// to its wireless debugging port and add it to the registry.
+#[tauri::command]
+pub async fn pair_wireless_device(
+    ip: String,
+    pairing_port: u16,
+    pairing_code: String,
+    connect_port: u16,
+    app: tauri::AppHandle,
+    adb: State<'_, SharedAdbExecutor>,
+) -> AppResult<PairWirelessResponse> {
+    let pairing_code = pairing_code.trim().to_string();
+    if !is_valid_pairing_code(&pairing_code) {
+        return Ok(PairWirelessResponse::failure(
+            PairingErrorCode::InvalidCode,
+            "Pairing code must be 6 digits".to_string(),
+        ));
+    }
+
+    // 1. Pairing handshake
+    let pair_addr = format_adb_address(&ip, pairing_port);
+    let executor = adb.inner().clone();
+    let output = run_blocking(move || {
+        executor
+            .pair(&pair_addr, &pairing_code)
+            .map_err(AppError::from)
+    })
+    .await;
+    let parsed = match output {
+        Ok(output) => parse_pair_output(&output),
+        Err(e) => parse_pair_output(&e.message),
+    };
+    if let Err((code, message)) = parsed {
+        return Ok(PairWirelessResponse::failure(code, message));
+    }
+
+    // 2. Connect to the wireless debugging port
+    let serial = format_adb_address(&ip, connect_port);
+    if let Err(e) = connect_address(&adb, serial.clone()).await {
+        return Ok(PairWirelessResponse::failure(
+            PairingErrorCode::ConnectFailed,
+            format!("Paired, but connecting failed: {}", e),
+        ));
+    }
+
+    // 3. Register the new device
+    let app_data_dir = app_data_dir(&app)?;
+    let registered = register_device_internal(&adb, &serial, &app_data_dir).await;
+    sync_polling(&app, &load_registry(&app_data_dir));
+    match registered {
+        Ok(device) => Ok(PairWirelessResponse {
+            success: true,
+            error_code: None,
+            message: format!("Paired and connected to {}", serial),
+            device: Some(device),
+        }),
+        Err(e) => Ok(PairWirelessResponse::failure(
+            PairingErrorCode::RegisterFailed,
+            format!("Paired and connected, but registration failed: {}", e),
+        )),
+    }
+}
+
+/// Start a QR code pairing session. Progress is reported through
+/// `qr-pairing-status` events until the device is paired and registered.
+#[tauri::command]
+pub async fn start_qr_pairing(
+    qr_pairing: State<'_, Mutex<QrPairingService>>,
+) -> AppResult<QrPairingSession> {
+    let mut service = qr_pairing.lock().map_err(AppError::lock)?;
+    service.start_session()
+}
+
+/// Cancel the active QR code pairing session
+#[tauri::command]
+pub async fn cancel_qr_pairing(qr_pairing: State<'_, Mutex<QrPairingService>>) -> AppResult<()> {
+    let mut service = qr_pairing.lock().map_err(AppError::lock)?;
+    service.cancel_session();
+    Ok(())
+}
+
+/// Switch a USB device to wireless: restart adbd in TCP mode, connect to
+/// its Wi-Fi address and link the new wireless serial to the USB entry.
+#[tauri::command]
+pub async fn go_wireless(
+    serial: String,
+    port: Option<u16>,
+    app: tauri::AppHandle,
+    adb: State<'_, SharedAdbExecutor>,
+) -> AppResult<DeviceInfo> {
+    let port = port.unwrap_or(DEFAULT_TCPIP_PORT);
+    let app_data_dir = app_data_dir(&app)?;
+
+    // 1. Find the Wi-Fi IP while USB is still available
+    let ip = detect_device_ip(&adb, &serial).await?;
+    let addr: SocketAddr = format_adb_address(&ip, port)
+        .parse()
+        .map_err(|e| AppError::invalid_input(format!("Invalid device address {}: {}", ip, e)))?;
+
+    // 2. Restart adbd in TCP mode and wait for it to listen
+    let usb = AdbTarget::Serial(serial.clone());
+    let executor = adb.inner().clone();
+    run_blocking(move || executor.tcpip(&usb, port).map_err(AppError::from))
+        .await
+        .map_err(|e| e.context("Failed to switch device to TCP/IP mode"))?;
+    run_blocking(move || wait_for_port(addr, TCPIP_READY_TIMEOUT)).await?;
+
+    // 3. Connect and register the wireless serial
+    let wireless_serial = addr.to_string();
+    connect_address(&adb, wireless_serial.clone()).await?;
+    register_device_internal(&adb, &wireless_serial, &app_data_dir).await?;
+    sync_polling(&app, &load_registry(&app_data_dir));
+
+    // 4. Link it to the USB entry
+    link_registry_entries(&app_data_dir, &serial, &wireless_serial).await
+}
+
+/// Discover wireless debugging services on the LAN, matched against the registry
+#[tauri::command]
+pub async fn discover_wireless_devices(
+    app: tauri::AppHandle,
+    adb: State<'_, SharedAdbExecutor>,
+) -> AppResult<Vec<DiscoveredDevice>> {
+    let app_data_dir = app_data_dir(&app)?;
+    discover_devices(&adb, &app_data_dir).await
+}
+
+/// Start watching for wireless debugging services. Changes are reported
+/// through `wireless-devices-discovered` events.
+#[tauri::command]
+pub async fn start_wireless_discovery(
+    discovery: State<'_, Mutex<MdnsDiscoveryService>>,
+) -> AppResult<()> {
+    let mut service = discovery.lock().map_err(AppError::lock)?;
+    service.start_watching()
+}
+
+/// Stop watching for wireless debugging services
+#[tauri::command]
+pub async fn stop_wireless_discovery(
+    discovery: State<'_, Mutex<MdnsDiscoveryService>>,
+) -> AppResult<()> {
+    let mut service = discovery.lock().map_err(AppError::lock)?;
+    service.stop_watching();
+    Ok(())
+}
+
+/// Reconnect a known device at whatever port it currently advertises, then
+/// move its registry entry (and settings) to the new wireless serial.
+#[tauri::command]
+pub async fn reconnect_known_device(
+    serial: String,
+    app: tauri::AppHandle,
+    adb: State<'_, SharedAdbExecutor>,
+) -> AppResult<DeviceInfo> {
+    let app_data_dir = app_data_dir(&app)?;
+
+    let discovered = discover_devices(&adb, &app_data_dir).await?;
+    let target = discovered
+        .iter()
+        .find(|d| {
+            d.service_type != MdnsServiceType::TlsPairing
+                && d.known_serial.as_deref() == Some(serial.as_str())
+        })
+        .ok_or_else(|| {
+            AppError::device_not_found(format!(
+                "No wireless debugging service found for {}",
+                serial
+            ))
+        })?;
+
+    connect_address(&adb, target.address.clone()).await?;
+    rename_registry_entry(&app_data_dir, &serial, &target.address).await?;
+    // Refresh status and props of the moved entry
+    let registered = register_device_internal(&adb, &target.address, &app_data_dir).await;
+    // Stop polling the old serial even if the refresh failed
+    sync_polling(&app, &load_registry(&app_data_dir));
+    registered
+}
+
+/// Opt a registered wireless device in or out of automatic reconnection
+#[tauri::command]
+pub async fn set_auto_reconnect(
+    serial: String,
+    enabled: bool,
+    app: tauri::AppHandle,
+    auto_reconnect: State<'_, Mutex<AutoReconnectService>>,
+) -> AppResult<DeviceInfo> {
+    let app_data_dir = app_data_dir(&app)?;
+
+    let device = update_registry_entry(&app_data_dir, &serial, |device| {
+        // Only wireless devices can be reconnected with `adb connect`
+        device.auto_reconnect = enabled && device.is_wireless;
+    })
+    .await?;
+    if enabled && !device.auto_reconnect {
+        return Err(AppError::invalid_input(format!(
+            "{} is not a wireless device",
+            serial
+        )));
     }
+
+    // Start over with a full set of attempts
+    auto_reconnect
+        .lock()
+        .map_err(AppError::lock)?
+        .reset_device(&serial);
+    Ok(device)
+}
+
+/// Update the retry settings used by automatic reconnection
+#[tauri::command]
+pub async fn set_auto_reconnect_config(
+    config: HealthPollingConfig,
+    auto_reconnect: State<'_, Mutex<AutoReconnectService>>,
+) -> AppResult<()> {
+    config.validate().map_err(AppError::invalid_input)?;
+    auto_reconnect
+        .lock()
+        .map_err(AppError::lock)?
+        .set_config(config);
+    Ok(())
+}
+
+/// Reconnection attempts currently in progress
+#[tauri::command]
+pub async fn get_reconnection_states(
+    auto_reconnect: State<'_, Mutex<AutoReconnectService>>,
+) -> AppResult<Vec<ReconnectionState>> {
+    let service = auto_reconnect.lock().map_err(AppError::lock)?;
+    Ok(service.get_states())
 }
 
 #[cfg(test)]
@@ -99,4 +514,94 @@ mod tests {
     fn connect_failure_empty() {
         assert!(!is_connect_success(""));
     }
+
+    #[test]
+    fn inet_addr_parses_wlan0() {
+        let output = "30: wlan0: <BROADCAST,MULTICAST,UP,LOWER_UP> mtu 1500 qdisc mq state UP group default qlen 3000\n    inet 192.168.1.42/24 brd 192.168.1.255 scope global wlan0\n       valid_lft forever preferred_lft forever\n";
+        assert_eq!(parse_inet_addr(output).as_deref(), Some("192.168.1.42"));
+    }
+
+    #[test]
+    fn inet_addr_missing_interface() {
+        assert_eq!(parse_inet_addr("Device \"wlan0\" does not exist.\n"), None);
+        assert_eq!(parse_inet_addr(""), None);
+    }
+
+    #[test]
+    fn route_src_prefers_wifi() {
+        let output = "10.0.2.0/24 dev rmnet0 proto kernel scope link src 10.0.2.15\n192.168.1.0/24 dev wlan0 proto kernel scope link src 192.168.1.42\n";
+        assert_eq!(parse_route_src(output).as_deref(), Some("192.168.1.42"));
+    }
+
+    #[test]
+    fn route_src_falls_back_to_any_route() {
+        let output = "10.0.2.0/24 dev eth0 proto kernel scope link src 10.0.2.15\n";
+        assert_eq!(parse_route_src(output).as_deref(), Some("10.0.2.15"));
+        assert_eq!(parse_route_src(""), None);
+    }
+
+    #[test]
+    fn wait_for_port_sees_listener() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        assert!(wait_for_port(addr, Duration::from_secs(1)).is_ok());
+    }
+
+    #[test]
+    fn pairing_code_validation() {
+        assert!(is_valid_pairing_code("123456"));
+        assert!(!is_valid_pairing_code("12345"));
+        assert!(!is_valid_pairing_code("1234567"));
+        assert!(!is_valid_pairing_code("12a456"));
+        assert!(!is_valid_pairing_code(""));
+    }
+
+    #[test]
+    fn pair_success_extracts_guid() {
+        let output = "Successfully paired to 192.168.1.5:37123 [guid=adb-R58M123-AbCdEf]";
+        assert_eq!(
+            parse_pair_output(output),
+            Ok(Some("adb-R58M123-AbCdEf".to_string()))
+        );
+    }
+
+    #[test]
+    fn pair_success_without_guid() {
+        assert_eq!(
+            parse_pair_output("Successfully paired to 192.168.1.5:37123"),
+            Ok(None)
+        );
+    }
+
+    #[test]
+    fn pair_failure_wrong_code() {
+        let (code, _) =
+            parse_pair_output("Failed: Wrong password or connection was dropped.").unwrap_err();
+        assert_eq!(code, PairingErrorCode::WrongCode);
+    }
+
+    #[test]
+    fn pair_failure_connection() {
+        let (code, _) = parse_pair_output("Failed: Unable to start pairing client.").unwrap_err();
+        assert_eq!(code, PairingErrorCode::ConnectionFailed);
+    }
+
+    #[test]
+    fn pair_failure_unsupported_server() {
+        let (code, _) = parse_pair_output("ADB command failed: unknown host service").unwrap_err();
+        assert_eq!(code, PairingErrorCode::Unsupported);
+    }
+
+    #[test]
+    fn pair_failure_empty_output() {
+        let (code, message) = parse_pair_output("").unwrap_err();
+        assert_eq!(code, PairingErrorCode::Unknown);
+        assert!(!message.is_empty());
+    }
+
+    #[test]
+    fn pair_error_code_serializes_snake_case() {
+        let json = serde_json::to_value(PairingErrorCode::WrongCode).unwrap();
+        assert_eq!(json, "wrong_code");
+    }
 }
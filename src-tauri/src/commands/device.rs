@@ -1,9 +1,12 @@
-use std::collections::HashMap;
+use crate::error::{AppError, AppResult};
+use crate::services::adb_client::{run_blocking, AdbTarget};
+use crate::services::adb_executor::SharedAdbExecutor;
+use crate::services::{DeviceTracker, HealthPollingService};
+use crate::types::health::{ConnectionType, ErrorCode};
 use std::fs;
-use std::path::Path;
-use std::process::Stdio;
-use tauri::Manager;
-use tokio::process::Command;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{Manager, State};
 
 #[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
 pub struct DeviceInfo {
@@ -17,6 +20,32 @@ pub struct DeviceInfo {
     pub last_seen: Option<String>,
     #[serde(default = "default_first_seen")]
     pub first_seen: String,
+    /// Serial of the other transport of the same phone (USB <-> wireless),
+    /// set by `go_wireless`
+    #[serde(default)]
+    pub linked_serial: Option<String>,
+    /// Opt-in: retry `adb connect` automatically when this wireless device drops
+    #[serde(default)]
+    pub auto_reconnect: bool,
+    /// `ro.serialno` (or `ro.boot.serialno`): identical across the USB and
+    /// wireless transports of one phone
+    #[serde(default)]
+    pub hardware_serial: Option<String>,
+    /// USB port path from `adb devices -l` (`usb:1-2.3`), tells identical phones apart
+    #[serde(default)]
+    pub usb_path: Option<String>,
+    /// `product:` from `adb devices -l`
+    #[serde(default)]
+    pub product: Option<String>,
+    /// `device:` (codename) from `adb devices -l`
+    #[serde(default)]
+    pub device_codename: Option<String>,
+    /// ADB transport id; only valid while the device is attached
+    #[serde(default)]
+    pub transport_id: Option<u32>,
+    /// Extra text ADB gave with the state, e.g. the udev hint of `no permissions`
+    #[serde(default)]
+    pub status_detail: Option<String>,
 }
 
 fn default_first_seen() -> String {
@@ -27,10 +56,156 @@ fn now_iso8601() -> String {
     chrono::Utc::now().to_rfc3339()
 }
 
-/// Minimal struct for ADB device list parsing
-struct AdbDevice {
-    serial: String,
-    status: String,
+/// Device state column of `adb devices`
+#[derive(serde::Serialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AdbDeviceState {
+    Device,
+    Offline,
+    /// RSA key not accepted on the phone yet
+    Unauthorized,
+    Authorizing,
+    Connecting,
+    /// USB node not accessible to this user (udev rules / plugdev on Linux)
+    NoPermissions,
+    Bootloader,
+    Recovery,
+    Rescue,
+    Sideload,
+    Host,
+    Detached,
+    #[default]
+    Unknown,
+}
+
+impl AdbDeviceState {
+    /// Parse the (possibly multi-word) state text, returning the state and any
+    /// extra detail ADB appended to it
+    pub fn parse(text: &str) -> (Self, Option<String>) {
+        let text = text.trim();
+        if let Some(rest) = text.strip_prefix("no permissions") {
+            let detail = rest
+                .trim_start()
+                .strip_prefix('(')
+                .and_then(|inner| inner.split(')').next())
+                .map(str::trim)
+                .filter(|detail| !detail.is_empty())
+                .map(str::to_string);
+            return (Self::NoPermissions, detail);
+        }
+
+        let state = match text {
+            "device" => Self::Device,
+            "offline" => Self::Offline,
+            "unauthorized" => Self::Unauthorized,
+            "authorizing" => Self::Authorizing,
+            "connecting" => Self::Connecting,
+            "bootloader" => Self::Bootloader,
+            "recovery" => Self::Recovery,
+            "rescue" => Self::Rescue,
+            "sideload" => Self::Sideload,
+            "host" => Self::Host,
+            "detached" => Self::Detached,
+            _ => return (Self::Unknown, (!text.is_empty()).then(|| text.to_string())),
+        };
+        (state, None)
+    }
+
+    /// Status string stored in `DeviceInfo::status`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Device => "device",
+            Self::Offline => "offline",
+            Self::Unauthorized => "unauthorized",
+            Self::Authorizing => "authorizing",
+            Self::Connecting => "connecting",
+            Self::NoPermissions => "no_permissions",
+            Self::Bootloader => "bootloader",
+            Self::Recovery => "recovery",
+            Self::Rescue => "rescue",
+            Self::Sideload => "sideload",
+            Self::Host => "host",
+            Self::Detached => "detached",
+            Self::Unknown => "unknown",
+        }
+    }
+}
+
+/// One line of `adb devices -l`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct AdbDevice {
+    pub(crate) serial: String,
+    pub(crate) state: AdbDeviceState,
+    pub(crate) state_detail: Option<String>,
+    pub(crate) usb: Option<String>,
+    pub(crate) product: Option<String>,
+    pub(crate) model: Option<String>,
+    pub(crate) device: Option<String>,
+    pub(crate) transport_id: Option<u32>,
+}
+
+impl AdbDevice {
+    /// `model:` field as a display name (`Pixel_6` -> `Pixel 6`)
+    fn display_model(&self) -> Option<String> {
+        self.model.as_ref().map(|m| m.replace('_', " "))
+    }
+
+    /// Status string for `DeviceInfo::status`
+    pub(crate) fn status(&self) -> &'static str {
+        self.state.as_str()
+    }
+
+    /// Transport kind: a `usb:` field means USB, otherwise judge by serial
+    pub(crate) fn connection_type(&self) -> ConnectionType {
+        if self.usb.is_some() {
+            ConnectionType::Usb
+        } else {
+            ConnectionType::from_serial(&self.serial)
+        }
+    }
+
+    /// Copy the state detail and `-l` fields onto a registry entry
+    fn apply_to(&self, device: &mut DeviceInfo) {
+        // Also corrects entries saved when mDNS serials were taken for USB
+        device.is_wireless = self.connection_type() == ConnectionType::Wireless;
+        device.status_detail = self.state_detail.clone();
+        device.transport_id = self.transport_id;
+        if self.usb.is_some() {
+            device.usb_path = self.usb.clone();
+        }
+        if self.product.is_some() {
+            device.product = self.product.clone();
+        }
+        if self.device.is_some() {
+            device.device_codename = self.device.clone();
+        }
+        if device.model.is_none() {
+            device.model = self.display_model();
+        }
+    }
+}
+
+/// Address a device by serial, or by transport id when several attached
+/// devices report the same serial.
+pub(crate) fn target_for(adb_devices: &[AdbDevice], serial: &str) -> AdbTarget {
+    let mut matches = adb_devices.iter().filter(|d| d.serial == serial);
+    let first = matches.next();
+    match (first, matches.next()) {
+        (
+            Some(AdbDevice {
+                transport_id: Some(id),
+                ..
+            }),
+            Some(_),
+        ) => AdbTarget::TransportId(*id),
+        _ => AdbTarget::Serial(serial.to_string()),
+    }
+}
+
+lazy_static::lazy_static! {
+    /// Serializes read-modify-write cycles on devices.json between
+    /// commands and the background device tracker
+    static ref REGISTRY_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::new(());
 }
 
 // ─── Registry I/O ──────────────────────────────────────────────────────────
@@ -46,70 +221,130 @@ pub fn load_registry(app_data_dir: &Path) -> Vec<DeviceInfo> {
     }
 }
 
-pub fn save_registry(app_data_dir: &Path, devices: &[DeviceInfo]) -> Result<(), String> {
+pub fn save_registry(app_data_dir: &Path, devices: &[DeviceInfo]) -> AppResult<()> {
     fs::create_dir_all(app_data_dir)
-        .map_err(|e| format!("Failed to create data directory: {}", e))?;
+        .map_err(|e| AppError::io("Failed to create data directory", e))?;
     let path = app_data_dir.join("devices.json");
     let json = serde_json::to_string_pretty(devices)
-        .map_err(|e| format!("Failed to serialize device registry: {}", e))?;
-    fs::write(&path, json).map_err(|e| format!("Failed to write device registry: {}", e))?;
+        .map_err(|e| AppError::internal(format!("Failed to serialize device registry: {}", e)))?;
+    fs::write(&path, json).map_err(|e| AppError::io("Failed to write device registry", e))?;
     Ok(())
 }
 
+/// Let the health poller follow the saved registry, if it's been asked to.
+/// Call after any change that adds, removes or renames entries.
+pub(crate) fn sync_polling(app: &tauri::AppHandle, registry: &[DeviceInfo]) {
+    if let Some(polling) = app.try_state::<Mutex<HealthPollingService>>() {
+        if let Ok(mut polling) = polling.lock() {
+            polling.sync_with_registry(registry);
+        }
+    }
+}
+
+/// Directory holding the registry and other app data
+pub(crate) fn app_data_dir(app: &tauri::AppHandle) -> AppResult<PathBuf> {
+    app.path()
+        .app_data_dir()
+        .map_err(|e| AppError::internal(format!("Failed to resolve app data dir: {}", e)))
+}
+
 // ─── Three-way merge ──────────────────────────────────────────────────────
 
+/// Pair registry entries with `adb devices` rows by serial. Phones sharing a
+/// serial are told apart by USB path: rows on the entry's recorded path are
+/// paired first, then any remaining row with the serial.
+fn match_adb_rows(registry: &[DeviceInfo], adb_devices: &[AdbDevice]) -> Vec<Option<usize>> {
+    let mut taken = vec![false; adb_devices.len()];
+    let mut rows = vec![None; registry.len()];
+    for same_path_only in [true, false] {
+        for (device, row) in registry.iter().zip(rows.iter_mut()) {
+            if row.is_some() {
+                continue;
+            }
+            *row = (0..adb_devices.len()).find(|&i| {
+                let adb_device = &adb_devices[i];
+                !taken[i]
+                    && adb_device.serial == device.serial
+                    && (!same_path_only
+                        || (device.usb_path.is_some() && adb_device.usb == device.usb_path))
+            });
+            if let Some(i) = *row {
+                taken[i] = true;
+            }
+        }
+    }
+    rows
+}
+
 /// Merge persistent registry with current ADB output (ignore unregistered devices).
 /// Returns (merged_devices, serials_needing_prop_fetch).
-fn merge_devices(
+pub(crate) fn merge_devices(
     registry: Vec<DeviceInfo>,
     adb_devices: &[AdbDevice],
 ) -> (Vec<DeviceInfo>, Vec<String>) {
     let now = now_iso8601();
-    let mut adb_map: HashMap<String, String> = adb_devices
-        .iter()
-        .map(|d| (d.serial.clone(), d.status.clone()))
-        .collect();
+    let rows = match_adb_rows(&registry, adb_devices);
+    let mut unmatched = vec![true; adb_devices.len()];
 
     let mut result: Vec<DeviceInfo> = Vec::new();
     let mut needs_props: Vec<String> = Vec::new();
 
     // Process existing registry entries
-    for mut device in registry {
-        if let Some(adb_status) = adb_map.remove(&device.serial) {
-            let was_disconnected = device.status == "disconnected"
-                || device.status == "offline"
-                || device.status == "unauthorized";
-            device.status = adb_status.clone();
+    for (mut device, row) in registry.into_iter().zip(rows) {
+        if let Some(i) = row {
+            unmatched[i] = false;
+            let adb_device = &adb_devices[i];
+            let was_disconnected = device.status != AdbDeviceState::Device.as_str();
+            device.status = adb_device.status().to_string();
             device.last_seen = Some(now.clone());
+            adb_device.apply_to(&mut device);
 
             // Fetch props if device is connected and was previously disconnected or is new
-            if adb_status == "device" && (was_disconnected || device.model.is_none()) {
+            if adb_device.state == AdbDeviceState::Device
+                && (was_disconnected || device.model.is_none() || device.hardware_serial.is_none())
+            {
                 needs_props.push(device.serial.clone());
             }
             result.push(device);
         } else {
             // Device in registry but not in ADB → disconnected
             device.status = "disconnected".to_string();
+            device.status_detail = None;
+            device.transport_id = None;
             // Preserve cached metadata (model, android_version, battery_level)
             result.push(device);
         }
     }
 
     // Process new devices from ADB that aren't in the registry
-    for (serial, status) in adb_map {
-        let device = DeviceInfo {
+    let new_devices = adb_devices
+        .iter()
+        .zip(unmatched)
+        .filter_map(|(adb_device, unmatched)| unmatched.then_some(adb_device));
+    for adb_device in new_devices {
+        let serial = adb_device.serial.clone();
+        let mut device = DeviceInfo {
             serial: serial.clone(),
-            status: status.clone(),
+            status: adb_device.status().to_string(),
             first_seen: now.clone(),
             last_seen: Some(now.clone()),
             model: None,
             android_version: None,
             battery_level: None,
-            is_wireless: false,
+            is_wireless: adb_device.connection_type() == ConnectionType::Wireless,
+            linked_serial: None,
+            auto_reconnect: false,
+            hardware_serial: None,
+            usb_path: None,
+            product: None,
+            device_codename: None,
+            transport_id: None,
+            status_detail: None,
         };
+        adb_device.apply_to(&mut device);
         
         // Fetch props if device is newly connected
-        if status == "device" {
+        if device.status == "device" {
             needs_props.push(serial);
         }
         result.push(device);
@@ -118,108 +353,321 @@ fn merge_devices(
     (result, needs_props)
 }
 
+/// Point a USB entry and a wireless entry of the same phone at each other.
+/// Entries missing from the registry are left alone.
+pub(crate) fn link_devices(registry: &mut [DeviceInfo], usb_serial: &str, wireless_serial: &str) {
+    for device in registry.iter_mut() {
+        if device.serial == usb_serial {
+            device.linked_serial = Some(wireless_serial.to_string());
+        } else if device.serial == wireless_serial {
+            device.linked_serial = Some(usb_serial.to_string());
+        }
+    }
+}
+
+/// Move a registry entry to a new serial (a wireless device that came back on
+/// another port), keeping its settings. Any entry already registered under
+/// the new serial is a duplicate of the same phone and is dropped. Returns
+/// false when `old_serial` is not registered.
+pub(crate) fn rename_device(
+    registry: &mut Vec<DeviceInfo>,
+    old_serial: &str,
+    new_serial: &str,
+) -> bool {
+    if !registry.iter().any(|d| d.serial == old_serial) {
+        return false;
+    }
+    if old_serial == new_serial {
+        return true;
+    }
+    registry.retain(|d| d.serial != new_serial);
+    for device in registry.iter_mut() {
+        if device.serial == old_serial {
+            device.serial = new_serial.to_string();
+        }
+        if device.linked_serial.as_deref() == Some(old_serial) {
+            device.linked_serial = Some(new_serial.to_string());
+        }
+    }
+    true
+}
+
+// ─── Logical devices ──────────────────────────────────────────────────────
+
+/// One physical phone and every transport (USB, Wi-Fi) it is registered under
+#[derive(serde::Serialize, Clone, Debug)]
+pub struct LogicalDevice {
+    /// Hardware serial when known, otherwise the serial of the first transport
+    pub id: String,
+    pub hardware_serial: Option<String>,
+    pub model: Option<String>,
+    pub android_version: Option<String>,
+    /// Best transport to use right now (`None` if none is online)
+    pub preferred_serial: Option<String>,
+    pub transports: Vec<DeviceInfo>,
+}
+
+/// Key identifying the physical phone behind a registry entry
+fn identity_key(device: &DeviceInfo, registry: &[DeviceInfo]) -> String {
+    if let Some(hardware_serial) = &device.hardware_serial {
+        return hardware_serial.clone();
+    }
+    if !device.is_wireless {
+        // USB serials are the hardware serial
+        return device.serial.clone();
+    }
+    device
+        .linked_serial
+        .as_ref()
+        .and_then(|linked| registry.iter().find(|d| &d.serial == linked))
+        .map(|linked| {
+            linked
+                .hardware_serial
+                .clone()
+                .unwrap_or_else(|| linked.serial.clone())
+        })
+        .unwrap_or_else(|| device.serial.clone())
+}
+
+/// Pick the transport to use: online USB first, then online wireless
+pub(crate) fn best_transport(transports: &[DeviceInfo]) -> Option<&DeviceInfo> {
+    let online = || transports.iter().filter(|d| d.status == "device");
+    online()
+        .find(|d| !d.is_wireless)
+        .or_else(|| online().next())
+}
+
+/// Group registry entries by physical phone, in registry order
+pub(crate) fn group_devices(devices: &[DeviceInfo]) -> Vec<LogicalDevice> {
+    let mut groups: Vec<LogicalDevice> = Vec::new();
+    for device in devices {
+        let key = identity_key(device, devices);
+        let index = match groups.iter().position(|g| g.id == key) {
+            Some(index) => index,
+            None => {
+                groups.push(LogicalDevice {
+                    id: key,
+                    hardware_serial: None,
+                    model: None,
+                    android_version: None,
+                    preferred_serial: None,
+                    transports: Vec::new(),
+                });
+                groups.len() - 1
+            }
+        };
+        let group = &mut groups[index];
+        group.hardware_serial = group
+            .hardware_serial
+            .take()
+            .or_else(|| device.hardware_serial.clone());
+        group.model = group.model.take().or_else(|| device.model.clone());
+        group.android_version = group
+            .android_version
+            .take()
+            .or_else(|| device.android_version.clone());
+        group.transports.push(device.clone());
+    }
+    for group in &mut groups {
+        group.preferred_serial = best_transport(&group.transports).map(|d| d.serial.clone());
+    }
+    groups
+}
+
+/// The phone a transport serial or logical device id belongs to
+pub(crate) fn logical_device(devices: &[DeviceInfo], serial: &str) -> Option<LogicalDevice> {
+    group_devices(devices)
+        .into_iter()
+        .find(|g| g.id == serial || g.transports.iter().any(|d| d.serial == serial))
+}
+
+/// Resolve a transport serial or logical device id to the best online transport
+pub(crate) fn resolve_transport(devices: &[DeviceInfo], serial: &str) -> Option<String> {
+    logical_device(devices, serial).and_then(|g| g.preferred_serial)
+}
+
 // ─── ADB helpers ──────────────────────────────────────────────────────────
 
-async fn get_prop(serial: &str, prop: &str) -> Result<String, String> {
-    let output = Command::new("adb")
-        .args(["-s", serial, "shell", "getprop", prop])
-        .stdout(Stdio::piped())
-        .output()
+async fn adb_shell(
+    adb: &SharedAdbExecutor,
+    target: &AdbTarget,
+    command: &str,
+) -> AppResult<String> {
+    let adb = adb.clone();
+    let target = target.clone();
+    let command = command.to_string();
+    run_blocking(move || adb.shell(&target, &command).map_err(AppError::from)).await
+}
+
+async fn get_prop(adb: &SharedAdbExecutor, target: &AdbTarget, prop: &str) -> AppResult<String> {
+    adb_shell(adb, target, &format!("getprop {}", prop))
         .await
-        .map_err(|e| format!("Failed to get prop {}: {}", prop, e))?;
+        .map(|value| value.trim().to_string())
+        .map_err(|e| e.context(&format!("Failed to get prop {}", prop)))
+}
 
-    if output.status.success() {
-        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
-    } else {
-        Err("Command failed".to_string())
+async fn get_battery_level(adb: &SharedAdbExecutor, target: &AdbTarget) -> AppResult<i32> {
+    let stdout = adb_shell(adb, target, "dumpsys battery")
+        .await
+        .map_err(|e| e.context("Failed to get battery"))?;
+
+    for line in stdout.lines() {
+        if line.contains("level:") {
+            let parts: Vec<&str> = line.split(':').collect();
+            if parts.len() == 2 {
+                return parts[1].trim().parse().map_err(|_| {
+                    AppError::new(ErrorCode::ParseError, "Parse error").with_details(line.trim())
+                });
+            }
+        }
     }
+    Err(AppError::new(ErrorCode::ParseError, "Level not found"))
 }
 
-async fn get_battery_level(serial: &str) -> Result<i32, String> {
-    let output = Command::new("adb")
-        .args(["-s", serial, "shell", "dumpsys", "battery"])
-        .stdout(Stdio::piped())
-        .output()
-        .await
-        .map_err(|e| format!("Failed to get battery: {}", e))?;
-
-    if output.status.success() {
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        for line in stdout.lines() {
-            if line.contains("level:") {
-                let parts: Vec<&str> = line.split(':').collect();
-                if parts.len() == 2 {
-                    return parts[1]
-                        .trim()
-                        .parse()
-                        .map_err(|_| "Parse error".to_string());
-                }
+/// Read the hardware serial, falling back to the bootloader-provided one
+async fn get_hardware_serial(adb: &SharedAdbExecutor, target: &AdbTarget) -> Option<String> {
+    for prop in ["ro.serialno", "ro.boot.serialno"] {
+        if let Ok(value) = get_prop(adb, target, prop).await {
+            if !value.is_empty() {
+                return Some(value);
             }
         }
-        Err("Level not found".to_string())
-    } else {
-        Err("Command failed".to_string())
+    }
+    None
+}
+
+/// Properties read from a connected device
+struct DeviceProps {
+    model: Option<String>,
+    android_version: Option<String>,
+    battery_level: Option<i32>,
+    hardware_serial: Option<String>,
+}
+
+impl DeviceProps {
+    fn apply_to(self, device: &mut DeviceInfo) {
+        // Keep the `adb devices -l` model if getprop failed
+        device.model = self.model.or(device.model.take());
+        device.android_version = self.android_version;
+        device.battery_level = self.battery_level;
+        if self.hardware_serial.is_some() {
+            device.hardware_serial = self.hardware_serial;
+        }
     }
 }
 
 /// Fetch expensive device properties. Called only for devices that need it.
-async fn fetch_device_props(serial: &str) -> (Option<String>, Option<String>, Option<i32>) {
-    let model = get_prop(serial, "ro.product.model").await.ok();
-    let android_version = get_prop(serial, "ro.build.version.release").await.ok();
-    let battery_level = get_battery_level(serial).await.ok();
-    (model, android_version, battery_level)
+async fn fetch_device_props(adb: &SharedAdbExecutor, target: &AdbTarget) -> DeviceProps {
+    DeviceProps {
+        model: get_prop(adb, target, "ro.product.model").await.ok(),
+        android_version: get_prop(adb, target, "ro.build.version.release").await.ok(),
+        battery_level: get_battery_level(adb, target).await.ok(),
+        hardware_serial: get_hardware_serial(adb, target).await,
+    }
 }
 
-/// Parse `adb devices` output into AdbDevice list
-fn parse_adb_output(stdout: &str) -> Vec<AdbDevice> {
+/// Keys of the `key:value` fields appended by `adb devices -l`
+const DEVICE_LIST_KEYS: [&str; 5] = ["usb", "product", "model", "device", "transport_id"];
+
+/// Split a `key:value` token if `key` is one of the `-l` fields
+fn split_device_field(token: &str) -> Option<(&str, &str)> {
+    token
+        .split_once(':')
+        .filter(|(key, _)| DEVICE_LIST_KEYS.contains(key))
+}
+
+/// Parse `adb devices` / `adb devices -l` output into AdbDevice list.
+/// Accepts both the binary's output (with header) and the server's
+/// `host:devices-l` payload (without header). The state is everything
+/// between the serial and the first `key:value` field, so multi-word
+/// states like `no permissions (...)` survive.
+pub(crate) fn parse_adb_output(stdout: &str) -> Vec<AdbDevice> {
     let mut devices = Vec::new();
-    for line in stdout.lines().skip(1) {
-        let parts: Vec<&str> = line.split_whitespace().collect();
-        if parts.len() >= 2 {
-            devices.push(AdbDevice {
-                serial: parts[0].to_string(),
-                status: parts[1].to_string(),
-            });
+    for line in stdout.lines() {
+        if line.starts_with("List of devices") || line.starts_with('*') {
+            continue;
         }
+        let mut tokens = line.split_whitespace();
+        let Some(serial) = tokens.next() else {
+            continue;
+        };
+
+        let mut device = AdbDevice {
+            serial: serial.to_string(),
+            ..Default::default()
+        };
+        let mut state: Vec<&str> = Vec::new();
+        let mut in_fields = false;
+        for token in tokens {
+            match split_device_field(token) {
+                Some((key, value)) => {
+                    in_fields = true;
+                    let value = Some(value.to_string());
+                    match key {
+                        "usb" => device.usb = value,
+                        "product" => device.product = value,
+                        "model" => device.model = value,
+                        "device" => device.device = value,
+                        _ => device.transport_id = value.and_then(|v| v.parse().ok()),
+                    }
+                }
+                // Unknown tokens after the first field belong to no column
+                None if in_fields => {}
+                None => state.push(token),
+            }
+        }
+        if state.is_empty() {
+            continue;
+        }
+        (device.state, device.state_detail) = AdbDeviceState::parse(&state.join(" "));
+        devices.push(device);
     }
     devices
 }
 
-async fn list_adb_devices_internal() -> Result<Vec<DeviceInfo>, String> {
-    let output = Command::new("adb")
-        .arg("devices")
-        .stdout(Stdio::piped())
-        .output()
+/// Query the ADB server for currently attached devices
+async fn query_adb_devices(adb: &SharedAdbExecutor) -> AppResult<Vec<AdbDevice>> {
+    let adb = adb.clone();
+    let stdout = run_blocking(move || adb.devices().map_err(AppError::from))
         .await
-        .map_err(|e| format!("Failed to run adb devices: {}", e))?;
-
-    if !output.status.success() {
-        return Err("adb devices command failed".to_string());
-    }
+        .map_err(|e| e.context("Failed to run adb devices"))?;
+    Ok(parse_adb_output(&stdout))
+}
 
-    let stdout =
-        String::from_utf8(output.stdout).map_err(|e| format!("Invalid UTF-8 output: {}", e))?;
-    let adb_devices = parse_adb_output(&stdout);
+async fn list_adb_devices_internal(adb: &SharedAdbExecutor) -> AppResult<Vec<DeviceInfo>> {
+    let adb_devices = query_adb_devices(adb).await?;
     let now = now_iso8601();
 
     let mut devices: Vec<DeviceInfo> = Vec::new();
-    for adb_device in adb_devices {
+    for adb_device in &adb_devices {
         let mut info = DeviceInfo {
-            is_wireless: adb_device.serial.contains(':'),
-            serial: adb_device.serial,
-            status: adb_device.status,
+            is_wireless: adb_device.connection_type() == ConnectionType::Wireless,
+            serial: adb_device.serial.clone(),
+            status: adb_device.status().to_string(),
             model: None,
             android_version: None,
             battery_level: None,
             last_seen: Some(now.clone()),
             first_seen: now.clone(),
+            linked_serial: None,
+            auto_reconnect: false,
+            hardware_serial: None,
+            usb_path: None,
+            product: None,
+            device_codename: None,
+            transport_id: None,
+            status_detail: None,
         };
 
+        adb_device.apply_to(&mut info);
+
         if info.status == "device" {
-            let (model, android_version, battery_level) = fetch_device_props(&info.serial).await;
-            info.model = model;
-            info.android_version = android_version;
-            info.battery_level = battery_level;
+            // Target this exact transport even if another device shares the serial
+            let target = match (target_for(&adb_devices, &info.serial), info.transport_id) {
+                (AdbTarget::TransportId(_), Some(id)) => AdbTarget::TransportId(id),
+                (target, _) => target,
+            };
+            fetch_device_props(adb, &target).await.apply_to(&mut info);
         }
 
         devices.push(info);
@@ -228,75 +676,158 @@ async fn list_adb_devices_internal() -> Result<Vec<DeviceInfo>, String> {
     Ok(devices)
 }
 
-// ─── Tauri commands ──────────────────────────────────────────────────────
-
-#[tauri::command]
-pub async fn list_devices(app: tauri::AppHandle) -> Result<Vec<DeviceInfo>, String> {
-    let app_data_dir = app
-        .path()
-        .app_data_dir()
-        .map_err(|e| format!("Failed to resolve app data dir: {}", e))?;
+/// Merge attached devices into the persistent registry and save it.
+/// Shared by `list_devices` and the background device tracker.
+pub(crate) async fn sync_registry(
+    adb: &SharedAdbExecutor,
+    app_data_dir: &Path,
+    adb_devices: &[AdbDevice],
+) -> AppResult<Vec<DeviceInfo>> {
+    let _guard = REGISTRY_LOCK.lock().await;
 
     // 1. Load persistent registry
-    let registry = load_registry(&app_data_dir);
+    let registry = load_registry(app_data_dir);
+
+    // 2. Three-way merge
+    let (mut devices, needs_props) = merge_devices(registry, adb_devices);
+
+    // 3. Fetch properties for devices that need them
+    let online = AdbDeviceState::Device.as_str();
+    for device in devices
+        .iter_mut()
+        .filter(|d| d.status == online && needs_props.contains(&d.serial))
+    {
+        // Phones sharing a serial are addressed by their own transport
+        let target = match (target_for(adb_devices, &device.serial), device.transport_id) {
+            (AdbTarget::TransportId(_), Some(id)) => AdbTarget::TransportId(id),
+            (target, _) => target,
+        };
+        fetch_device_props(adb, &target).await.apply_to(device);
+    }
 
-    // 2. Run adb devices
-    let output = Command::new("adb")
-        .arg("devices")
-        .stdout(Stdio::piped())
-        .output()
-        .await
-        .map_err(|e| format!("Failed to run adb devices: {}", e))?;
+    // 4. Save updated registry
+    if let Err(e) = save_registry(app_data_dir, &devices) {
+        eprintln!("Warning: failed to save device registry: {}", e);
+    }
 
-    if !output.status.success() {
-        return Err("adb devices command failed".to_string());
+    Ok(devices)
+}
+
+/// Link a USB serial and its wireless serial in the registry.
+/// Returns the updated wireless entry.
+pub(crate) async fn link_registry_entries(
+    app_data_dir: &Path,
+    usb_serial: &str,
+    wireless_serial: &str,
+) -> AppResult<DeviceInfo> {
+    let _guard = REGISTRY_LOCK.lock().await;
+    let mut registry = load_registry(app_data_dir);
+    link_devices(&mut registry, usb_serial, wireless_serial);
+    save_registry(app_data_dir, &registry)?;
+    registry
+        .into_iter()
+        .find(|d| d.serial == wireless_serial)
+        .ok_or_else(|| {
+            AppError::device_not_found(format!("Device {} is not registered", wireless_serial))
+        })
+}
+
+/// Move a registry entry to a new serial and save it.
+pub(crate) async fn rename_registry_entry(
+    app_data_dir: &Path,
+    old_serial: &str,
+    new_serial: &str,
+) -> AppResult<()> {
+    let _guard = REGISTRY_LOCK.lock().await;
+    let mut registry = load_registry(app_data_dir);
+    if !rename_device(&mut registry, old_serial, new_serial) {
+        return Err(AppError::device_not_found(format!(
+            "Device {} is not registered",
+            old_serial
+        )));
     }
+    save_registry(app_data_dir, &registry)
+}
 
-    let stdout =
-        String::from_utf8(output.stdout).map_err(|e| format!("Invalid UTF-8 output: {}", e))?;
+/// Apply an in-place update to one registry entry and save it.
+pub(crate) async fn update_registry_entry(
+    app_data_dir: &Path,
+    serial: &str,
+    update: impl FnOnce(&mut DeviceInfo),
+) -> AppResult<DeviceInfo> {
+    let _guard = REGISTRY_LOCK.lock().await;
+    let mut registry = load_registry(app_data_dir);
+    let device = registry
+        .iter_mut()
+        .find(|d| d.serial == serial)
+        .ok_or_else(|| {
+            AppError::device_not_found(format!("Device {} is not registered", serial))
+        })?;
+    update(device);
+    let updated = device.clone();
+    save_registry(app_data_dir, &registry)?;
+    Ok(updated)
+}
 
-    let adb_devices = parse_adb_output(&stdout);
+// ─── Tauri commands ──────────────────────────────────────────────────────
 
-    // 3. Three-way merge
-    let (mut devices, needs_props) = merge_devices(registry, &adb_devices);
+#[tauri::command]
+pub async fn list_devices(
+    app: tauri::AppHandle,
+    adb: State<'_, SharedAdbExecutor>,
+) -> AppResult<Vec<DeviceInfo>> {
+    let app_data_dir = app_data_dir(&app)?;
 
-    // 4. Fetch properties for devices that need them
-    for serial in &needs_props {
-        if let Some(device) = devices.iter_mut().find(|d| d.serial == *serial) {
-            let (model, android_version, battery_level) = fetch_device_props(serial).await;
-            device.model = model;
-            device.android_version = android_version;
-            device.battery_level = battery_level;
-        }
-    }
+    // Query attached devices
+    let adb_devices = query_adb_devices(&adb).await?;
 
-    // 5. Save updated registry
-    if let Err(e) = save_registry(&app_data_dir, &devices) {
-        eprintln!("Warning: failed to save device registry: {}", e);
-    }
+    sync_registry(&adb, &app_data_dir, &adb_devices).await
+}
 
-    Ok(devices)
+/// Registered devices grouped by physical phone
+#[tauri::command]
+pub async fn list_logical_devices(
+    app: tauri::AppHandle,
+    adb: State<'_, SharedAdbExecutor>,
+) -> AppResult<Vec<LogicalDevice>> {
+    let devices = list_devices(app, adb).await?;
+    Ok(group_devices(&devices))
 }
 
 #[tauri::command]
-pub async fn list_adb_devices() -> Result<Vec<DeviceInfo>, String> {
-    list_adb_devices_internal().await
+pub async fn list_adb_devices(adb: State<'_, SharedAdbExecutor>) -> AppResult<Vec<DeviceInfo>> {
+    list_adb_devices_internal(&adb).await
 }
 
 #[tauri::command]
-pub async fn register_device(serial: String, app: tauri::AppHandle) -> Result<DeviceInfo, String> {
-    let app_data_dir = app
-        .path()
-        .app_data_dir()
-        .map_err(|e| format!("Failed to resolve app data dir: {}", e))?;
+pub async fn register_device(
+    serial: String,
+    app: tauri::AppHandle,
+    adb: State<'_, SharedAdbExecutor>,
+) -> AppResult<DeviceInfo> {
+    let app_data_dir = app_data_dir(&app)?;
 
-    let mut registry = load_registry(&app_data_dir);
+    let device = register_device_internal(&adb, &serial, &app_data_dir).await?;
+    sync_polling(&app, &load_registry(&app_data_dir));
+    Ok(device)
+}
+
+/// Add a device to the registry (or refresh an existing entry).
+/// Shared by `register_device` and the wireless pairing flows.
+pub(crate) async fn register_device_internal(
+    adb: &SharedAdbExecutor,
+    serial: &str,
+    app_data_dir: &Path,
+) -> AppResult<DeviceInfo> {
+    let _guard = REGISTRY_LOCK.lock().await;
+    let mut registry = load_registry(app_data_dir);
 
     if let Some(index) = registry.iter().position(|d| d.serial == serial) {
         let mut existing = registry[index].clone();
-        if let Ok(adb_devices) = list_adb_devices_internal().await {
+        if let Ok(adb_devices) = list_adb_devices_internal(adb).await {
             if let Some(adb_device) = adb_devices.into_iter().find(|d| d.serial == serial) {
                 existing.status = adb_device.status;
+                existing.status_detail = adb_device.status_detail;
                 existing.last_seen = adb_device.last_seen;
                 existing.is_wireless = adb_device.is_wireless;
                 if existing.model.is_none() {
@@ -304,57 +835,76 @@ pub async fn register_device(serial: String, app: tauri::AppHandle) -> Result<De
                     existing.android_version = adb_device.android_version;
                     existing.battery_level = adb_device.battery_level;
                 }
+                if adb_device.hardware_serial.is_some() {
+                    existing.hardware_serial = adb_device.hardware_serial;
+                }
                 registry[index] = existing.clone();
-                save_registry(&app_data_dir, &registry)?;
+                save_registry(app_data_dir, &registry)?;
             }
         }
         return Ok(existing);
     }
 
-    let adb_devices = list_adb_devices_internal().await?;
+    let adb_devices = list_adb_devices_internal(adb).await?;
     let mut device = adb_devices
         .into_iter()
         .find(|d| d.serial == serial)
-        .ok_or_else(|| "Device not found in adb devices".to_string())?;
+        .ok_or_else(|| AppError::device_not_found("Device not found in adb devices"))?;
 
     let now = now_iso8601();
     device.first_seen = now.clone();
     device.last_seen = Some(now);
 
     registry.push(device.clone());
-    save_registry(&app_data_dir, &registry)?;
+    save_registry(app_data_dir, &registry)?;
     Ok(device)
 }
 
 #[tauri::command]
-pub async fn forget_device(serial: String, app: tauri::AppHandle) -> Result<(), String> {
-    let app_data_dir = app
-        .path()
-        .app_data_dir()
-        .map_err(|e| format!("Failed to resolve app data dir: {}", e))?;
+pub async fn forget_device(serial: String, app: tauri::AppHandle) -> AppResult<()> {
+    let app_data_dir = app_data_dir(&app)?;
 
+    let _guard = REGISTRY_LOCK.lock().await;
     let mut devices = load_registry(&app_data_dir);
     devices.retain(|d| d.serial != serial);
     save_registry(&app_data_dir, &devices)?;
+    sync_polling(&app, &devices);
     Ok(())
 }
 
+/// Start the background device tracker (event-driven alternative to
+/// polling `list_devices`)
 #[tauri::command]
-pub async fn test_device(serial: String) -> Result<(), String> {
-    let output = Command::new("adb")
-        .args(["-s", &serial, "shell", "echo", "test"])
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .output()
-        .await
-        .map_err(|e| format!("Failed to run adb test: {}", e))?;
+pub async fn start_device_tracking(
+    device_tracker: State<'_, Mutex<DeviceTracker>>,
+) -> AppResult<()> {
+    let mut tracker = device_tracker.lock().map_err(AppError::lock)?;
+    tracker.start_tracking()
+}
 
-    if output.status.success() {
-        Ok(())
-    } else {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        Err(format!("Device test failed: {}", stderr))
-    }
+/// Stop the background device tracker
+#[tauri::command]
+pub async fn stop_device_tracking(
+    device_tracker: State<'_, Mutex<DeviceTracker>>,
+) -> AppResult<()> {
+    let mut tracker = device_tracker.lock().map_err(AppError::lock)?;
+    tracker.stop_tracking()
+}
+
+#[tauri::command]
+pub async fn test_device(
+    serial: String,
+    transport_id: Option<u32>,
+    adb: State<'_, SharedAdbExecutor>,
+) -> AppResult<()> {
+    let target = match transport_id {
+        Some(id) => AdbTarget::TransportId(id),
+        None => AdbTarget::Serial(serial),
+    };
+    adb_shell(&adb, &target, "echo test")
+        .await
+        .map(|_| ())
+        .map_err(|e| e.context("Device test failed"))
 }
 
 #[cfg(test)]
@@ -372,6 +922,14 @@ mod tests {
             is_wireless: false,
             last_seen: Some("2026-01-15T10:30:00+00:00".to_string()),
             first_seen: "2026-01-10T08:00:00+00:00".to_string(),
+            linked_serial: None,
+            auto_reconnect: false,
+            hardware_serial: None,
+            usb_path: None,
+            product: None,
+            device_codename: None,
+            transport_id: None,
+            status_detail: None,
         };
         let json = serde_json::to_value(&info).unwrap();
         assert_eq!(json["serial"], "abc123");
@@ -395,6 +953,14 @@ mod tests {
             is_wireless: true,
             last_seen: None,
             first_seen: "2026-01-10T08:00:00+00:00".to_string(),
+            linked_serial: None,
+            auto_reconnect: false,
+            hardware_serial: None,
+            usb_path: None,
+            product: None,
+            device_codename: None,
+            transport_id: None,
+            status_detail: None,
         };
         let json = serde_json::to_value(&info).unwrap();
         assert_eq!(json["serial"], "192.168.1.100:5555");
@@ -422,10 +988,27 @@ mod tests {
         let devices = parse_adb_output(output);
         assert_eq!(devices.len(), 2);
         assert_eq!(devices[0].serial, "abc123");
-        assert_eq!(devices[0].status, "device");
+        assert_eq!(devices[0].state, AdbDeviceState::Device);
         assert_eq!(devices[1].serial, "192.168.1.5:5555");
     }
 
+    #[test]
+    fn parse_adb_output_parses_server_payload_without_header() {
+        let output = "abc123 device usb:1-2 product:oriole model:Pixel_6 transport_id:1\n";
+        let devices = parse_adb_output(output);
+        assert_eq!(devices.len(), 1);
+        assert_eq!(devices[0].serial, "abc123");
+        assert_eq!(devices[0].state, AdbDeviceState::Device);
+    }
+
+    #[test]
+    fn parse_adb_output_skips_daemon_banner() {
+        let output = "* daemon not running; starting now at tcp:5037\n* daemon started successfully\nList of devices attached\nabc123\tdevice\n";
+        let devices = parse_adb_output(output);
+        assert_eq!(devices.len(), 1);
+        assert_eq!(devices[0].serial, "abc123");
+    }
+
     #[test]
     fn parse_adb_output_handles_empty() {
         let output = "List of devices attached\n\n";
@@ -433,6 +1016,155 @@ mod tests {
         assert_eq!(devices.len(), 0);
     }
 
+    #[test]
+    fn parse_adb_output_parses_long_fields() {
+        let output = "List of devices attached\n\
+            R58M123          device usb:1-2.3 product:a52qnsxx model:SM_A525F device:a52q transport_id:4\n\
+            192.168.1.5:5555 device product:oriole model:Pixel_6 device:oriole transport_id:7\n";
+        let devices = parse_adb_output(output);
+        assert_eq!(devices.len(), 2);
+        assert_eq!(
+            devices[0],
+            AdbDevice {
+                serial: "R58M123".to_string(),
+                state: AdbDeviceState::Device,
+                state_detail: None,
+                usb: Some("1-2.3".to_string()),
+                product: Some("a52qnsxx".to_string()),
+                model: Some("SM_A525F".to_string()),
+                device: Some("a52q".to_string()),
+                transport_id: Some(4),
+            }
+        );
+        assert_eq!(devices[1].usb, None);
+        assert_eq!(devices[1].transport_id, Some(7));
+        assert_eq!(devices[1].display_model().as_deref(), Some("Pixel 6"));
+    }
+
+    #[test]
+    fn parse_adb_output_keeps_multi_word_state() {
+        let output = "0123456789ABCDEF\tno permissions (user in plugdev group; are your udev rules wrong?); see [http://developer.android.com/tools/device.html] usb:1-4 transport_id:2\n";
+        let devices = parse_adb_output(output);
+        assert_eq!(devices.len(), 1);
+        assert_eq!(devices[0].state, AdbDeviceState::NoPermissions);
+        assert_eq!(
+            devices[0].state_detail.as_deref(),
+            Some("user in plugdev group; are your udev rules wrong?")
+        );
+        assert_eq!(devices[0].status(), "no_permissions");
+        assert_eq!(devices[0].usb.as_deref(), Some("1-4"));
+        assert_eq!(devices[0].transport_id, Some(2));
+    }
+
+    #[test]
+    fn device_state_parses_known_states() {
+        assert_eq!(
+            AdbDeviceState::parse("device"),
+            (AdbDeviceState::Device, None)
+        );
+        assert_eq!(
+            AdbDeviceState::parse("unauthorized"),
+            (AdbDeviceState::Unauthorized, None)
+        );
+        assert_eq!(
+            AdbDeviceState::parse("recovery"),
+            (AdbDeviceState::Recovery, None)
+        );
+        assert_eq!(
+            AdbDeviceState::parse(
+                "no permissions; see [http://developer.android.com/tools/device.html]"
+            ),
+            (AdbDeviceState::NoPermissions, None)
+        );
+        assert_eq!(
+            AdbDeviceState::parse("weird state"),
+            (AdbDeviceState::Unknown, Some("weird state".to_string()))
+        );
+    }
+
+    #[test]
+    fn merge_records_no_permissions_detail() {
+        let adb = parse_adb_output(
+            "0123456789ABCDEF\tno permissions (missing udev rules? user is in the plugdev group); see [http://developer.android.com/tools/device.html]\n",
+        );
+        let (devices, needs_props) = merge_devices(Vec::new(), &adb);
+        assert_eq!(devices[0].status, "no_permissions");
+        assert_eq!(
+            devices[0].status_detail.as_deref(),
+            Some("missing udev rules? user is in the plugdev group")
+        );
+        assert!(needs_props.is_empty());
+    }
+
+    #[test]
+    fn parse_adb_output_ignores_bad_transport_id() {
+        let devices = parse_adb_output("abc123 device transport_id:abc\n");
+        assert_eq!(devices[0].transport_id, None);
+    }
+
+    #[test]
+    fn connection_type_prefers_usb_field() {
+        let devices = parse_adb_output(
+            "List of devices attached\n\
+             R58M123 device usb:1-2 transport_id:1\n\
+             192.168.1.5:5555 device transport_id:2\n\
+             adb-R58M123-AbCdEf._adb-tls-connect._tcp device transport_id:3\n\
+             emulator-5554 device transport_id:4\n",
+        );
+        let types: Vec<ConnectionType> = devices.iter().map(|d| d.connection_type()).collect();
+        assert_eq!(
+            types,
+            vec![
+                ConnectionType::Usb,
+                ConnectionType::Wireless,
+                ConnectionType::Wireless,
+                ConnectionType::Usb,
+            ]
+        );
+    }
+
+    #[test]
+    fn target_for_uses_transport_id_only_when_ambiguous() {
+        let device = |serial: &str, id: u32| AdbDevice {
+            serial: serial.to_string(),
+            state: AdbDeviceState::Device,
+            transport_id: Some(id),
+            ..Default::default()
+        };
+        let unique = vec![device("abc123", 1), device("def456", 2)];
+        assert_eq!(
+            target_for(&unique, "abc123"),
+            AdbTarget::Serial("abc123".to_string())
+        );
+
+        let duplicated = vec![device("0123456789ABCDEF", 3), device("0123456789ABCDEF", 5)];
+        assert_eq!(
+            target_for(&duplicated, "0123456789ABCDEF"),
+            AdbTarget::TransportId(3)
+        );
+    }
+
+    #[test]
+    fn merge_copies_long_fields_and_model() {
+        let adb = vec![AdbDevice {
+            serial: "abc123".to_string(),
+            state: AdbDeviceState::Device,
+            usb: Some("1-2".to_string()),
+            model: Some("Pixel_6".to_string()),
+            transport_id: Some(9),
+            ..Default::default()
+        }];
+        let (devices, _) = merge_devices(Vec::new(), &adb);
+        assert_eq!(devices[0].usb_path.as_deref(), Some("1-2"));
+        assert_eq!(devices[0].model.as_deref(), Some("Pixel 6"));
+        assert_eq!(devices[0].transport_id, Some(9));
+
+        // Transport ids are dropped once the device is gone
+        let (devices, _) = merge_devices(devices, &[]);
+        assert_eq!(devices[0].transport_id, None);
+        assert_eq!(devices[0].usb_path.as_deref(), Some("1-2"));
+    }
+
     // ─── merge_devices tests ──────────────────────────────────────────
 
     #[test]
@@ -440,7 +1172,8 @@ mod tests {
         let registry = Vec::new();
         let adb = vec![AdbDevice {
             serial: "abc123".to_string(),
-            status: "device".to_string(),
+            state: AdbDeviceState::Device,
+            ..Default::default()
         }];
         let (devices, needs_props) = merge_devices(registry, &adb);
         assert_eq!(devices.len(), 1);
@@ -451,6 +1184,34 @@ mod tests {
         assert!(needs_props.contains(&"abc123".to_string()));
     }
 
+    #[test]
+    fn merge_flags_ip_and_mdns_serials_as_wireless() {
+        let adb = parse_adb_output(
+            "List of devices attached\n\
+             R58M123 device usb:1-2 transport_id:1\n\
+             192.168.1.5:5555 device transport_id:2\n\
+             adb-R58M123-AbCdEf._adb-tls-connect._tcp device transport_id:3\n",
+        );
+        let (devices, _) = merge_devices(Vec::new(), &adb);
+        let wireless: Vec<(&str, bool)> = devices
+            .iter()
+            .map(|d| (d.serial.as_str(), d.is_wireless))
+            .collect();
+        assert_eq!(
+            wireless,
+            vec![
+                ("R58M123", false),
+                ("192.168.1.5:5555", true),
+                ("adb-R58M123-AbCdEf._adb-tls-connect._tcp", true),
+            ]
+        );
+
+        let mut stale = transport("adb-R58M123-AbCdEf._adb-tls-connect._tcp", "device", None);
+        stale.is_wireless = false;
+        let (devices, _) = merge_devices(vec![stale], &adb[2..]);
+        assert!(devices[0].is_wireless);
+    }
+
     #[test]
     fn merge_marks_missing_device_as_disconnected() {
         let registry = vec![DeviceInfo {
@@ -462,6 +1223,14 @@ mod tests {
             is_wireless: false,
             last_seen: Some("2026-01-10T08:00:00+00:00".to_string()),
             first_seen: "2026-01-01T00:00:00+00:00".to_string(),
+            linked_serial: None,
+            auto_reconnect: false,
+            hardware_serial: None,
+            usb_path: None,
+            product: None,
+            device_codename: None,
+            transport_id: None,
+            status_detail: None,
         }];
         let adb: Vec<AdbDevice> = Vec::new();
         let (devices, needs_props) = merge_devices(registry, &adb);
@@ -485,10 +1254,19 @@ mod tests {
             is_wireless: false,
             last_seen: Some("2026-01-10T08:00:00+00:00".to_string()),
             first_seen: "2026-01-01T00:00:00+00:00".to_string(),
+            linked_serial: None,
+            auto_reconnect: false,
+            hardware_serial: None,
+            usb_path: None,
+            product: None,
+            device_codename: None,
+            transport_id: None,
+            status_detail: None,
         }];
         let adb = vec![AdbDevice {
             serial: "abc123".to_string(),
-            status: "device".to_string(),
+            state: AdbDeviceState::Device,
+            ..Default::default()
         }];
         let (devices, needs_props) = merge_devices(registry, &adb);
         assert_eq!(devices.len(), 1);
@@ -510,6 +1288,14 @@ mod tests {
             is_wireless: false,
             last_seen: Some("2026-01-10T08:00:00+00:00".to_string()),
             first_seen: "2026-01-01T00:00:00+00:00".to_string(),
+            linked_serial: None,
+            auto_reconnect: false,
+            hardware_serial: None,
+            usb_path: None,
+            product: None,
+            device_codename: None,
+            transport_id: None,
+            status_detail: None,
         }];
         let adb: Vec<AdbDevice> = Vec::new();
         let (devices, _) = merge_devices(registry, &adb);
@@ -529,7 +1315,8 @@ mod tests {
         let registry = Vec::new();
         let adb = vec![AdbDevice {
             serial: "abc456".to_string(),
-            status: "unauthorized".to_string(),
+            state: AdbDeviceState::Unauthorized,
+            ..Default::default()
         }];
         let (devices, needs_props) = merge_devices(registry, &adb);
         assert_eq!(devices.len(), 1);
@@ -540,6 +1327,275 @@ mod tests {
 
     // ─── load_registry / save_registry tests ──────────────────────────
 
+    #[test]
+    fn link_devices_links_both_entries() {
+        let mut registry = vec![
+            DeviceInfo {
+                serial: "abc123".to_string(),
+                status: "device".to_string(),
+                model: Some("Pixel 7".to_string()),
+                android_version: None,
+                battery_level: None,
+                is_wireless: false,
+                last_seen: None,
+                first_seen: "2026-01-01T00:00:00+00:00".to_string(),
+                linked_serial: None,
+                auto_reconnect: false,
+                hardware_serial: None,
+                usb_path: None,
+                product: None,
+                device_codename: None,
+                transport_id: None,
+                status_detail: None,
+            },
+            DeviceInfo {
+                serial: "192.168.1.42:5555".to_string(),
+                status: "device".to_string(),
+                model: Some("Pixel 7".to_string()),
+                android_version: None,
+                battery_level: None,
+                is_wireless: true,
+                last_seen: None,
+                first_seen: "2026-01-01T00:00:00+00:00".to_string(),
+                linked_serial: None,
+                auto_reconnect: false,
+                hardware_serial: None,
+                usb_path: None,
+                product: None,
+                device_codename: None,
+                transport_id: None,
+                status_detail: None,
+            },
+        ];
+        link_devices(&mut registry, "abc123", "192.168.1.42:5555");
+        assert_eq!(
+            registry[0].linked_serial.as_deref(),
+            Some("192.168.1.42:5555")
+        );
+        assert_eq!(registry[1].linked_serial.as_deref(), Some("abc123"));
+    }
+
+    #[test]
+    fn rename_device_keeps_settings_and_links() {
+        let mut registry = vec![
+            DeviceInfo {
+                linked_serial: Some("192.168.1.5:40000".to_string()),
+                ..transport("R58M123", "device", None)
+            },
+            DeviceInfo {
+                auto_reconnect: true,
+                linked_serial: Some("R58M123".to_string()),
+                ..transport("192.168.1.5:40000", "disconnected", None)
+            },
+            // Registered meanwhile by a device list refresh
+            transport("192.168.1.5:41234", "device", None),
+        ];
+
+        assert!(rename_device(
+            &mut registry,
+            "192.168.1.5:40000",
+            "192.168.1.5:41234"
+        ));
+        assert_eq!(registry.len(), 2);
+        assert_eq!(
+            registry[0].linked_serial.as_deref(),
+            Some("192.168.1.5:41234")
+        );
+        assert_eq!(registry[1].serial, "192.168.1.5:41234");
+        assert!(registry[1].auto_reconnect);
+
+        assert!(!rename_device(&mut registry, "unknown", "10.0.0.2:5555"));
+        assert_eq!(registry.len(), 2);
+    }
+
+    #[test]
+    fn registry_from_older_version_still_loads() {
+        let json = r#"[{"serial":"abc123","status":"device","model":null,"android_version":null,"battery_level":null,"is_wireless":false,"last_seen":null,"first_seen":"2026-01-01T00:00:00+00:00"}]"#;
+        let devices: Vec<DeviceInfo> = serde_json::from_str(json).unwrap();
+        assert!(devices[0].linked_serial.is_none());
+        assert!(!devices[0].auto_reconnect);
+    }
+
+    // ─── Logical device tests ──────────────────────────────────────────
+
+    fn transport(serial: &str, status: &str, hardware_serial: Option<&str>) -> DeviceInfo {
+        DeviceInfo {
+            serial: serial.to_string(),
+            status: status.to_string(),
+            model: Some("Pixel 7".to_string()),
+            android_version: Some("14".to_string()),
+            battery_level: None,
+            is_wireless: ConnectionType::from_serial(serial) == ConnectionType::Wireless,
+            last_seen: None,
+            first_seen: "2026-01-01T00:00:00+00:00".to_string(),
+            linked_serial: None,
+            auto_reconnect: false,
+            hardware_serial: hardware_serial.map(|s| s.to_string()),
+            usb_path: None,
+            product: None,
+            device_codename: None,
+            transport_id: None,
+            status_detail: None,
+        }
+    }
+
+    #[test]
+    fn merge_requests_props_when_hardware_serial_unknown() {
+        let registry = vec![transport("abc123", "device", None)];
+        let adb = vec![AdbDevice {
+            serial: "abc123".to_string(),
+            state: AdbDeviceState::Device,
+            ..Default::default()
+        }];
+        let (_, needs_props) = merge_devices(registry, &adb);
+        assert_eq!(needs_props, vec!["abc123".to_string()]);
+    }
+
+    #[test]
+    fn merge_keeps_phones_sharing_a_serial_apart() {
+        let row = |usb: &str, model: &str, id: u32| AdbDevice {
+            serial: "R58M123".to_string(),
+            state: AdbDeviceState::Device,
+            usb: Some(usb.to_string()),
+            model: Some(model.to_string()),
+            transport_id: Some(id),
+            ..Default::default()
+        };
+        let registry = vec![DeviceInfo {
+            usb_path: Some("1-3".to_string()),
+            model: None,
+            ..transport("R58M123", "disconnected", None)
+        }];
+        let adb = vec![row("1-2", "SM_A525F", 4), row("1-3", "SM_G991B", 5)];
+
+        let (devices, _) = merge_devices(registry, &adb);
+        assert_eq!(devices.len(), 2);
+        // The known phone keeps its own row even though it's listed second
+        assert_eq!(devices[0].usb_path.as_deref(), Some("1-3"));
+        assert_eq!(devices[0].transport_id, Some(5));
+        assert_eq!(devices[0].model.as_deref(), Some("SM G991B"));
+        assert_eq!(devices[1].usb_path.as_deref(), Some("1-2"));
+        assert_eq!(devices[1].transport_id, Some(4));
+        assert_eq!(devices[1].model.as_deref(), Some("SM A525F"));
+
+        // Unplugging one phone only disconnects its own entry
+        let (devices, _) = merge_devices(devices, &adb[..1]);
+        assert_eq!(devices[0].status, "disconnected");
+        assert_eq!(devices[0].transport_id, None);
+        assert_eq!(devices[1].status, "device");
+        assert_eq!(devices[1].transport_id, Some(4));
+    }
+
+    #[test]
+    fn group_devices_by_hardware_serial() {
+        let devices = vec![
+            transport("R58M123", "device", Some("R58M123")),
+            transport("192.168.1.5:5555", "device", Some("R58M123")),
+            transport("other456", "device", Some("other456")),
+        ];
+        let groups = group_devices(&devices);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].id, "R58M123");
+        assert_eq!(groups[0].transports.len(), 2);
+        assert_eq!(groups[1].id, "other456");
+    }
+
+    #[test]
+    fn group_devices_follows_linked_serial() {
+        let mut wireless = transport("192.168.1.5:5555", "device", None);
+        wireless.linked_serial = Some("abc123".to_string());
+        let devices = vec![transport("abc123", "disconnected", None), wireless];
+        let groups = group_devices(&devices);
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].id, "abc123");
+        assert_eq!(
+            groups[0].preferred_serial.as_deref(),
+            Some("192.168.1.5:5555")
+        );
+    }
+
+    #[test]
+    fn best_transport_prefers_online_usb() {
+        let devices = vec![
+            transport("192.168.1.5:5555", "device", Some("R58M123")),
+            transport("R58M123", "device", Some("R58M123")),
+        ];
+        assert_eq!(best_transport(&devices).unwrap().serial, "R58M123");
+
+        let offline = vec![
+            transport("192.168.1.5:5555", "offline", Some("R58M123")),
+            transport("R58M123", "disconnected", Some("R58M123")),
+        ];
+        assert!(best_transport(&offline).is_none());
+    }
+
+    #[test]
+    fn resolve_transport_accepts_any_member_serial() {
+        let devices = vec![
+            transport("R58M123", "disconnected", Some("R58M123")),
+            transport("192.168.1.5:5555", "device", Some("R58M123")),
+        ];
+        assert_eq!(
+            resolve_transport(&devices, "R58M123").as_deref(),
+            Some("192.168.1.5:5555")
+        );
+        assert_eq!(resolve_transport(&devices, "unknown"), None);
+    }
+
+    fn scripted_adb() -> crate::services::ScriptedExecutor {
+        let adb = crate::services::ScriptedExecutor::new();
+        adb.respond_devices(
+            "R58M123 device usb:1-2 model:SM_A525F transport_id:4\n\
+             emulator-5554 unauthorized transport_id:5\n",
+        )
+        .respond_shell("getprop ro.product.model", "Galaxy A52\n")
+        .respond_shell("getprop ro.build.version.release", "14\n")
+        .respond_shell("dumpsys battery", "  level: 64\n")
+        .respond_shell("getprop ro.serialno", "R58M123\n");
+        adb
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn list_adb_devices_queries_props_of_online_devices() {
+        use crate::services::adb_executor::AdbRequest;
+
+        let adb = scripted_adb();
+        let shared: SharedAdbExecutor = std::sync::Arc::new(adb.clone());
+        let devices = list_adb_devices_internal(&shared).await.unwrap();
+
+        assert_eq!(devices.len(), 2);
+        assert_eq!(devices[0].model.as_deref(), Some("Galaxy A52"));
+        assert_eq!(devices[0].android_version.as_deref(), Some("14"));
+        assert_eq!(devices[0].battery_level, Some(64));
+        assert_eq!(devices[0].hardware_serial.as_deref(), Some("R58M123"));
+        assert_eq!(devices[1].status, "unauthorized");
+        assert_eq!(devices[1].model, None);
+
+        // Unauthorized devices are never queried
+        assert!(!adb.calls().iter().any(|call| matches!(
+            call,
+            AdbRequest::Shell { target: AdbTarget::Serial(serial), .. } if serial == "emulator-5554"
+        )));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn register_device_adds_attached_device_to_registry() {
+        let dir = std::env::temp_dir().join("scrcpy-test-register-scripted");
+        let _ = fs::remove_dir_all(&dir);
+        let shared: SharedAdbExecutor = std::sync::Arc::new(scripted_adb());
+
+        let device = register_device_internal(&shared, "R58M123", &dir)
+            .await
+            .unwrap();
+        assert_eq!(device.status, "device");
+        assert_eq!(load_registry(&dir).len(), 1);
+        assert!(register_device_internal(&shared, "missing", &dir)
+            .await
+            .is_err());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
     #[test]
     fn load_registry_returns_empty_for_missing_file() {
         let dir = std::env::temp_dir().join("scrcpy-test-missing");
@@ -573,6 +1629,14 @@ mod tests {
                 is_wireless: false,
                 last_seen: Some("2026-01-15T10:30:00+00:00".to_string()),
                 first_seen: "2026-01-10T08:00:00+00:00".to_string(),
+                linked_serial: None,
+                auto_reconnect: false,
+                hardware_serial: None,
+                usb_path: None,
+                product: None,
+                device_codename: None,
+                transport_id: None,
+                status_detail: None,
             },
             DeviceInfo {
                 serial: "192.168.1.5:5555".to_string(),
@@ -583,6 +1647,14 @@ mod tests {
                 is_wireless: true,
                 last_seen: None,
                 first_seen: "2026-01-12T09:00:00+00:00".to_string(),
+                linked_serial: None,
+                auto_reconnect: false,
+                hardware_serial: None,
+                usb_path: None,
+                product: None,
+                device_codename: None,
+                transport_id: None,
+                status_detail: None,
             },
         ];
 
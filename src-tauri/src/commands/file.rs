@@ -1,7 +1,8 @@
+use crate::error::{AppError, AppResult};
 use std::fs;
 
 #[tauri::command]
-pub async fn select_save_file() -> Result<Option<String>, String> {
+pub async fn select_save_file() -> AppResult<Option<String>> {
     let file_path = rfd::FileDialog::new()
         .set_title("Select Recording Save Location")
         .add_filter("MP4 Video", &["mp4"])
@@ -17,7 +18,7 @@ pub async fn select_save_file() -> Result<Option<String>, String> {
 }
 
 #[tauri::command]
-pub async fn export_presets(content: String) -> Result<bool, String> {
+pub async fn export_presets(content: String) -> AppResult<bool> {
     let file_path = rfd::FileDialog::new()
         .set_title("Export Presets")
         .add_filter("JSON Files", &["json"])
@@ -26,7 +27,7 @@ pub async fn export_presets(content: String) -> Result<bool, String> {
 
     match file_path {
         Some(path) => {
-            fs::write(&path, content).map_err(|e| format!("Failed to write file: {}", e))?;
+            fs::write(&path, content).map_err(|e| AppError::io("Failed to write file", e))?;
             Ok(true)
         }
         None => Ok(false), // User cancelled
@@ -34,7 +35,7 @@ pub async fn export_presets(content: String) -> Result<bool, String> {
 }
 
 #[tauri::command]
-pub async fn import_presets() -> Result<Option<String>, String> {
+pub async fn import_presets() -> AppResult<Option<String>> {
     let file_path = rfd::FileDialog::new()
         .set_title("Import Presets")
         .add_filter("JSON Files", &["json"])
@@ -43,7 +44,7 @@ pub async fn import_presets() -> Result<Option<String>, String> {
     match file_path {
         Some(path) => {
             let content = fs::read_to_string(&path)
-                .map_err(|e| format!("Failed to read file: {}", e))?;
+                .map_err(|e| AppError::io("Failed to read file", e))?;
             Ok(Some(content))
         }
         None => Ok(None), // User cancelled
@@ -1,8 +1,18 @@
+use crate::commands::device::{load_registry, parse_adb_output};
+use crate::error::{AppError, AppResult};
+use crate::services::adb_client::run_blocking;
+use crate::services::adb_executor::SharedAdbExecutor;
+use crate::services::health_history;
+use crate::services::history_store::{ExportFilter, ExportFormat, RetentionPolicy};
 use crate::services::HealthPollingService;
-use crate::types::health::{DeviceHealth, HealthPollingConfig};
+use crate::types::health::{
+    AlertRule, ConnectionType, DeviceHealth, HealthHistoryResponse, HealthPollingConfig,
+    HealthSample, HealthTrendsResponse, ReconnectionState, StartHealthPollingRequest,
+    StartPollingDevice,
+};
 use serde::{Deserialize, Serialize};
 use std::sync::Mutex;
-use tauri::State;
+use tauri::{AppHandle, Manager, State};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CommandResultResponse {
@@ -17,44 +27,80 @@ pub struct GetDeviceHealthResponse {
 }
 
 /// Start health polling for the given devices
+///
+/// `request.devices` carry a connection type each; bare `request.deviceIds`
+/// get theirs detected from the attached transports.
 #[tauri::command]
 pub async fn start_health_polling(
-    device_ids: Vec<String>,
-    config: Option<HealthPollingConfig>,
+    request: StartHealthPollingRequest,
     polling_service: State<'_, Mutex<HealthPollingService>>,
-) -> Result<CommandResultResponse, String> {
-    if device_ids.is_empty() {
-        return Err("No device IDs provided".to_string());
+    adb: State<'_, SharedAdbExecutor>,
+) -> AppResult<CommandResultResponse> {
+    let StartHealthPollingRequest {
+        config,
+        devices,
+        device_ids,
+    } = request;
+    let devices = resolve_polling_devices(&adb, device_ids, devices).await;
+    if devices.is_empty() {
+        return Err(AppError::invalid_input("No device IDs provided"));
     }
 
-    let config = config.unwrap_or_default();
-
     // Validate config
-    config.validate()?;
+    config.validate().map_err(AppError::invalid_input)?;
 
     // Start polling
-    let mut service = polling_service
-        .lock()
-        .map_err(|e| format!("Lock error: {}", e))?;
-    service.start_polling(device_ids.clone(), config)?;
+    let count = devices.len();
+    let mut service = polling_service.lock().map_err(AppError::lock)?;
+    service.start_polling(devices, config)?;
 
     Ok(CommandResultResponse {
         success: true,
-        message: Some(format!(
-            "Started polling for {} device(s)",
-            device_ids.len()
-        )),
+        message: Some(format!("Started polling for {} device(s)", count)),
     })
 }
 
+/// Merge explicit devices with bare IDs, detecting the transport of the latter
+async fn resolve_polling_devices(
+    adb: &SharedAdbExecutor,
+    device_ids: Vec<String>,
+    mut devices: Vec<StartPollingDevice>,
+) -> Vec<StartPollingDevice> {
+    let device_ids: Vec<String> = device_ids
+        .into_iter()
+        .filter(|id| !devices.iter().any(|d| d.device_id == *id))
+        .collect();
+    if device_ids.is_empty() {
+        return devices;
+    }
+
+    // Without a device list, fall back to judging by serial
+    let executor = adb.clone();
+    let attached = run_blocking(move || executor.devices().map_err(|e| e.to_string()))
+        .await
+        .map(|output| parse_adb_output(&output))
+        .unwrap_or_default();
+
+    for device_id in device_ids {
+        let connection_type = attached
+            .iter()
+            .find(|d| d.serial == device_id)
+            .map(|d| d.connection_type())
+            .unwrap_or_else(|| ConnectionType::from_serial(&device_id));
+        devices.push(StartPollingDevice {
+            device_id,
+            connection_type,
+        });
+    }
+    devices
+}
+
 /// Stop health polling
 #[tauri::command]
 pub async fn stop_health_polling(
     polling_service: State<'_, Mutex<HealthPollingService>>,
-) -> Result<CommandResultResponse, String> {
-    let mut service = polling_service
-        .lock()
-        .map_err(|e| format!("Lock error: {}", e))?;
+) -> AppResult<CommandResultResponse> {
+    let mut service = polling_service.lock().map_err(AppError::lock)?;
     service.stop_polling()?;
 
     Ok(CommandResultResponse {
@@ -63,17 +109,296 @@ pub async fn stop_health_polling(
     })
 }
 
+/// Start polling one more device while polling is running
+///
+/// The connection type is detected like in `start_health_polling` when not
+/// given.
+#[tauri::command]
+pub async fn add_polled_device(
+    device_id: String,
+    connection_type: Option<ConnectionType>,
+    polling_service: State<'_, Mutex<HealthPollingService>>,
+    adb: State<'_, SharedAdbExecutor>,
+) -> AppResult<CommandResultResponse> {
+    let devices = match connection_type {
+        Some(connection_type) => vec![StartPollingDevice {
+            device_id: device_id.clone(),
+            connection_type,
+        }],
+        None => resolve_polling_devices(&adb, vec![device_id.clone()], Vec::new()).await,
+    };
+
+    let mut service = polling_service.lock().map_err(AppError::lock)?;
+    let mut added = false;
+    for device in devices {
+        added |= service.add_device(device);
+    }
+
+    Ok(CommandResultResponse {
+        success: true,
+        message: Some(if added {
+            format!("Started polling {}", device_id)
+        } else {
+            format!("{} is already polled", device_id)
+        }),
+    })
+}
+
+/// Stop polling one device; its cached health is kept and marked Offline
+#[tauri::command]
+pub async fn remove_polled_device(
+    device_id: String,
+    polling_service: State<'_, Mutex<HealthPollingService>>,
+) -> AppResult<CommandResultResponse> {
+    let mut service = polling_service.lock().map_err(AppError::lock)?;
+    if !service.remove_device(&device_id) {
+        return Err(AppError::device_not_found(format!(
+            "{} is not being polled",
+            device_id
+        )));
+    }
+
+    Ok(CommandResultResponse {
+        success: true,
+        message: Some(format!("Stopped polling {}", device_id)),
+    })
+}
+
+/// Replace the polling config without restarting; each device picks it up
+/// on its next cycle
+#[tauri::command]
+pub async fn update_polling_config(
+    config: HealthPollingConfig,
+    polling_service: State<'_, Mutex<HealthPollingService>>,
+) -> AppResult<CommandResultResponse> {
+    let service = polling_service.lock().map_err(AppError::lock)?;
+    service.update_config(config)?;
+
+    Ok(CommandResultResponse {
+        success: true,
+        message: Some("Polling config updated".to_string()),
+    })
+}
+
+/// Have the poller follow the device registry: online devices are polled
+/// automatically and dropped again once they disconnect
+#[tauri::command]
+pub async fn set_polling_follow_registry(
+    enabled: bool,
+    app: AppHandle,
+    polling_service: State<'_, Mutex<HealthPollingService>>,
+) -> AppResult<CommandResultResponse> {
+    let registry = app
+        .path()
+        .app_data_dir()
+        .map(|dir| load_registry(&dir))
+        .unwrap_or_default();
+
+    let mut service = polling_service.lock().map_err(AppError::lock)?;
+    service.set_follow_registry(enabled, &registry);
+
+    Ok(CommandResultResponse {
+        success: true,
+        message: Some(format!(
+            "Following the device registry {}",
+            if enabled { "enabled" } else { "disabled" }
+        )),
+    })
+}
+
+/// Retry/backoff state of polled devices whose polls are failing, optionally
+/// for one device only
+#[tauri::command]
+pub async fn get_polling_retry_states(
+    device_id: Option<String>,
+    polling_service: State<'_, Mutex<HealthPollingService>>,
+) -> AppResult<Vec<ReconnectionState>> {
+    let service = polling_service.lock().map_err(AppError::lock)?;
+    let mut states = service.get_retry_states();
+    if let Some(device_id) = device_id {
+        states.retain(|state| state.device_id == device_id);
+    }
+    Ok(states)
+}
+
+/// Recorded health samples of a device between `from` and `to` (Unix ms,
+/// inclusive, open-ended when omitted), averaged down to at most
+/// `max_points` for charting
+#[tauri::command]
+pub async fn get_health_history(
+    device_id: String,
+    from: Option<u64>,
+    to: Option<u64>,
+    max_points: Option<usize>,
+    polling_service: State<'_, Mutex<HealthPollingService>>,
+) -> AppResult<HealthHistoryResponse> {
+    if max_points == Some(0) {
+        return Err(AppError::invalid_input("max_points must be >= 1"));
+    }
+    let samples = history_range(&device_id, from, to, &polling_service)?;
+
+    Ok(HealthHistoryResponse {
+        total_samples: samples.len(),
+        samples: match max_points {
+            Some(max_points) => health_history::downsample(&samples, max_points),
+            None => samples,
+        },
+        device_id,
+    })
+}
+
+/// Min/max/avg of each metric and the battery drain rate (%/hour while not
+/// charging) over the same range as `get_health_history`
+#[tauri::command]
+pub async fn get_health_trends(
+    device_id: String,
+    from: Option<u64>,
+    to: Option<u64>,
+    polling_service: State<'_, Mutex<HealthPollingService>>,
+) -> AppResult<HealthTrendsResponse> {
+    let samples = history_range(&device_id, from, to, &polling_service)?;
+    Ok(health_history::trends(&device_id, &samples))
+}
+
+fn history_range(
+    device_id: &str,
+    from: Option<u64>,
+    to: Option<u64>,
+    polling_service: &Mutex<HealthPollingService>,
+) -> AppResult<Vec<HealthSample>> {
+    if let (Some(from), Some(to)) = (from, to) {
+        if from > to {
+            return Err(AppError::invalid_input("from must not be after to"));
+        }
+    }
+    let service = polling_service.lock().map_err(AppError::lock)?;
+    Ok(service.get_history(device_id, from, to))
+}
+
+/// How long and how much health history is kept on disk
+#[tauri::command]
+pub async fn get_health_history_retention(
+    polling_service: State<'_, Mutex<HealthPollingService>>,
+) -> AppResult<RetentionPolicy> {
+    let store = polling_service
+        .lock()
+        .map_err(AppError::lock)?
+        .history_store()?;
+    let store = store.lock().map_err(AppError::lock)?;
+    Ok(store.retention().clone())
+}
+
+/// Persist a new retention policy and apply it right away
+#[tauri::command]
+pub async fn set_health_history_retention(
+    retention: RetentionPolicy,
+    polling_service: State<'_, Mutex<HealthPollingService>>,
+) -> AppResult<CommandResultResponse> {
+    let store = polling_service
+        .lock()
+        .map_err(AppError::lock)?
+        .history_store()?;
+    let now = chrono::Utc::now().timestamp_millis() as u64;
+    store
+        .lock()
+        .map_err(AppError::lock)?
+        .set_retention(retention, now)?;
+
+    Ok(CommandResultResponse {
+        success: true,
+        message: Some("Health history retention updated".to_string()),
+    })
+}
+
+/// Export persisted health samples (optionally one device, `from`..=`to`)
+/// to a file picked by the user. Returns the written path, `None` if the
+/// dialog was cancelled.
+#[tauri::command]
+pub async fn export_health_history(
+    format: ExportFormat,
+    device_id: Option<String>,
+    from: Option<u64>,
+    to: Option<u64>,
+    polling_service: State<'_, Mutex<HealthPollingService>>,
+) -> AppResult<Option<String>> {
+    if let (Some(from), Some(to)) = (from, to) {
+        if from > to {
+            return Err(AppError::invalid_input("from must not be after to"));
+        }
+    }
+    let store = polling_service
+        .lock()
+        .map_err(AppError::lock)?
+        .history_store()?;
+
+    let extension = format.extension();
+    let Some(path) = rfd::FileDialog::new()
+        .set_title("Export Health History")
+        .add_filter(extension.to_uppercase(), &[extension])
+        .set_file_name(format!("health_history.{}", extension))
+        .save_file()
+    else {
+        return Ok(None); // User cancelled
+    };
+
+    let file =
+        std::fs::File::create(&path).map_err(|e| AppError::io("Failed to create file", e))?;
+    let mut writer = std::io::BufWriter::new(file);
+    let filter = ExportFilter {
+        device_id,
+        from,
+        to,
+    };
+    store
+        .lock()
+        .map_err(AppError::lock)?
+        .export(&filter, format, &mut writer)?;
+    std::io::Write::flush(&mut writer).map_err(|e| AppError::io("Failed to write file", e))?;
+
+    Ok(Some(path.to_string_lossy().into_owned()))
+}
+
+/// Alert rules evaluated on every poll
+#[tauri::command]
+pub async fn get_alert_rules(
+    polling_service: State<'_, Mutex<HealthPollingService>>,
+) -> AppResult<Vec<AlertRule>> {
+    let engine = polling_service
+        .lock()
+        .map_err(AppError::lock)?
+        .alert_engine();
+    let engine = engine.lock().map_err(AppError::lock)?;
+    Ok(engine.rules().to_vec())
+}
+
+/// Replace all alert rules; they are persisted and apply from the next poll
+#[tauri::command]
+pub async fn set_alert_rules(
+    rules: Vec<AlertRule>,
+    polling_service: State<'_, Mutex<HealthPollingService>>,
+) -> AppResult<CommandResultResponse> {
+    let engine = polling_service
+        .lock()
+        .map_err(AppError::lock)?
+        .alert_engine();
+    let count = rules.len();
+    engine.lock().map_err(AppError::lock)?.set_rules(rules)?;
+
+    Ok(CommandResultResponse {
+        success: true,
+        message: Some(format!("Saved {} alert rules", count)),
+    })
+}
+
 /// Get current cached health for a device
 #[tauri::command]
 pub async fn get_device_health(
     device_id: String,
     polling_service: State<'_, Mutex<HealthPollingService>>,
-) -> Result<GetDeviceHealthResponse, String> {
+) -> AppResult<GetDeviceHealthResponse> {
     // Get health snapshot without holding lock across await
     let health = {
-        let service = polling_service
-            .lock()
-            .map_err(|e| format!("Lock error: {}", e))?;
+        let service = polling_service.lock().map_err(AppError::lock)?;
         service.get_device_health_blocking(&device_id)
     };
 
@@ -82,3 +407,94 @@ pub async fn get_device_health(
         is_cached: true,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::ScriptedExecutor;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_resolve_polling_devices_detects_transport() {
+        let scripted = ScriptedExecutor::new();
+        scripted.respond_devices(
+            "R58M123 device usb:1-2 transport_id:1\n\
+             192.168.1.5:5555 device transport_id:2\n",
+        );
+        let adb: SharedAdbExecutor = Arc::new(scripted);
+
+        let devices = resolve_polling_devices(
+            &adb,
+            vec![
+                "R58M123".to_string(),
+                "192.168.1.5:5555".to_string(),
+                "emulator-5554".to_string(),
+            ],
+            vec![StartPollingDevice {
+                device_id: "emulator-5554".to_string(),
+                connection_type: ConnectionType::Wireless,
+            }],
+        )
+        .await;
+
+        let types: Vec<(&str, ConnectionType)> = devices
+            .iter()
+            .map(|d| (d.device_id.as_str(), d.connection_type))
+            .collect();
+        assert_eq!(
+            types,
+            vec![
+                ("emulator-5554", ConnectionType::Wireless),
+                ("R58M123", ConnectionType::Usb),
+                ("192.168.1.5:5555", ConnectionType::Wireless),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_start_request_from_invoke_args() {
+        let scripted = ScriptedExecutor::new();
+        scripted.respond_devices("R58M123 device usb:1-2 transport_id:1\n");
+        let adb: SharedAdbExecutor = Arc::new(scripted);
+        // What `invoke("start_health_polling", { request })` sends
+        let resolve = |args: serde_json::Value| {
+            let adb = adb.clone();
+            async move {
+                let request: StartHealthPollingRequest =
+                    serde_json::from_value(args["request"].clone()).unwrap();
+                let interval = request.config.polling_interval_usb;
+                let devices =
+                    resolve_polling_devices(&adb, request.device_ids, request.devices).await;
+                let devices: Vec<(String, ConnectionType)> = devices
+                    .into_iter()
+                    .map(|d| (d.device_id, d.connection_type))
+                    .collect();
+                (devices, interval)
+            }
+        };
+
+        let (devices, interval) = resolve(serde_json::json!({
+            "request": {
+                "config": HealthPollingConfig { polling_interval_usb: 2000, ..Default::default() },
+                "devices": [{ "deviceId": "abc123", "connectionType": "wireless" }],
+            }
+        }))
+        .await;
+        assert_eq!(
+            devices,
+            vec![("abc123".to_string(), ConnectionType::Wireless)]
+        );
+        assert_eq!(interval, 2000);
+
+        // Older callers send bare serials and no config
+        let (devices, interval) = resolve(serde_json::json!({
+            "request": { "deviceIds": ["R58M123"] }
+        }))
+        .await;
+        assert_eq!(devices, vec![("R58M123".to_string(), ConnectionType::Usb)]);
+        assert_eq!(
+            interval,
+            HealthPollingConfig::default().polling_interval_usb
+        );
+    }
+}
@@ -1,7 +1,13 @@
+use crate::commands::device::{
+    app_data_dir, list_devices, load_registry, logical_device, resolve_transport, DeviceInfo,
+};
+use crate::error::{AppError, AppResult};
+use crate::services::adb_executor::SharedAdbExecutor;
+use crate::types::health::ErrorCode;
 use std::collections::HashMap;
 use std::process::Stdio;
 use std::sync::Arc;
-use tauri::Emitter;
+use tauri::{Emitter, State};
 use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::process::{Child, Command};
 use tokio::sync::Mutex;
@@ -11,40 +17,99 @@ lazy_static::lazy_static! {
         Arc::new(Mutex::new(HashMap::new()));
 }
 
+/// Point the `-s`/`--serial` argument at `serial`, adding it if missing
+fn with_serial_arg(args: Vec<String>, serial: &str) -> Vec<String> {
+    let mut result = Vec::with_capacity(args.len() + 2);
+    let mut replaced = false;
+    let mut iter = args.into_iter();
+    while let Some(arg) = iter.next() {
+        if arg == "-s" || arg == "--serial" {
+            iter.next();
+            result.push(arg);
+            result.push(serial.to_string());
+            replaced = true;
+        } else if arg.starts_with("--serial=") {
+            result.push(format!("--serial={}", serial));
+            replaced = true;
+        } else {
+            result.push(arg);
+        }
+    }
+    if !replaced {
+        result.splice(0..0, ["-s".to_string(), serial.to_string()]);
+    }
+    result
+}
+
+/// Sessions are keyed by logical device, so a phone is mirrored at most once
+/// whichever of its transports it was started or stopped by
+fn session_key(devices: &[DeviceInfo], serial: &str) -> String {
+    logical_device(devices, serial).map_or_else(|| serial.to_string(), |g| g.id)
+}
+
+/// Refuse to start a second session under `key`
+fn ensure_no_session<T>(processes: &HashMap<String, T>, key: &str) -> AppResult<()> {
+    if processes.contains_key(key) {
+        return Err(AppError::new(
+            ErrorCode::SessionExists,
+            format!("Mirroring session already active for {}", key),
+        ));
+    }
+    Ok(())
+}
+
+/// Start mirroring. With `auto_transport`, `serial` may be any transport or
+/// hardware serial of a registered phone; the best online transport is used.
+/// Events carry the serial scrcpy was started with, which is returned;
+/// `stop_scrcpy` accepts any serial of the phone.
 #[tauri::command]
 pub async fn start_scrcpy(
     app: tauri::AppHandle,
     serial: String,
     args: Vec<String>,
-) -> Result<(), String> {
+    auto_transport: Option<bool>,
+    adb: State<'_, SharedAdbExecutor>,
+) -> AppResult<String> {
     if serial.is_empty() {
-        return Err("Device serial is required".to_string());
+        return Err(AppError::invalid_input("Device serial is required"));
     }
 
-    // Check if session already active
-    {
-        let processes = SCRCPY_PROCESSES.lock().await;
-        if processes.contains_key(&serial) {
-            return Err(format!("Mirroring session already active for {}", serial));
-        }
-    }
+    let (transport, args, key) = if auto_transport.unwrap_or(false) {
+        let devices = list_devices(app.clone(), adb).await?;
+        let transport = resolve_transport(&devices, &serial).ok_or_else(|| {
+            AppError::device_not_found(format!("No online transport for {}", serial))
+        })?;
+        let args = with_serial_arg(args, &transport);
+        (transport, args, session_key(&devices, &serial))
+    } else {
+        let registry = load_registry(&app_data_dir(&app)?);
+        let key = session_key(&registry, &serial);
+        (serial, args, key)
+    };
+
+    // Held until the child is registered so concurrent starts can't race
+    let mut processes = SCRCPY_PROCESSES.lock().await;
+    ensure_no_session(&processes, &key)?;
 
     let mut cmd = Command::new("scrcpy");
     cmd.args(&args);
     cmd.stdout(Stdio::piped());
     cmd.stderr(Stdio::piped());
 
-    let mut child = cmd
-        .spawn()
-        .map_err(|e| format!("Failed to start scrcpy: {}", e))?;
+    let mut child = cmd.spawn().map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => AppError::new(
+            ErrorCode::ScrcpyMissing,
+            "scrcpy was not found; install it and make sure it is on PATH",
+        )
+        .with_details(e.to_string()),
+        _ => AppError::io("Failed to start scrcpy", e),
+    })?;
 
-    spawn_output_readers(&app, &serial, &mut child);
+    spawn_output_readers(&app, &transport, &mut child);
 
-    let serial_clone = serial.clone();
-    {
-        let mut processes = SCRCPY_PROCESSES.lock().await;
-        processes.insert(serial, child);
-    }
+    let serial_clone = transport.clone();
+    processes.insert(key.clone(), child);
+    drop(processes);
 
     // Spawn exit monitor
     let app_clone = app.clone();
@@ -52,11 +117,11 @@ pub async fn start_scrcpy(
         loop {
             tokio::time::sleep(std::time::Duration::from_millis(500)).await;
             let mut processes = SCRCPY_PROCESSES.lock().await;
-            if let Some(child) = processes.get_mut(&serial_clone) {
+            if let Some(child) = processes.get_mut(&key) {
                 match child.try_wait() {
                     Ok(Some(status)) => {
                         let code = status.code();
-                        processes.remove(&serial_clone);
+                        processes.remove(&key);
                         let _ = app_clone.emit(
                             "scrcpy-exit",
                             serde_json::json!({
@@ -68,7 +133,7 @@ pub async fn start_scrcpy(
                     }
                     Ok(None) => continue,
                     Err(_) => {
-                        processes.remove(&serial_clone);
+                        processes.remove(&key);
                         let _ = app_clone.emit(
                             "scrcpy-exit",
                             serde_json::json!({
@@ -85,7 +150,7 @@ pub async fn start_scrcpy(
         }
     });
 
-    Ok(())
+    Ok(transport)
 }
 
 /// Take stdout and stderr from the child process, spawn tasks that read
@@ -131,15 +196,20 @@ fn spawn_output_readers(app: &tauri::AppHandle, serial: &str, child: &mut Child)
 }
 
 #[tauri::command]
-pub async fn stop_scrcpy(serial: String) -> Result<(), String> {
+pub async fn stop_scrcpy(app: tauri::AppHandle, serial: String) -> AppResult<()> {
+    let key = session_key(&load_registry(&app_data_dir(&app)?), &serial);
     let mut processes = SCRCPY_PROCESSES.lock().await;
-    if let Some(mut child) = processes.remove(&serial) {
+    // The registry may have regrouped the phone since the session started
+    if let Some(mut child) = processes.remove(&key).or_else(|| processes.remove(&serial)) {
         let _ = child.kill().await;
         // Reap the zombie to prevent "free(): corrupted unsorted chunks"
         let _ = child.wait().await;
         Ok(())
     } else {
-        Err("No scrcpy process found for this device".to_string())
+        Err(AppError::new(
+            ErrorCode::SessionNotFound,
+            "No scrcpy process found for this device",
+        ))
     }
 }
 
@@ -154,6 +224,12 @@ pub async fn kill_all_scrcpy() {
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+
+    fn to_args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|a| a.to_string()).collect()
+    }
+
     #[test]
     fn args_passthrough_preserves_order() {
         // Verify that Vec<String> args maintain their order
@@ -205,4 +281,66 @@ mod tests {
         assert_eq!(args.len(), 3);
         assert_eq!(args[2], "--otg");
     }
+
+    #[test]
+    fn serial_arg_is_replaced() {
+        let args = with_serial_arg(
+            to_args(&["-s", "R58M123", "--max-fps", "60"]),
+            "10.0.0.5:5555",
+        );
+        assert_eq!(args, to_args(&["-s", "10.0.0.5:5555", "--max-fps", "60"]));
+
+        let args = with_serial_arg(to_args(&["--serial=R58M123", "--otg"]), "R58M123");
+        assert_eq!(args, to_args(&["--serial=R58M123", "--otg"]));
+    }
+
+    #[test]
+    fn second_start_of_same_phone_is_refused() {
+        let transport = |serial: &str, status: &str| DeviceInfo {
+            serial: serial.to_string(),
+            status: status.to_string(),
+            model: None,
+            android_version: None,
+            battery_level: None,
+            is_wireless: serial.contains(':'),
+            last_seen: None,
+            first_seen: "2026-01-01T00:00:00+00:00".to_string(),
+            linked_serial: None,
+            auto_reconnect: false,
+            hardware_serial: Some("R58M123".to_string()),
+            usb_path: None,
+            product: None,
+            device_codename: None,
+            transport_id: None,
+            status_detail: None,
+        };
+        let devices = vec![
+            transport("R58M123", "device"),
+            transport("192.168.1.5:5555", "device"),
+        ];
+
+        // Started by its wireless serial without auto_transport
+        let first = session_key(&devices, "192.168.1.5:5555");
+        let mut processes = HashMap::new();
+        ensure_no_session(&processes, &first).unwrap();
+        processes.insert(first, ());
+
+        // Then by its USB serial with auto_transport: same phone, same key
+        assert_eq!(
+            resolve_transport(&devices, "R58M123").as_deref(),
+            Some("R58M123")
+        );
+        let second = session_key(&devices, "R58M123");
+        let err = ensure_no_session(&processes, &second).unwrap_err();
+        assert_eq!(err.code, ErrorCode::SessionExists);
+
+        // Phones missing from the registry are keyed by their serial
+        assert_eq!(session_key(&devices, "emulator-5554"), "emulator-5554");
+    }
+
+    #[test]
+    fn serial_arg_is_added_when_missing() {
+        let args = with_serial_arg(to_args(&["--max-fps", "60"]), "R58M123");
+        assert_eq!(args, to_args(&["-s", "R58M123", "--max-fps", "60"]));
+    }
 }
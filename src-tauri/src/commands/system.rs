@@ -1,6 +1,18 @@
-use std::process::Stdio;
+use crate::commands::device::{parse_adb_output, AdbDevice};
+use crate::error::{AppError, AppResult};
+use crate::services::adb_client::run_blocking;
+use crate::services::adb_executor::SharedAdbExecutor;
+use crate::services::adb_server::{self, AdbServerStatus};
+use crate::services::process::{output_with_timeout, output_with_timeout_async, ProcessError};
+use crate::services::usb_diagnostics::{self, DiagnosticPaths, UsbDiagnostics};
+use crate::types::health::ErrorCode;
+use std::time::Duration;
+use tauri::State;
 use tokio::process::Command;
 
+/// Deadline for `--version` style probes; a hung binary is killed after it
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
 #[derive(serde::Serialize)]
 pub struct Dependencies {
     pub adb: bool,
@@ -17,23 +29,17 @@ pub struct ScrcpyVersionInfo {
 
 #[tauri::command]
 pub async fn check_dependencies() -> Dependencies {
-    let adb_available = Command::new("adb")
-        .arg("version")
-        .stdout(Stdio::null())
-        .stderr(Stdio::null())
-        .status()
-        .await
-        .map(|s| s.success())
-        .unwrap_or(false);
-
-    let scrcpy_available = Command::new("scrcpy")
-        .arg("--version")
-        .stdout(Stdio::null())
-        .stderr(Stdio::null())
-        .status()
-        .await
-        .map(|s| s.success())
-        .unwrap_or(false);
+    let adb_available =
+        output_with_timeout_async(Command::new("adb").arg("version"), PROBE_TIMEOUT)
+            .await
+            .map(|o| o.status.success())
+            .unwrap_or(false);
+
+    let scrcpy_available =
+        output_with_timeout_async(Command::new("scrcpy").arg("--version"), PROBE_TIMEOUT)
+            .await
+            .map(|o| o.status.success())
+            .unwrap_or(false);
 
     Dependencies {
         adb: adb_available,
@@ -52,14 +58,16 @@ fn parse_version_parts(version_token: &str) -> (u32, u32, u32) {
 }
 
 #[tauri::command]
-pub async fn get_scrcpy_version() -> Result<ScrcpyVersionInfo, String> {
-    let output = Command::new("scrcpy")
-        .arg("--version")
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .output()
+pub async fn get_scrcpy_version() -> AppResult<ScrcpyVersionInfo> {
+    let output = output_with_timeout_async(Command::new("scrcpy").arg("--version"), PROBE_TIMEOUT)
         .await
-        .map_err(|e| format!("Failed to run scrcpy --version: {}", e))?;
+        .map_err(|e| {
+            let code = match e {
+                ProcessError::Spawn { .. } => ErrorCode::ScrcpyMissing,
+                ProcessError::Timeout { .. } => ErrorCode::Timeout,
+            };
+            AppError::new(code, format!("Failed to run scrcpy --version: {}", e))
+        })?;
 
     // scrcpy prints version to stdout: "scrcpy 3.3.4 <url>"
     let stdout = String::from_utf8_lossy(&output.stdout);
@@ -71,10 +79,9 @@ pub async fn get_scrcpy_version() -> Result<ScrcpyVersionInfo, String> {
     };
 
     let first_line = text.lines().next().unwrap_or("");
-    let version_token = first_line
-        .split_whitespace()
-        .nth(1)
-        .ok_or_else(|| format!("Cannot parse version from: {}", first_line))?;
+    let version_token = first_line.split_whitespace().nth(1).ok_or_else(|| {
+        AppError::new(ErrorCode::ParseError, "Cannot parse scrcpy version").with_details(first_line)
+    })?;
 
     let (major, minor, patch) = parse_version_parts(version_token);
 
@@ -117,6 +124,82 @@ fn list_v4l2_devices_impl() -> Vec<String> {
     Vec::new()
 }
 
+/// ADB server state, including client/server version mismatch detection
+#[tauri::command]
+pub async fn get_adb_server_status(
+    adb: State<'_, SharedAdbExecutor>,
+) -> AppResult<AdbServerStatus> {
+    let adb = adb.inner().clone();
+    run_blocking(move || Ok(adb_server::status(adb.as_ref()))).await
+}
+
+/// Start the ADB server if it isn't running
+#[tauri::command]
+pub async fn start_adb_server(adb: State<'_, SharedAdbExecutor>) -> AppResult<AdbServerStatus> {
+    let adb = adb.inner().clone();
+    run_blocking(move || adb_server::start(adb.as_ref())).await
+}
+
+/// Stop the ADB server (`adb kill-server`)
+#[tauri::command]
+pub async fn kill_adb_server(adb: State<'_, SharedAdbExecutor>) -> AppResult<()> {
+    let adb = adb.inner().clone();
+    run_blocking(move || adb_server::kill(adb.as_ref())).await
+}
+
+/// Restart the ADB server; pollers and the device tracker pick it up again
+#[tauri::command]
+pub async fn restart_adb_server(adb: State<'_, SharedAdbExecutor>) -> AppResult<AdbServerStatus> {
+    let adb = adb.inner().clone();
+    run_blocking(move || adb_server::restart(adb.as_ref())).await
+}
+
+/// Explain `no permissions` / `unauthorized` USB devices and suggest a udev rule
+#[tauri::command]
+pub async fn diagnose_usb_permissions(
+    adb: State<'_, SharedAdbExecutor>,
+) -> AppResult<UsbDiagnostics> {
+    // A missing ADB server only loses the state matching, not the sysfs checks
+    let adb = adb.inner().clone();
+    let adb_devices = run_blocking(move || adb.devices().map_err(|e| e.to_string()))
+        .await
+        .map(|output| parse_adb_output(&output))
+        .unwrap_or_default();
+    run_blocking(move || diagnose_usb_permissions_impl(&adb_devices)).await
+}
+
+#[cfg(target_os = "linux")]
+fn diagnose_usb_permissions_impl(adb_devices: &[AdbDevice]) -> AppResult<UsbDiagnostics> {
+    let id_output = |flag: &str| {
+        output_with_timeout(std::process::Command::new("id").arg(flag), PROBE_TIMEOUT)
+            .ok()
+            .filter(|o| o.status.success())
+            .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+    };
+    let user = std::env::var("USER")
+        .ok()
+        .or_else(|| id_output("-un"))
+        .ok_or_else(|| AppError::internal("Cannot determine the current user"))?;
+    let session_groups = id_output("-Gn")
+        .map(|groups| groups.split_whitespace().map(str::to_string).collect())
+        .unwrap_or_default();
+
+    Ok(usb_diagnostics::diagnose(
+        &DiagnosticPaths::default(),
+        &user,
+        session_groups,
+        adb_devices,
+    ))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn diagnose_usb_permissions_impl(_adb_devices: &[AdbDevice]) -> AppResult<UsbDiagnostics> {
+    Err(AppError::new(
+        ErrorCode::Unsupported,
+        "USB permission diagnostics are only available on Linux",
+    ))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,201 @@
+//! Backend Error Model
+//!
+//! Every Tauri command fails with an `AppError`, which reaches the frontend
+//! as `{ code, message, details }`. `code` is an `ErrorCode`, so the UI can
+//! pick a localized message and remediation steps without parsing `message`;
+//! `message` is an English description for logs, `details` carries raw tool
+//! output when there is any.
+//!
+//! Errors are categorized where they are raised: `AdbError`s map by variant
+//! (and by the ADB server's own FAIL reasons), everything else through the
+//! constructors below.
+
+use crate::services::adb_client::{AdbError, TaskError};
+use crate::types::health::ErrorCode;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AppError {
+    pub code: ErrorCode,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<String>,
+}
+
+/// Result of a Tauri command
+pub type AppResult<T> = Result<T, AppError>;
+
+impl AppError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            details: None,
+        }
+    }
+
+    /// Attach raw output (e.g. `adb` stderr) for the details view
+    pub fn with_details(mut self, details: impl Into<String>) -> Self {
+        self.details = Some(details.into());
+        self
+    }
+
+    /// Prefix the message with what was being attempted, keeping the code
+    pub fn context(mut self, context: &str) -> Self {
+        self.message = format!("{}: {}", context, self.message);
+        self
+    }
+
+    pub fn invalid_input(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::InvalidInput, message)
+    }
+
+    pub fn device_not_found(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::DeviceNotFound, message)
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Internal, message)
+    }
+
+    /// A managed service's mutex was poisoned
+    pub fn lock<T>(e: std::sync::PoisonError<T>) -> Self {
+        Self::internal(format!("Lock error: {}", e))
+    }
+
+    /// A local file operation failed; `context` says which
+    pub fn io(context: &str, e: std::io::Error) -> Self {
+        let code = match e.kind() {
+            std::io::ErrorKind::PermissionDenied => ErrorCode::PermissionDenied,
+            _ => ErrorCode::Io,
+        };
+        Self::new(code, format!("{}: {}", context, e))
+    }
+
+    /// Categorize a FAIL reason reported by the ADB server or `adb` binary
+    fn adb_failure(reason: &str) -> ErrorCode {
+        let lower = reason.to_lowercase();
+        // The binary fallback reports a missing or replaced server on stderr
+        if lower.contains("cannot connect to daemon")
+            || lower.contains("daemon not running")
+            || lower.contains("failed to start daemon")
+            || lower.contains("doesn't match this client")
+            || lower.contains("server killed")
+        {
+            ErrorCode::AdbServerUnavailable
+        } else if lower.contains("unauthorized") {
+            ErrorCode::DeviceUnauthorized
+        } else if lower.contains("no permissions") || lower.contains("insufficient permissions") {
+            ErrorCode::PermissionDenied
+        } else if lower.contains("offline") {
+            ErrorCode::Offline
+        } else if lower.contains("not found") || lower.contains("no devices") {
+            ErrorCode::DeviceNotFound
+        } else {
+            ErrorCode::AdbError
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl From<AdbError> for AppError {
+    fn from(e: AdbError) -> Self {
+        let code = match &e {
+            AdbError::ServerUnavailable(_) => ErrorCode::AdbServerUnavailable,
+            AdbError::Failed(reason) => Self::adb_failure(reason),
+            AdbError::Protocol(_) => ErrorCode::AdbError,
+            AdbError::Timeout(_) => ErrorCode::Timeout,
+            AdbError::BinaryUnavailable(_) => ErrorCode::AdbMissing,
+        };
+        Self::new(code, e.to_string())
+    }
+}
+
+impl TaskError for AppError {
+    fn task_failed(message: String) -> Self {
+        Self::internal(message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serializes_code_message_details() {
+        let error = AppError::new(ErrorCode::SessionExists, "Mirroring already active")
+            .with_details("R58M123");
+        let json = serde_json::to_value(&error).unwrap();
+        assert_eq!(json["code"], "session_exists");
+        assert_eq!(json["message"], "Mirroring already active");
+        assert_eq!(json["details"], "R58M123");
+
+        let json = serde_json::to_value(AppError::internal("boom")).unwrap();
+        assert!(json.get("details").is_none());
+    }
+
+    #[test]
+    fn test_adb_errors_map_by_variant_and_reason() {
+        let code = |e: AdbError| AppError::from(e).code;
+
+        assert_eq!(
+            code(AdbError::BinaryUnavailable("No such file".into())),
+            ErrorCode::AdbMissing
+        );
+        assert_eq!(
+            code(AdbError::ServerUnavailable("refused".into())),
+            ErrorCode::AdbServerUnavailable
+        );
+        assert_eq!(code(AdbError::Timeout("shell".into())), ErrorCode::Timeout);
+        assert_eq!(
+            code(AdbError::Failed(
+                "device unauthorized.\nThis adb server's $ADB_VENDOR_KEYS is not set".into()
+            )),
+            ErrorCode::DeviceUnauthorized
+        );
+        assert_eq!(
+            code(AdbError::Failed("device 'R58M123' not found".into())),
+            ErrorCode::DeviceNotFound
+        );
+        assert_eq!(
+            code(AdbError::Failed("device offline".into())),
+            ErrorCode::Offline
+        );
+        // Seen on stderr when another SDK's server owns the port
+        assert_eq!(
+            code(AdbError::Failed(
+                "adb server version (40) doesn't match this client (41); killing...".into()
+            )),
+            ErrorCode::AdbServerUnavailable
+        );
+        assert_eq!(
+            code(AdbError::Failed(
+                "* daemon not running; starting now at tcp:5037\nerror: device not found".into()
+            )),
+            ErrorCode::AdbServerUnavailable
+        );
+        // Anything else is a generic ADB failure, not a guess at a timeout
+        assert_eq!(
+            code(AdbError::Failed("bad format string".into())),
+            ErrorCode::AdbError
+        );
+    }
+
+    #[test]
+    fn test_context_keeps_code() {
+        let error =
+            AppError::from(AdbError::Timeout("echo test".into())).context("Device test failed");
+        assert_eq!(error.code, ErrorCode::Timeout);
+        assert!(error
+            .message
+            .starts_with("Device test failed: ADB request timed out"));
+    }
+}
@@ -1,9 +1,13 @@
 mod commands;
+pub mod error;
 pub mod services;
 pub mod types;
 
-use services::HealthPollingService;
-use std::sync::Mutex;
+use services::{
+    AdbClient, AutoReconnectService, DeviceTracker, HealthPollingService, MdnsDiscoveryService,
+    QrPairingService, SharedAdbExecutor,
+};
+use std::sync::{Arc, Mutex};
 use tauri::Manager;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -11,10 +15,32 @@ pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_os::init())
+        .plugin(tauri_plugin_notification::init())
         .setup(|app| {
+            // All device I/O goes through one executor (ADB server protocol)
+            let adb: SharedAdbExecutor = Arc::new(AdbClient::new());
+            app.manage(adb.clone());
+
             // Initialize health polling service
-            let polling_service = HealthPollingService::new(app.handle().clone());
+            let polling_service = HealthPollingService::new(app.handle().clone(), adb.clone());
             app.manage(Mutex::new(polling_service));
+
+            // Initialize device tracker (started on demand from the frontend)
+            let device_tracker = DeviceTracker::new(app.handle().clone(), adb.clone());
+            app.manage(Mutex::new(device_tracker));
+
+            // Initialize QR pairing (sessions are started from the frontend)
+            let qr_pairing = QrPairingService::new(app.handle().clone(), adb.clone());
+            app.manage(Mutex::new(qr_pairing));
+
+            // Initialize wireless discovery (watcher started on demand)
+            let mdns_discovery = MdnsDiscoveryService::new(app.handle().clone(), adb.clone());
+            app.manage(Mutex::new(mdns_discovery));
+
+            // Initialize auto-reconnect (idles until a device opts in)
+            let mut auto_reconnect = AutoReconnectService::new(app.handle().clone(), adb);
+            auto_reconnect.start()?;
+            app.manage(Mutex::new(auto_reconnect));
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
@@ -22,20 +48,51 @@ pub fn run() {
             commands::system::get_scrcpy_version,
             commands::system::get_platform,
             commands::system::list_v4l2_devices,
+            commands::system::diagnose_usb_permissions,
+            commands::system::get_adb_server_status,
+            commands::system::start_adb_server,
+            commands::system::kill_adb_server,
+            commands::system::restart_adb_server,
             commands::device::list_devices,
             commands::device::list_adb_devices,
+            commands::device::list_logical_devices,
             commands::device::register_device,
             commands::device::test_device,
             commands::device::forget_device,
+            commands::device::start_device_tracking,
+            commands::device::stop_device_tracking,
             commands::scrcpy::start_scrcpy,
             commands::scrcpy::stop_scrcpy,
             commands::connection::connect_wireless_device,
             commands::connection::disconnect_wireless_device,
+            commands::connection::pair_wireless_device,
+            commands::connection::start_qr_pairing,
+            commands::connection::cancel_qr_pairing,
+            commands::connection::go_wireless,
+            commands::connection::discover_wireless_devices,
+            commands::connection::start_wireless_discovery,
+            commands::connection::stop_wireless_discovery,
+            commands::connection::reconnect_known_device,
+            commands::connection::set_auto_reconnect,
+            commands::connection::set_auto_reconnect_config,
+            commands::connection::get_reconnection_states,
             commands::file::select_save_file,
             commands::file::export_presets,
             commands::file::import_presets,
             commands::health::start_health_polling,
             commands::health::stop_health_polling,
+            commands::health::add_polled_device,
+            commands::health::remove_polled_device,
+            commands::health::update_polling_config,
+            commands::health::set_polling_follow_registry,
+            commands::health::get_polling_retry_states,
+            commands::health::get_health_history,
+            commands::health::get_health_trends,
+            commands::health::get_health_history_retention,
+            commands::health::set_health_history_retention,
+            commands::health::export_health_history,
+            commands::health::get_alert_rules,
+            commands::health::set_alert_rules,
             commands::health::get_device_health,
         ])
         .on_window_event(|window, event| {
@@ -44,6 +101,15 @@ pub fn run() {
                 if let Ok(mut polling_service) = window.app_handle().state::<Mutex<HealthPollingService>>().lock() {
                     let _ = polling_service.stop_polling();
                 }
+                if let Ok(mut device_tracker) = window.app_handle().state::<Mutex<DeviceTracker>>().lock() {
+                    let _ = device_tracker.stop_tracking();
+                }
+                if let Ok(mut mdns_discovery) = window.app_handle().state::<Mutex<MdnsDiscoveryService>>().lock() {
+                    mdns_discovery.stop_watching();
+                }
+                if let Ok(mut auto_reconnect) = window.app_handle().state::<Mutex<AutoReconnectService>>().lock() {
+                    auto_reconnect.stop();
+                }
                 
                 // Kill all scrcpy processes and reap zombies on app close
                 tauri::async_runtime::block_on(async {
@@ -0,0 +1,736 @@
+//! ADB Server Protocol Client
+//!
+//! Talks to the ADB host server directly over TCP (localhost:5037 by default)
+//! instead of forking an `adb` process for every query.
+//! Supports:
+//! - `host:devices-l` (device list)
+//! - `host:transport:<serial>` / `host:transport-id:<id>` + `shell:<cmd>`
+//!   (shell commands)
+//! - `host:connect:<addr>` / `host:disconnect:<addr>` (wireless connections)
+//! - `host:pair:<code>:<addr>` (Android 11+ wireless pairing)
+//! - `host:mdns:services` (wireless debugging services on the LAN)
+//! - `host:track-devices-l` (device change subscription)
+//! - `host:version` / `host:kill` (server lifecycle)
+//!
+//! Device I/O is exposed through the `AdbExecutor` trait. Every operation
+//! falls back to the `adb` binary when the server can't be reached, so
+//! behavior is unchanged on machines where the server hasn't been started
+//! yet (the binary starts it on demand). The fallback is skipped while the
+//! user has the server stopped; clones of a client share that state.
+//!
+//! The client timeout is a hard deadline for the whole request: protocol
+//! reads stop when it passes and a fallback `adb` process is killed.
+
+use crate::services::adb_executor::{AdbExecutor, ProcessExecutor, SharedAdbExecutor};
+use crate::services::process::ProcessError;
+use std::fmt;
+use std::io::{ErrorKind, Read, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpStream};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Default port of the ADB host server
+pub const DEFAULT_ADB_SERVER_PORT: u16 = 5037;
+
+/// Default socket timeout for server requests
+const DEFAULT_TIMEOUT_MS: u64 = 10_000;
+
+/// Errors raised while talking to the ADB server
+#[derive(Debug, Clone, PartialEq)]
+pub enum AdbError {
+    /// Server not reachable (not running, wrong port) - triggers binary fallback
+    ServerUnavailable(String),
+    /// Server answered the request with FAIL
+    Failed(String),
+    /// I/O error or malformed response in the middle of a request
+    Protocol(String),
+    /// No complete answer within the client timeout
+    Timeout(String),
+    /// The `adb` binary could not be started
+    BinaryUnavailable(String),
+}
+
+impl fmt::Display for AdbError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AdbError::ServerUnavailable(msg) => write!(f, "ADB server unavailable: {}", msg),
+            AdbError::Failed(msg) => write!(f, "ADB command failed: {}", msg),
+            AdbError::Protocol(msg) => write!(f, "ADB protocol error: {}", msg),
+            AdbError::Timeout(msg) => write!(f, "ADB request timed out: {}", msg),
+            AdbError::BinaryUnavailable(msg) => write!(f, "Failed to execute adb: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for AdbError {}
+
+impl From<ProcessError> for AdbError {
+    fn from(e: ProcessError) -> Self {
+        match e {
+            ProcessError::Spawn { message, .. } => AdbError::BinaryUnavailable(message),
+            timeout @ ProcessError::Timeout { .. } => AdbError::Timeout(timeout.to_string()),
+        }
+    }
+}
+
+/// How a command addresses a device
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AdbTarget {
+    /// `-s <serial>` / `host:transport:<serial>`
+    Serial(String),
+    /// `-t <id>` / `host:transport-id:<id>`, for devices sharing a serial
+    TransportId(u32),
+}
+
+impl AdbTarget {
+    fn transport_request(&self) -> String {
+        match self {
+            AdbTarget::Serial(serial) => format!("host:transport:{}", serial),
+            AdbTarget::TransportId(id) => format!("host:transport-id:{}", id),
+        }
+    }
+
+    pub(crate) fn binary_args(&self) -> [String; 2] {
+        match self {
+            AdbTarget::Serial(serial) => ["-s".to_string(), serial.clone()],
+            AdbTarget::TransportId(id) => ["-t".to_string(), id.to_string()],
+        }
+    }
+}
+
+impl fmt::Display for AdbTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AdbTarget::Serial(serial) => write!(f, "{}", serial),
+            AdbTarget::TransportId(id) => write!(f, "transport {}", id),
+        }
+    }
+}
+
+/// ADB host protocol client
+///
+/// Cheap to clone; every request opens its own short-lived connection,
+/// exactly like the `adb` binary does.
+#[derive(Debug, Clone)]
+pub struct AdbClient {
+    server_addr: SocketAddr,
+    timeout: Duration,
+    /// Used whenever the server can't be reached
+    fallback: ProcessExecutor,
+    /// Set while the user has the server stopped; disables the fallback
+    stopped_by_user: Arc<AtomicBool>,
+}
+
+impl Default for AdbClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AdbClient {
+    /// Client for the local server, honoring `ANDROID_ADB_SERVER_PORT`
+    pub fn new() -> Self {
+        let port = std::env::var("ANDROID_ADB_SERVER_PORT")
+            .ok()
+            .and_then(|p| p.parse().ok())
+            .unwrap_or(DEFAULT_ADB_SERVER_PORT);
+        Self::with_server_addr(SocketAddr::from((Ipv4Addr::LOCALHOST, port)))
+    }
+
+    /// Client for a server listening on a specific address
+    pub fn with_server_addr(server_addr: SocketAddr) -> Self {
+        Self {
+            server_addr,
+            timeout: Duration::from_millis(DEFAULT_TIMEOUT_MS),
+            fallback: ProcessExecutor::default(),
+            stopped_by_user: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Override the deadline applied to every request and fallback process
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self.fallback.set_timeout(timeout);
+        self
+    }
+
+    /// Use a specific `adb` binary for the fallback path instead of PATH
+    pub fn with_adb_path(mut self, adb_path: impl Into<PathBuf>) -> Self {
+        let mut fallback = ProcessExecutor::new(adb_path);
+        fallback.set_timeout(self.timeout);
+        self.fallback = fallback;
+        self
+    }
+
+    /// Deadline applied to every request
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    // ─── Server lifecycle ──────────────────────────────────────────────────
+
+    /// Address of the server this client talks to
+    pub fn server_addr(&self) -> SocketAddr {
+        self.server_addr
+    }
+
+    /// Subscribe to device list changes (protocol only)
+    ///
+    /// The server immediately sends the current list, then a new full
+    /// snapshot every time a device appears, disappears or changes state.
+    pub fn track_devices(&self) -> Result<DeviceTracking, AdbError> {
+        let mut stream = self.open()?;
+        send_request(&mut stream, "host:track-devices-l")?;
+        read_status(&mut stream)?;
+        Ok(DeviceTracking {
+            stream,
+            timeout: self.timeout,
+        })
+    }
+
+    // ─── Protocol primitives ───────────────────────────────────────────────
+
+    /// Open a connection to the server
+    fn open(&self) -> Result<TcpStream, AdbError> {
+        let stream = TcpStream::connect_timeout(&self.server_addr, self.timeout)
+            .map_err(|e| AdbError::ServerUnavailable(format!("{}: {}", self.server_addr, e)))?;
+        stream
+            .set_read_timeout(Some(self.timeout))
+            .and_then(|_| stream.set_write_timeout(Some(self.timeout)))
+            .map_err(io_error)?;
+        Ok(stream)
+    }
+
+    /// Run a `host:` request that answers with a length-prefixed payload
+    fn host_query(&self, request: &str) -> Result<String, AdbError> {
+        let mut stream = self.open()?;
+        send_request(&mut stream, request)?;
+        read_status(&mut stream)?;
+        read_length_prefixed(&mut stream)
+    }
+
+    /// Switch to a device transport and run a service, reading until EOF
+    fn transport_exec(&self, target: &AdbTarget, service: &str) -> Result<String, AdbError> {
+        let deadline = Instant::now() + self.timeout;
+        let mut stream = self.open()?;
+        send_request(&mut stream, &target.transport_request())?;
+        read_status(&mut stream)?;
+        send_request(&mut stream, service)?;
+        read_status(&mut stream)?;
+        read_to_end(&mut stream, deadline)
+    }
+
+    // ─── Binary fallback ───────────────────────────────────────────────────
+
+    /// Fall back to the binary when the server can't be reached, unless the
+    /// user stopped it (the binary would start it again)
+    fn or_binary(
+        &self,
+        result: Result<String, AdbError>,
+        args: &[&str],
+    ) -> Result<String, AdbError> {
+        match result {
+            Err(AdbError::ServerUnavailable(_)) if !self.stopped_by_user() => self.run_binary(args),
+            other => other,
+        }
+    }
+
+    /// Run the `adb` binary and return stdout, killing it at the deadline
+    fn run_binary(&self, args: &[&str]) -> Result<String, AdbError> {
+        self.fallback.run(args)
+    }
+}
+
+// ─── Device I/O (protocol first, binary fallback) ────────────────────────
+
+impl AdbExecutor for AdbClient {
+    /// `host:devices-l` output has no header line
+    fn devices(&self) -> Result<String, AdbError> {
+        self.or_binary(self.host_query("host:devices-l"), &["devices", "-l"])
+    }
+
+    fn shell(&self, target: &AdbTarget, command: &str) -> Result<String, AdbError> {
+        let [flag, value] = target.binary_args();
+        self.or_binary(
+            self.transport_exec(target, &format!("shell:{}", command)),
+            &[&flag, &value, "shell", command],
+        )
+    }
+
+    fn connect(&self, address: &str) -> Result<String, AdbError> {
+        self.or_binary(
+            self.host_query(&format!("host:connect:{}", address)),
+            &["connect", address],
+        )
+    }
+
+    fn disconnect(&self, address: &str) -> Result<String, AdbError> {
+        self.or_binary(
+            self.host_query(&format!("host:disconnect:{}", address)),
+            &["disconnect", address],
+        )
+    }
+
+    fn pair(&self, address: &str, code: &str) -> Result<String, AdbError> {
+        match self.host_query(&format!("host:pair:{}:{}", code, address)) {
+            Err(AdbError::ServerUnavailable(_)) if !self.stopped_by_user() => {
+                self.fallback.run_combined(&["pair", address, code])
+            }
+            other => other,
+        }
+    }
+
+    fn tcpip(&self, target: &AdbTarget, port: u16) -> Result<String, AdbError> {
+        let [flag, value] = target.binary_args();
+        self.or_binary(
+            self.transport_exec(target, &format!("tcpip:{}", port)),
+            &[&flag, &value, "tcpip", &port.to_string()],
+        )
+    }
+
+    fn mdns_services(&self) -> Result<String, AdbError> {
+        self.or_binary(self.host_query("host:mdns:services"), &["mdns", "services"])
+    }
+
+    fn with_timeout(&self, timeout: Duration) -> SharedAdbExecutor {
+        Arc::new(self.clone().with_timeout(timeout))
+    }
+
+    /// Protocol only: the binary would start a server to answer
+    fn server_version(&self) -> Result<u32, AdbError> {
+        let hex = self.host_query("host:version")?;
+        u32::from_str_radix(hex.trim(), 16)
+            .map_err(|_| AdbError::Protocol(format!("Invalid version response: {}", hex)))
+    }
+
+    fn client_version(&self) -> Result<String, AdbError> {
+        self.run_binary(&["version"])
+    }
+
+    fn start_server(&self) -> Result<(), AdbError> {
+        self.run_binary(&["start-server"]).map(|_| ())
+    }
+
+    /// `host:kill` over the protocol
+    fn kill_server(&self) -> Result<(), AdbError> {
+        let result = self.open().and_then(|mut stream| {
+            send_request(&mut stream, "host:kill")?;
+            read_status(&mut stream)
+        });
+        match result {
+            Err(AdbError::ServerUnavailable(_)) => Ok(()),
+            other => other,
+        }
+    }
+
+    fn server_port(&self) -> u16 {
+        self.server_addr.port()
+    }
+
+    fn stopped_by_user(&self) -> bool {
+        self.stopped_by_user.load(Ordering::SeqCst)
+    }
+
+    fn set_stopped_by_user(&self, stopped: bool) {
+        self.stopped_by_user.store(stopped, Ordering::SeqCst);
+    }
+}
+
+/// Live `host:track-devices-l` subscription
+pub struct DeviceTracking {
+    stream: TcpStream,
+    timeout: Duration,
+}
+
+impl DeviceTracking {
+    /// Wait up to `wait` for the next device list snapshot
+    ///
+    /// Returns Ok(None) if nothing changed within `wait`, and
+    /// ServerUnavailable once the server closes the subscription.
+    pub fn next_snapshot(&mut self, wait: Duration) -> Result<Option<String>, AdbError> {
+        self.stream.set_read_timeout(Some(wait)).map_err(io_error)?;
+
+        let mut probe = [0u8; 1];
+        match self.stream.peek(&mut probe) {
+            Ok(0) => {
+                return Err(AdbError::ServerUnavailable(
+                    "Device tracking connection closed".to_string(),
+                ))
+            }
+            Ok(_) => {}
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                return Ok(None)
+            }
+            Err(e) => return Err(io_error(e)),
+        }
+
+        // A snapshot is arriving - read it in full with the regular timeout
+        self.stream
+            .set_read_timeout(Some(self.timeout))
+            .map_err(io_error)?;
+        read_length_prefixed(&mut self.stream).map(Some)
+    }
+}
+
+/// Run an async-context ADB operation on the blocking thread pool
+pub async fn run_blocking<T, E, F>(operation: F) -> Result<T, E>
+where
+    F: FnOnce() -> Result<T, E> + Send + 'static,
+    T: Send + 'static,
+    E: TaskError + Send + 'static,
+{
+    tokio::task::spawn_blocking(operation)
+        .await
+        .map_err(|e| E::task_failed(format!("ADB task failed: {}", e)))?
+}
+
+/// Error types `run_blocking` can report a panicked or cancelled task in
+pub trait TaskError {
+    fn task_failed(message: String) -> Self;
+}
+
+impl TaskError for String {
+    fn task_failed(message: String) -> Self {
+        message
+    }
+}
+
+fn io_error(e: std::io::Error) -> AdbError {
+    match e.kind() {
+        ErrorKind::WouldBlock | ErrorKind::TimedOut => AdbError::Timeout(e.to_string()),
+        _ => AdbError::Protocol(e.to_string()),
+    }
+}
+
+/// Encode a request as `<4 hex digit length><payload>`
+fn encode_request(request: &str) -> Vec<u8> {
+    format!("{:04x}{}", request.len(), request).into_bytes()
+}
+
+fn send_request(stream: &mut TcpStream, request: &str) -> Result<(), AdbError> {
+    stream.write_all(&encode_request(request)).map_err(io_error)
+}
+
+/// Read the 4-byte `OKAY`/`FAIL` status, turning `FAIL` into an error
+fn read_status(stream: &mut TcpStream) -> Result<(), AdbError> {
+    let mut status = [0u8; 4];
+    stream.read_exact(&mut status).map_err(io_error)?;
+    match &status {
+        b"OKAY" => Ok(()),
+        b"FAIL" => Err(AdbError::Failed(read_length_prefixed(stream)?)),
+        other => Err(AdbError::Protocol(format!(
+            "Unexpected status: {}",
+            String::from_utf8_lossy(other)
+        ))),
+    }
+}
+
+fn read_length_prefixed(stream: &mut TcpStream) -> Result<String, AdbError> {
+    let mut len_hex = [0u8; 4];
+    stream.read_exact(&mut len_hex).map_err(io_error)?;
+    let len = usize::from_str_radix(&String::from_utf8_lossy(&len_hex), 16)
+        .map_err(|_| AdbError::Protocol("Invalid length prefix".to_string()))?;
+
+    let mut payload = vec![0u8; len];
+    stream.read_exact(&mut payload).map_err(io_error)?;
+    Ok(String::from_utf8_lossy(&payload).to_string())
+}
+
+/// Read until EOF, giving up once `deadline` passes even if data trickles in
+fn read_to_end(stream: &mut TcpStream, deadline: Instant) -> Result<String, AdbError> {
+    let mut output = Vec::new();
+    let mut chunk = [0u8; 8192];
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Err(AdbError::Timeout(format!(
+                "no EOF after {} bytes",
+                output.len()
+            )));
+        }
+        stream.set_read_timeout(Some(remaining)).map_err(io_error)?;
+        match stream.read(&mut chunk) {
+            Ok(0) => break,
+            Ok(n) => output.extend_from_slice(&chunk[..n]),
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(io_error(e)),
+        }
+    }
+    Ok(String::from_utf8_lossy(&output).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+    use std::thread;
+
+    fn serial(s: &str) -> AdbTarget {
+        AdbTarget::Serial(s.to_string())
+    }
+
+    /// Reply sent by the fake server for a single request
+    enum FakeReply {
+        /// OKAY, then keep the connection open for the next request
+        Transport,
+        /// OKAY followed by a length-prefixed payload
+        Host(&'static str),
+        /// OKAY followed by raw output until EOF
+        Stream(&'static str),
+        /// FAIL with a length-prefixed message
+        Fail(&'static str),
+    }
+
+    fn read_request(stream: &mut TcpStream) -> Option<String> {
+        let mut len_hex = [0u8; 4];
+        stream.read_exact(&mut len_hex).ok()?;
+        let len = usize::from_str_radix(std::str::from_utf8(&len_hex).ok()?, 16).ok()?;
+        let mut payload = vec![0u8; len];
+        stream.read_exact(&mut payload).ok()?;
+        String::from_utf8(payload).ok()
+    }
+
+    /// Spawn a fake ADB server answering `connections` connections
+    fn fake_server(connections: usize, handler: fn(&str) -> FakeReply) -> AdbClient {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        thread::spawn(move || {
+            for stream in listener.incoming().take(connections) {
+                let mut stream = stream.unwrap();
+                while let Some(request) = read_request(&mut stream) {
+                    match handler(&request) {
+                        FakeReply::Transport => {
+                            stream.write_all(b"OKAY").unwrap();
+                            continue;
+                        }
+                        FakeReply::Host(payload) => {
+                            let body = format!("OKAY{:04x}{}", payload.len(), payload);
+                            stream.write_all(body.as_bytes()).unwrap();
+                        }
+                        FakeReply::Stream(output) => {
+                            stream.write_all(b"OKAY").unwrap();
+                            stream.write_all(output.as_bytes()).unwrap();
+                        }
+                        FakeReply::Fail(msg) => {
+                            let body = format!("FAIL{:04x}{}", msg.len(), msg);
+                            stream.write_all(body.as_bytes()).unwrap();
+                        }
+                    }
+                    break;
+                }
+            }
+        });
+
+        AdbClient::with_server_addr(addr).with_timeout(Duration::from_secs(2))
+    }
+
+    #[test]
+    fn test_encode_request() {
+        assert_eq!(encode_request("host:version"), b"000chost:version".to_vec());
+        assert_eq!(encode_request(""), b"0000".to_vec());
+    }
+
+    #[test]
+    fn test_server_version() {
+        let client = fake_server(1, |req| {
+            assert_eq!(req, "host:version");
+            FakeReply::Host("0029")
+        });
+        assert_eq!(client.server_version().unwrap(), 41);
+    }
+
+    #[test]
+    fn test_devices_long() {
+        let client = fake_server(1, |req| {
+            assert_eq!(req, "host:devices-l");
+            FakeReply::Host("abc123 device usb:1-2 product:oriole model:Pixel_6 transport_id:1\n")
+        });
+        let output = client.devices().unwrap();
+        assert!(output.starts_with("abc123 device"));
+    }
+
+    #[test]
+    fn test_shell_via_transport() {
+        let client = fake_server(1, |req| match req {
+            "host:transport:abc123" => FakeReply::Transport,
+            "shell:getprop ro.product.model" => FakeReply::Stream("Pixel 6\n"),
+            other => panic!("unexpected request {}", other),
+        });
+        assert_eq!(
+            client
+                .shell(&serial("abc123"), "getprop ro.product.model")
+                .unwrap(),
+            "Pixel 6\n"
+        );
+    }
+
+    #[test]
+    fn test_shell_by_transport_id() {
+        let client = fake_server(1, |req| match req {
+            "host:transport-id:7" => FakeReply::Transport,
+            "shell:echo ok" => FakeReply::Stream("ok\n"),
+            other => panic!("unexpected request {}", other),
+        });
+        assert_eq!(
+            client.shell(&AdbTarget::TransportId(7), "echo ok").unwrap(),
+            "ok\n"
+        );
+    }
+
+    #[test]
+    fn test_tcpip_via_transport() {
+        let client = fake_server(1, |req| match req {
+            "host:transport:abc123" => FakeReply::Transport,
+            "tcpip:5555" => FakeReply::Stream("restarting in TCP mode port: 5555\n"),
+            other => panic!("unexpected request {}", other),
+        });
+        assert!(client
+            .tcpip(&serial("abc123"), 5555)
+            .unwrap()
+            .contains("TCP mode port: 5555"));
+    }
+
+    #[test]
+    fn test_shell_unknown_device_fails() {
+        let client = fake_server(1, |_| FakeReply::Fail("device 'nope' not found"));
+        let err = client.shell(&serial("nope"), "echo ok").unwrap_err();
+        assert!(err.to_string().contains("device 'nope' not found"));
+    }
+
+    #[test]
+    fn test_connect_returns_server_message() {
+        let client = fake_server(1, |req| {
+            assert_eq!(req, "host:connect:192.168.1.5:5555");
+            FakeReply::Host("connected to 192.168.1.5:5555")
+        });
+        assert_eq!(
+            client.connect("192.168.1.5:5555").unwrap(),
+            "connected to 192.168.1.5:5555"
+        );
+    }
+
+    #[test]
+    fn test_pair_request_format() {
+        let client = fake_server(1, |req| {
+            assert_eq!(req, "host:pair:123456:192.168.1.5:37123");
+            FakeReply::Host("Successfully paired to 192.168.1.5:37123 [guid=adb-ABC-xyz]")
+        });
+        assert!(client
+            .pair("192.168.1.5:37123", "123456")
+            .unwrap()
+            .starts_with("Successfully paired"));
+    }
+
+    #[test]
+    fn test_track_devices_snapshots() {
+        let client = fake_server(1, |req| {
+            assert_eq!(req, "host:track-devices-l");
+            FakeReply::Host("abc123 device usb:1-2 transport_id:1\n")
+        });
+        let mut tracking = client.track_devices().unwrap();
+        let snapshot = tracking.next_snapshot(Duration::from_secs(2)).unwrap();
+        assert_eq!(
+            snapshot.as_deref(),
+            Some("abc123 device usb:1-2 transport_id:1\n")
+        );
+
+        // Fake server closes the connection after the first snapshot
+        assert!(matches!(
+            tracking.next_snapshot(Duration::from_secs(2)),
+            Err(AdbError::ServerUnavailable(_))
+        ));
+    }
+
+    #[test]
+    fn test_kill_server() {
+        let client = fake_server(1, |req| {
+            assert_eq!(req, "host:kill");
+            FakeReply::Transport
+        });
+        assert!(client.kill_server().is_ok());
+    }
+
+    #[test]
+    fn test_kill_server_when_not_running() {
+        let addr = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let client = AdbClient::with_server_addr(addr).with_timeout(Duration::from_millis(500));
+        assert!(client.kill_server().is_ok());
+    }
+
+    #[test]
+    fn test_unreachable_server_is_unavailable() {
+        // Bind and drop to get a port with nothing listening
+        let addr = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let client = AdbClient::with_server_addr(addr).with_timeout(Duration::from_millis(500));
+        assert!(matches!(
+            client.host_query("host:version"),
+            Err(AdbError::ServerUnavailable(_))
+        ));
+    }
+
+    #[test]
+    fn test_hung_shell_times_out() {
+        // Server accepts the shell service, then never sends output or EOF
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            while read_request(&mut stream).is_some() {
+                stream.write_all(b"OKAY").unwrap();
+            }
+        });
+
+        let client = AdbClient::with_server_addr(addr).with_timeout(Duration::from_millis(300));
+        let start = Instant::now();
+        let result = client.shell(&serial("abc123"), "dumpsys battery");
+        assert!(matches!(result, Err(AdbError::Timeout(_))), "{:?}", result);
+        assert!(start.elapsed() < Duration::from_secs(2));
+    }
+
+    /// Fake `adb` that hangs like a stuck `dumpsys`
+    #[cfg(unix)]
+    fn slow_fake_adb(name: &str) -> std::path::PathBuf {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = std::env::temp_dir().join(format!(
+            "scrcpy-gui-slow-adb-{}-{}",
+            name,
+            std::process::id()
+        ));
+        std::fs::write(&path, "#!/bin/sh\nexec sleep 5\n").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_slow_binary_fallback_is_killed() {
+        let adb = slow_fake_adb("fallback");
+        let addr = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let client = AdbClient::with_server_addr(addr)
+            .with_timeout(Duration::from_millis(300))
+            .with_adb_path(&adb);
+
+        let start = Instant::now();
+        let result = client.shell(&serial("abc123"), "dumpsys battery");
+        assert!(start.elapsed() < Duration::from_secs(2));
+        match result {
+            Err(AdbError::Timeout(msg)) => assert!(msg.contains("timed out after 300ms")),
+            other => panic!("expected timeout, got {:?}", other),
+        }
+        assert!(matches!(client.devices(), Err(AdbError::Timeout(_))));
+        let _ = std::fs::remove_file(adb);
+    }
+}
@@ -0,0 +1,480 @@
+//! ADB Executor
+//!
+//! `AdbExecutor` is the single seam for device I/O. Commands and services
+//! receive a `SharedAdbExecutor` (Tauri managed state in the app) instead of
+//! spawning `adb` themselves. Implementations:
+//! - `AdbClient` - talks to the ADB server protocol, falls back to the binary
+//! - `ProcessExecutor` - runs the `adb` binary for every request
+//! - `ScriptedExecutor` - canned in-memory responses for tests
+//!
+//! Server lifecycle (`adb_server`) goes through the same seam, so the
+//! executor also owns the "stopped by user" state for the server it talks to.
+
+use crate::services::adb_client::{AdbError, AdbTarget, DEFAULT_ADB_SERVER_PORT};
+use crate::services::process::output_with_timeout;
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Default deadline for a single `adb` invocation
+const DEFAULT_TIMEOUT_MS: u64 = 10_000;
+
+/// Executor shared between commands and background services
+pub type SharedAdbExecutor = Arc<dyn AdbExecutor>;
+
+/// Blocking device I/O; async callers wrap calls in `run_blocking`
+pub trait AdbExecutor: Send + Sync {
+    /// Attached devices in `adb devices -l` format
+    fn devices(&self) -> Result<String, AdbError>;
+
+    /// Run a shell command on a device and return its raw stdout
+    fn shell(&self, target: &AdbTarget, command: &str) -> Result<String, AdbError>;
+
+    /// Connect to a wireless device (`ip:port`), returning the server's message
+    fn connect(&self, address: &str) -> Result<String, AdbError>;
+
+    /// Disconnect a wireless device (`ip:port`), returning the server's message
+    fn disconnect(&self, address: &str) -> Result<String, AdbError>;
+
+    /// Pair with an Android 11+ device; returns the raw output (success or
+    /// failure text) so callers can classify it
+    fn pair(&self, address: &str, code: &str) -> Result<String, AdbError>;
+
+    /// Restart adbd on the device in TCP mode (`adb tcpip <port>`)
+    fn tcpip(&self, target: &AdbTarget, port: u16) -> Result<String, AdbError>;
+
+    /// ADB services discovered over mDNS, in `adb mdns services` format
+    fn mdns_services(&self) -> Result<String, AdbError>;
+
+    /// The same executor with a different per-request deadline
+    fn with_timeout(&self, timeout: Duration) -> SharedAdbExecutor;
+
+    // ─── Server lifecycle ──────────────────────────────────────────────────
+
+    /// Internal protocol version of the running server (`host:version`)
+    fn server_version(&self) -> Result<u32, AdbError>;
+
+    /// Raw `adb version` output of the client binary
+    fn client_version(&self) -> Result<String, AdbError>;
+
+    /// Start the server if it isn't running (`adb start-server`)
+    fn start_server(&self) -> Result<(), AdbError>;
+
+    /// Ask the server to exit; succeeds if none is running
+    fn kill_server(&self) -> Result<(), AdbError>;
+
+    /// Port of the server this executor talks to
+    fn server_port(&self) -> u16;
+
+    /// Whether the user stopped the server; requests must not start it again
+    fn stopped_by_user(&self) -> bool;
+
+    /// Record a user-requested kill (`true`) or start/restart (`false`)
+    fn set_stopped_by_user(&self, stopped: bool);
+}
+
+// ─── Process executor ────────────────────────────────────────────────────
+
+/// Runs the `adb` binary for every request, killing it at the deadline
+#[derive(Debug, Clone)]
+pub struct ProcessExecutor {
+    adb_path: PathBuf,
+    timeout: Duration,
+}
+
+impl Default for ProcessExecutor {
+    fn default() -> Self {
+        Self::new("adb")
+    }
+}
+
+impl ProcessExecutor {
+    pub fn new(adb_path: impl Into<PathBuf>) -> Self {
+        Self {
+            adb_path: adb_path.into(),
+            timeout: Duration::from_millis(DEFAULT_TIMEOUT_MS),
+        }
+    }
+
+    pub(crate) fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    /// Run `adb <args>` and return stdout; a non-zero exit becomes `Failed`
+    pub fn run(&self, args: &[&str]) -> Result<String, AdbError> {
+        let output = output_with_timeout(Command::new(&self.adb_path).args(args), self.timeout)?;
+
+        if output.status.success() {
+            Ok(String::from_utf8_lossy(&output.stdout).to_string())
+        } else {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            Err(AdbError::Failed(stderr.trim().to_string()))
+        }
+    }
+
+    /// For commands whose failure text is meaningful: returns stdout and
+    /// stderr combined regardless of the exit status
+    pub fn run_combined(&self, args: &[&str]) -> Result<String, AdbError> {
+        let output = output_with_timeout(Command::new(&self.adb_path).args(args), self.timeout)?;
+
+        Ok(format!(
+            "{}{}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        ))
+    }
+}
+
+impl AdbExecutor for ProcessExecutor {
+    fn devices(&self) -> Result<String, AdbError> {
+        self.run(&["devices", "-l"])
+    }
+
+    fn shell(&self, target: &AdbTarget, command: &str) -> Result<String, AdbError> {
+        let [flag, value] = target.binary_args();
+        self.run(&[&flag, &value, "shell", command])
+    }
+
+    fn connect(&self, address: &str) -> Result<String, AdbError> {
+        self.run(&["connect", address])
+    }
+
+    fn disconnect(&self, address: &str) -> Result<String, AdbError> {
+        self.run(&["disconnect", address])
+    }
+
+    fn pair(&self, address: &str, code: &str) -> Result<String, AdbError> {
+        self.run_combined(&["pair", address, code])
+    }
+
+    fn tcpip(&self, target: &AdbTarget, port: u16) -> Result<String, AdbError> {
+        let [flag, value] = target.binary_args();
+        self.run(&[&flag, &value, "tcpip", &port.to_string()])
+    }
+
+    fn mdns_services(&self) -> Result<String, AdbError> {
+        self.run(&["mdns", "services"])
+    }
+
+    fn with_timeout(&self, timeout: Duration) -> SharedAdbExecutor {
+        let mut executor = self.clone();
+        executor.timeout = timeout;
+        Arc::new(executor)
+    }
+
+    /// The binary can't query the server; this always fails
+    fn server_version(&self) -> Result<u32, AdbError> {
+        Err(AdbError::Protocol(
+            "host:version needs the ADB server protocol".to_string(),
+        ))
+    }
+
+    fn client_version(&self) -> Result<String, AdbError> {
+        self.run(&["version"])
+    }
+
+    fn start_server(&self) -> Result<(), AdbError> {
+        self.run(&["start-server"]).map(|_| ())
+    }
+
+    fn kill_server(&self) -> Result<(), AdbError> {
+        self.run(&["kill-server"]).map(|_| ())
+    }
+
+    fn server_port(&self) -> u16 {
+        DEFAULT_ADB_SERVER_PORT
+    }
+
+    /// Every request runs the binary, which starts a server on demand, so
+    /// there is no stopped state to keep
+    fn stopped_by_user(&self) -> bool {
+        false
+    }
+
+    fn set_stopped_by_user(&self, _stopped: bool) {}
+}
+
+// ─── Scripted executor ───────────────────────────────────────────────────
+
+/// A request as seen by an executor; used to script and record calls
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AdbRequest {
+    Devices,
+    Shell {
+        target: AdbTarget,
+        command: String,
+    },
+    Connect(String),
+    Disconnect(String),
+    Pair {
+        address: String,
+        code: String,
+    },
+    Tcpip {
+        target: AdbTarget,
+        port: u16,
+    },
+    MdnsServices,
+    /// Answered with the protocol version in decimal
+    ServerVersion,
+    ClientVersion,
+    StartServer,
+    KillServer,
+}
+
+enum Matcher {
+    Exact(AdbRequest),
+    /// A shell command on any device
+    AnyShell(String),
+}
+
+impl Matcher {
+    fn matches(&self, request: &AdbRequest) -> bool {
+        match (self, request) {
+            (Matcher::Exact(expected), request) => expected == request,
+            (Matcher::AnyShell(expected), AdbRequest::Shell { command, .. }) => expected == command,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Default)]
+struct Script {
+    responses: Vec<(Matcher, Result<String, AdbError>)>,
+    calls: Vec<AdbRequest>,
+    stopped_by_user: bool,
+}
+
+/// In-memory executor answering from canned responses.
+///
+/// Clones share the same script, so a test can keep a handle, hand an
+/// `Arc` to the code under test, then change responses or inspect calls.
+/// The most recently scripted matching response wins; unscripted requests
+/// fail with `AdbError::Failed`.
+#[derive(Clone, Default)]
+pub struct ScriptedExecutor {
+    script: Arc<Mutex<Script>>,
+}
+
+impl ScriptedExecutor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn script(&self) -> std::sync::MutexGuard<'_, Script> {
+        self.script.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn push(&self, matcher: Matcher, response: Result<String, AdbError>) -> &Self {
+        self.script().responses.push((matcher, response));
+        self
+    }
+
+    /// Answer `request` with `output`
+    pub fn respond(&self, request: AdbRequest, output: impl Into<String>) -> &Self {
+        self.push(Matcher::Exact(request), Ok(output.into()))
+    }
+
+    /// Answer `request` with an error
+    pub fn fail(&self, request: AdbRequest, error: AdbError) -> &Self {
+        self.push(Matcher::Exact(request), Err(error))
+    }
+
+    /// Answer `adb shell <command>` on any device with `output`
+    pub fn respond_shell(&self, command: &str, output: impl Into<String>) -> &Self {
+        self.push(Matcher::AnyShell(command.to_string()), Ok(output.into()))
+    }
+
+    /// Answer `adb devices -l` with `output`
+    pub fn respond_devices(&self, output: impl Into<String>) -> &Self {
+        self.respond(AdbRequest::Devices, output)
+    }
+
+    /// Every request received so far, in order
+    pub fn calls(&self) -> Vec<AdbRequest> {
+        self.script().calls.clone()
+    }
+
+    fn answer(&self, request: AdbRequest) -> Result<String, AdbError> {
+        let mut script = self.script();
+        let response = script
+            .responses
+            .iter()
+            .rev()
+            .find(|(matcher, _)| matcher.matches(&request))
+            .map(|(_, response)| response.clone())
+            .unwrap_or_else(|| {
+                Err(AdbError::Failed(format!(
+                    "no scripted response for {:?}",
+                    request
+                )))
+            });
+        script.calls.push(request);
+        response
+    }
+}
+
+impl AdbExecutor for ScriptedExecutor {
+    fn devices(&self) -> Result<String, AdbError> {
+        self.answer(AdbRequest::Devices)
+    }
+
+    fn shell(&self, target: &AdbTarget, command: &str) -> Result<String, AdbError> {
+        self.answer(AdbRequest::Shell {
+            target: target.clone(),
+            command: command.to_string(),
+        })
+    }
+
+    fn connect(&self, address: &str) -> Result<String, AdbError> {
+        self.answer(AdbRequest::Connect(address.to_string()))
+    }
+
+    fn disconnect(&self, address: &str) -> Result<String, AdbError> {
+        self.answer(AdbRequest::Disconnect(address.to_string()))
+    }
+
+    fn pair(&self, address: &str, code: &str) -> Result<String, AdbError> {
+        self.answer(AdbRequest::Pair {
+            address: address.to_string(),
+            code: code.to_string(),
+        })
+    }
+
+    fn tcpip(&self, target: &AdbTarget, port: u16) -> Result<String, AdbError> {
+        self.answer(AdbRequest::Tcpip {
+            target: target.clone(),
+            port,
+        })
+    }
+
+    fn mdns_services(&self) -> Result<String, AdbError> {
+        self.answer(AdbRequest::MdnsServices)
+    }
+
+    fn with_timeout(&self, _timeout: Duration) -> SharedAdbExecutor {
+        Arc::new(self.clone())
+    }
+
+    fn server_version(&self) -> Result<u32, AdbError> {
+        let output = self.answer(AdbRequest::ServerVersion)?;
+        output
+            .trim()
+            .parse()
+            .map_err(|_| AdbError::Protocol(format!("Invalid version response: {}", output)))
+    }
+
+    fn client_version(&self) -> Result<String, AdbError> {
+        self.answer(AdbRequest::ClientVersion)
+    }
+
+    fn start_server(&self) -> Result<(), AdbError> {
+        self.answer(AdbRequest::StartServer).map(|_| ())
+    }
+
+    fn kill_server(&self) -> Result<(), AdbError> {
+        self.answer(AdbRequest::KillServer).map(|_| ())
+    }
+
+    fn server_port(&self) -> u16 {
+        DEFAULT_ADB_SERVER_PORT
+    }
+
+    fn stopped_by_user(&self) -> bool {
+        self.script().stopped_by_user
+    }
+
+    fn set_stopped_by_user(&self, stopped: bool) {
+        self.script().stopped_by_user = stopped;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn serial(s: &str) -> AdbTarget {
+        AdbTarget::Serial(s.to_string())
+    }
+
+    #[test]
+    fn scripted_answers_and_records_calls() {
+        let adb = ScriptedExecutor::new();
+        adb.respond_devices("abc123\tdevice\n")
+            .respond_shell("getprop ro.product.model", "Pixel 7\n")
+            .respond(
+                AdbRequest::Shell {
+                    target: serial("xyz"),
+                    command: "getprop ro.product.model".to_string(),
+                },
+                "Galaxy\n",
+            );
+
+        assert_eq!(adb.devices().unwrap(), "abc123\tdevice\n");
+        assert_eq!(
+            adb.shell(&serial("abc123"), "getprop ro.product.model")
+                .unwrap(),
+            "Pixel 7\n"
+        );
+        assert_eq!(
+            adb.shell(&serial("xyz"), "getprop ro.product.model")
+                .unwrap(),
+            "Galaxy\n"
+        );
+        assert_eq!(adb.calls().len(), 3);
+        assert_eq!(adb.calls()[0], AdbRequest::Devices);
+    }
+
+    #[test]
+    fn scripted_latest_response_wins_and_shares_state() {
+        let adb = ScriptedExecutor::new();
+        let shared: SharedAdbExecutor = Arc::new(adb.clone());
+        adb.respond_devices("abc123\tdevice\n");
+        adb.fail(
+            AdbRequest::Connect("10.0.0.2:5555".to_string()),
+            AdbError::Timeout("slow".to_string()),
+        );
+
+        assert_eq!(shared.devices().unwrap(), "abc123\tdevice\n");
+        adb.respond_devices("abc123\toffline\n");
+        assert_eq!(shared.devices().unwrap(), "abc123\toffline\n");
+        assert!(matches!(
+            shared.connect("10.0.0.2:5555"),
+            Err(AdbError::Timeout(_))
+        ));
+        assert_eq!(adb.calls().len(), 3);
+    }
+
+    #[test]
+    fn scripted_unknown_request_fails() {
+        let adb = ScriptedExecutor::new();
+        let error = adb.mdns_services().unwrap_err();
+        assert!(error.to_string().contains("no scripted response"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn process_executor_builds_binary_arguments() {
+        use std::os::unix::fs::PermissionsExt;
+
+        // Fake adb that echoes its arguments
+        let adb = std::env::temp_dir().join(format!("scrcpy-gui-echo-adb-{}", std::process::id()));
+        std::fs::write(&adb, "#!/bin/sh\necho \"$@\"\n").unwrap();
+        std::fs::set_permissions(&adb, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let executor = ProcessExecutor::new(&adb);
+        assert_eq!(
+            executor
+                .shell(&AdbTarget::TransportId(7), "echo ok")
+                .unwrap(),
+            "-t 7 shell echo ok\n"
+        );
+        assert_eq!(
+            executor.tcpip(&serial("abc123"), 5555).unwrap(),
+            "-s abc123 tcpip 5555\n"
+        );
+        assert_eq!(executor.devices().unwrap(), "devices -l\n");
+        let _ = std::fs::remove_file(adb);
+    }
+}
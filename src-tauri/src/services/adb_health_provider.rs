@@ -2,26 +2,188 @@
 //!
 //! Handles all ADB command execution for device health data collection.
 //! Responsible for:
-//! - Battery info (percentage, temperature, status)
+//! - Battery info (percentage, temperature, status, health, charger,
+//!   voltage, charge counter, current)
 //! - Storage info (used, total, free)
 //! - Device info (model, Android version, build)
 //! - Connection latency measurement
+//!
+//! Polling uses `query_health`, which runs the probes enabled by the
+//! `collect_*` flags in a single shell invocation. Each probe's output is
+//! preceded by a sentinel line so the combined output can be split back into
+//! sections.
 
+use crate::services::adb_client::{AdbClient, AdbError, AdbTarget};
+use crate::services::adb_executor::SharedAdbExecutor;
 use crate::types::*;
-use std::process::Command;
+use std::collections::HashMap;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 
+/// Sentinel lines separating batched probe output: `@@scrcpy-gui:<section>@@`
+const SECTION_PREFIX: &str = "@@scrcpy-gui:";
+const SECTION_SUFFIX: &str = "@@";
+
+/// Probes run on every poll: (section, shell command)
+const DYNAMIC_PROBES: [(&str, &str); 2] = [("battery", "dumpsys battery"), ("storage", "df /data")];
+
+/// Battery current, read from sysfs since `dumpsys battery` doesn't report it
+const CURRENT_PROBE: (&str, &str) = ("current", "cat /sys/class/power_supply/battery/current_now");
+
+/// Probes whose answers don't change while a device stays connected
+const STATIC_PROBES: [(&str, &str); 3] = [
+    ("model", "getprop ro.product.model"),
+    ("version", "getprop ro.build.version.release"),
+    ("build", "getprop ro.build.id"),
+];
+
+/// Device clock (ns) at the start and end of the batch
+const CLOCK_PROBE: &str = "date +%s%N";
+
+/// Everything collected by one batched health query
+#[derive(Debug, Clone)]
+pub struct HealthSnapshot {
+    pub battery: Option<BatteryInfo>,
+    pub storage: Option<StorageInfo>,
+    /// Only present when static info was requested
+    pub device: Option<DeviceInfo>,
+    /// Round trip minus the time the probes spent running on the device
+    pub latency: u32,
+}
+
+/// Which probes one batched health query runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HealthQuery {
+    pub battery: bool,
+    pub storage: bool,
+    /// Static model/version/build info
+    pub device_info: bool,
+    /// Device clock readings, to take on-device time out of the latency
+    pub latency: bool,
+}
+
+impl HealthQuery {
+    /// Every probe, with or without the static ones
+    pub fn all(include_static: bool) -> Self {
+        Self {
+            battery: true,
+            storage: true,
+            device_info: include_static,
+            latency: true,
+        }
+    }
+
+    /// The probes enabled by the config's `collect_*` flags; static info only
+    /// when it isn't cached yet
+    pub fn from_config(config: &HealthPollingConfig, include_static: bool) -> Self {
+        Self {
+            battery: config.collect_battery,
+            storage: config.collect_storage,
+            device_info: config.collect_device_info && include_static,
+            latency: config.collect_connection_metrics,
+        }
+    }
+
+    /// The single shell command running the probes, each behind a sentinel.
+    ///
+    /// With every probe disabled the query still checks that the device
+    /// answers.
+    pub fn script(&self) -> String {
+        let [battery, storage] = DYNAMIC_PROBES;
+        let mut probes = Vec::new();
+        if self.latency {
+            probes.push(("clock_start", CLOCK_PROBE));
+        }
+        if self.battery {
+            probes.push(battery);
+            probes.push(CURRENT_PROBE);
+        }
+        if self.storage {
+            probes.push(storage);
+        }
+        if self.device_info {
+            probes.extend(STATIC_PROBES);
+        }
+        if self.latency {
+            probes.push(("clock_end", CLOCK_PROBE));
+        }
+
+        let mut script: Vec<String> = probes
+            .iter()
+            .map(|(section, command)| {
+                format!(
+                    "echo '{}{}{}'; {} 2>&1",
+                    SECTION_PREFIX, section, SECTION_SUFFIX, command
+                )
+            })
+            .collect();
+        script.push(format!("echo '{}end{}'", SECTION_PREFIX, SECTION_SUFFIX));
+        script.join("; ")
+    }
+}
+
+/// Build the single shell command running every probe, each behind a sentinel
+pub fn build_health_query(include_static: bool) -> String {
+    HealthQuery::all(include_static).script()
+}
+
+/// Split batched output into `section -> output`.
+///
+/// Fails unless the closing sentinel arrived, so a truncated answer is never
+/// mistaken for a device with missing data.
+pub fn parse_health_sections(output: &str) -> Result<HashMap<String, String>, String> {
+    let mut sections: HashMap<String, String> = HashMap::new();
+    let mut current: Option<String> = None;
+    let mut complete = false;
+
+    for line in output.lines() {
+        let line = line.trim_end_matches('\r');
+        let marker = line
+            .trim()
+            .strip_prefix(SECTION_PREFIX)
+            .and_then(|rest| rest.strip_suffix(SECTION_SUFFIX));
+        if let Some(section) = marker {
+            if section == "end" {
+                complete = true;
+                break;
+            }
+            current = Some(section.to_string());
+            sections.entry(section.to_string()).or_default();
+        } else if let Some(section) = &current {
+            let text = sections.entry(section.clone()).or_default();
+            text.push_str(line);
+            text.push('\n');
+        }
+    }
+
+    if !complete {
+        return Err("Incomplete health query output".to_string());
+    }
+    Ok(sections)
+}
+
 /// ADB Health Provider
 ///
 /// Executes ADB commands to collect device health metrics.
-/// All commands timeout after the configured query_timeout (default 500ms).
+/// All commands timeout after the configured query_timeout (default 500ms);
+/// a command still running at that point is abandoned and its process killed.
 pub struct AdbHealthProvider {
-    query_timeout_ms: u32,
+    executor: SharedAdbExecutor,
 }
 
 impl AdbHealthProvider {
     pub fn new(query_timeout_ms: u32) -> Self {
-        Self { query_timeout_ms }
+        Self::with_executor(
+            query_timeout_ms,
+            &(Arc::new(AdbClient::new()) as SharedAdbExecutor),
+        )
+    }
+
+    /// Provider running its queries through `executor`, with the query timeout applied
+    pub fn with_executor(query_timeout_ms: u32, executor: &SharedAdbExecutor) -> Self {
+        Self {
+            executor: executor.with_timeout(Duration::from_millis(query_timeout_ms as u64)),
+        }
     }
 
     /// Execute an ADB command with timeout protection
@@ -31,35 +193,12 @@ impl AdbHealthProvider {
     /// * `cmd` - Shell command to execute
     ///
     /// # Returns
-    /// Ok(output) if command succeeds, Err(message) if timeout or error occurs
-    pub fn run_adb_command(&self, device_id: &str, cmd: &str) -> Result<String, String> {
-        let start = Instant::now();
-        let timeout = Duration::from_millis(self.query_timeout_ms as u64);
-
-        match Command::new("adb")
-            .args(["-s", device_id, "shell", cmd])
-            .output()
-        {
-            Ok(output) => {
-                let elapsed = start.elapsed();
-
-                if elapsed > timeout {
-                    return Err(format!(
-                        "Command timeout: took {}ms (limit {}ms)",
-                        elapsed.as_millis(),
-                        self.query_timeout_ms
-                    ));
-                }
-
-                if !output.status.success() {
-                    let stderr = String::from_utf8_lossy(&output.stderr).to_string();
-                    return Err(format!("ADB command failed: {}", stderr));
-                }
-
-                Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
-            }
-            Err(e) => Err(format!("Failed to execute ADB command: {}", e)),
-        }
+    /// Ok(output) if command succeeds, `Err(AdbError::Timeout)` if it did not
+    /// finish within the query timeout, or another `AdbError`
+    pub fn run_adb_command(&self, device_id: &str, cmd: &str) -> Result<String, AdbError> {
+        let target = AdbTarget::Serial(device_id.to_string());
+        let output = self.executor.shell(&target, cmd)?;
+        Ok(output.trim().to_string())
     }
 
     /// Get battery information from device
@@ -67,20 +206,35 @@ impl AdbHealthProvider {
     /// Uses `adb shell dumpsys battery` to extract:
     /// - Percentage (0-100)
     /// - Temperature (Celsius)
-    /// - Charging status
+    /// - Charging status, health and charger source
+    /// - Voltage, charge counter and max charging current
     pub fn get_battery_info(&self, device_id: &str) -> Result<BatteryInfo, String> {
-        let output = self.run_adb_command(device_id, "dumpsys battery")?;
+        let output = self
+            .run_adb_command(device_id, "dumpsys battery")
+            .map_err(|e| e.to_string())?;
+        self.parse_battery_info(&output)
+    }
 
-        let percentage = self.parse_battery_percentage(&output)?;
-        let temperature = self.parse_battery_temperature(&output).ok();
-        let is_charging = self.parse_battery_charging_status(&output).ok();
-        let health = self.parse_battery_health(&output).ok();
+    /// Parse full `dumpsys battery` output
+    fn parse_battery_info(&self, output: &str) -> Result<BatteryInfo, String> {
+        let percentage = self.parse_battery_percentage(output)?;
+        let temperature = self.parse_battery_temperature(output).ok();
+        let is_charging = self.parse_battery_charging_status(output).ok();
+        let health = self.parse_battery_health(output).ok();
+        let charge_counter = dumpsys_field(output, "Charge counter").and_then(|v| v.parse().ok());
+        let max_charging_current =
+            dumpsys_field(output, "Max charging current").and_then(|v| v.parse().ok());
 
         Ok(BatteryInfo {
             percentage,
             temperature,
             is_charging,
             health,
+            charge_counter,
+            current_now: None,
+            charger: parse_charger_source(output),
+            voltage: parse_voltage(output),
+            max_charging_current,
         })
     }
 
@@ -133,46 +287,56 @@ impl AdbHealthProvider {
     }
 
     /// Parse battery charging status from dumpsys battery output
+    ///
+    /// AOSP prints the `BATTERY_STATUS_*` constant: 2 = charging and 5 = full
+    /// (still on the charger), 3 = discharging and 4 = not charging. 1 is
+    /// unknown. Some OEM builds print the name instead.
     fn parse_battery_charging_status(&self, output: &str) -> Result<bool, String> {
-        for line in output.lines() {
-            if line.contains("status:") {
-                let status = line
-                    .split("status:")
-                    .nth(1)
-                    .ok_or("Could not parse charging status")?
-                    .trim();
-                return Ok(status != "2" && status != "Discharging"); // 2 = Discharging
-            }
+        let status = dumpsys_field(output, "status").ok_or("Charging status not found")?;
+        let name: String = status
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric())
+            .collect::<String>()
+            .to_lowercase();
+
+        match name.as_str() {
+            "2" | "charging" | "5" | "full" => Ok(true),
+            "3" | "discharging" | "4" | "notcharging" => Ok(false),
+            _ => Err(format!("Unknown charging status: {}", status)),
         }
-        Err("Charging status not found".to_string())
     }
 
     /// Parse battery health from dumpsys battery output
+    ///
+    /// AOSP prints the `BATTERY_HEALTH_*` constant (1-7); some OEM builds
+    /// print its name instead ("Over voltage", "UNSPECIFIED_FAILURE", ...).
     fn parse_battery_health(&self, output: &str) -> Result<BatteryHealth, String> {
-        for line in output.lines() {
-            if line.contains("health:") {
-                let health = line
-                    .split("health:")
-                    .nth(1)
-                    .ok_or("Could not parse health")?
-                    .trim();
-
-                return match health {
-                    "2" | "Good" => Ok(BatteryHealth::Good),
-                    "3" | "Overheat" => Ok(BatteryHealth::Overheat),
-                    // Treat warm/unknown as Good for simplicity
-                    _ => Ok(BatteryHealth::Good),
-                };
-            }
-        }
-        Err("Battery health not found".to_string())
+        let health = dumpsys_field(output, "health").ok_or("Battery health not found")?;
+        let name: String = health
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric())
+            .collect::<String>()
+            .to_lowercase();
+
+        Ok(match name.as_str() {
+            "2" | "good" => BatteryHealth::Good,
+            "3" | "overheat" => BatteryHealth::Overheat,
+            "4" | "dead" => BatteryHealth::Dead,
+            "5" | "overvoltage" => BatteryHealth::OverVoltage,
+            "6" | "unspecifiedfailure" => BatteryHealth::UnspecifiedFailure,
+            "7" | "cold" => BatteryHealth::Cold,
+            "warm" => BatteryHealth::Warm,
+            _ => BatteryHealth::Unknown,
+        })
     }
 
     /// Get storage information from device
     ///
     /// Uses `adb shell df /data` to extract storage metrics
     pub fn get_storage_info(&self, device_id: &str) -> Result<StorageInfo, String> {
-        let output = self.run_adb_command(device_id, "df /data")?;
+        let output = self
+            .run_adb_command(device_id, "df /data")
+            .map_err(|e| e.to_string())?;
         self.parse_storage_info(&output)
     }
 
@@ -222,10 +386,15 @@ impl AdbHealthProvider {
 
     /// Get device information (model, Android version, build number)
     pub fn get_device_info(&self, device_id: &str) -> Result<DeviceInfo, String> {
-        let model_name = self.run_adb_command(device_id, "getprop ro.product.model")?;
-        let android_version =
-            self.run_adb_command(device_id, "getprop ro.build.version.release")?;
-        let build_number = self.run_adb_command(device_id, "getprop ro.build.id")?;
+        let model_name = self
+            .run_adb_command(device_id, "getprop ro.product.model")
+            .map_err(|e| e.to_string())?;
+        let android_version = self
+            .run_adb_command(device_id, "getprop ro.build.version.release")
+            .map_err(|e| e.to_string())?;
+        let build_number = self
+            .run_adb_command(device_id, "getprop ro.build.id")
+            .map_err(|e| e.to_string())?;
 
         Ok(DeviceInfo {
             model_name,
@@ -241,18 +410,128 @@ impl AdbHealthProvider {
         let start = Instant::now();
 
         // Use 'echo' as a lightweight command for latency measurement
-        self.run_adb_command(device_id, "echo ok")?;
+        self.run_adb_command(device_id, "echo ok")
+            .map_err(|e| e.to_string())?;
 
         let elapsed = start.elapsed().as_millis() as u32;
         Ok(elapsed)
     }
 
+    /// Collect the probes selected by `query` in one shell round trip
+    pub fn query_health(
+        &self,
+        device_id: &str,
+        query: &HealthQuery,
+    ) -> Result<HealthSnapshot, AdbError> {
+        let start = Instant::now();
+        let output = self.run_adb_command(device_id, &query.script())?;
+        let round_trip = start.elapsed().as_millis() as u32;
+
+        let sections = parse_health_sections(&output).map_err(AdbError::Protocol)?;
+        let section = |name: &str| sections.get(name).map(|s| s.trim()).unwrap_or("");
+
+        let battery = query
+            .battery
+            .then(|| self.parse_battery_info(section("battery")).ok())
+            .flatten()
+            .map(|mut battery| {
+                battery.current_now = parse_current_now(section("current"), battery.is_charging);
+                battery
+            });
+        let storage = query
+            .storage
+            .then(|| self.parse_storage_info(section("storage")).ok())
+            .flatten();
+        let device = query
+            .device_info
+            .then(|| parse_device_info(section("model"), section("version"), section("build")))
+            .flatten();
+
+        // Time spent running the probes on the device isn't link latency
+        let on_device_ms = match (
+            section("clock_start").parse::<u64>(),
+            section("clock_end").parse::<u64>(),
+        ) {
+            (Ok(start), Ok(end)) => (end.saturating_sub(start) / 1_000_000) as u32,
+            _ => 0,
+        };
+
+        Ok(HealthSnapshot {
+            battery,
+            storage,
+            device,
+            latency: round_trip.saturating_sub(on_device_ms),
+        })
+    }
+
     /// Derive quality level from latency
     pub fn derive_quality_level(&self, latency: u32) -> QualityLevel {
         derive_quality_level(latency)
     }
 }
 
+/// Value of a `key: value` line of dumpsys output.
+///
+/// The whole key must match, so `voltage` doesn't pick up
+/// `Max charging voltage`.
+fn dumpsys_field<'a>(output: &'a str, key: &str) -> Option<&'a str> {
+    output.lines().find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        (name.trim() == key).then(|| value.trim())
+    })
+}
+
+/// Charger from the `<source> powered` flags; `None` if none are reported
+fn parse_charger_source(output: &str) -> Option<ChargerSource> {
+    let sources = [
+        ("AC powered", ChargerSource::Ac),
+        ("USB powered", ChargerSource::Usb),
+        ("Wireless powered", ChargerSource::Wireless),
+        ("Dock powered", ChargerSource::Dock),
+    ];
+    let mut reported = false;
+    for (key, source) in sources {
+        match dumpsys_field(output, key) {
+            Some("true") => return Some(source),
+            Some(_) => reported = true,
+            None => {}
+        }
+    }
+    reported.then_some(ChargerSource::Unplugged)
+}
+
+/// Battery voltage in mV; some OEM builds report µV
+fn parse_voltage(output: &str) -> Option<u32> {
+    let value: u32 = dumpsys_field(output, "voltage")?.parse().ok()?;
+    match value {
+        0 => None,
+        uv if uv > 100_000 => Some(uv / 1000),
+        mv => Some(mv),
+    }
+}
+
+/// Parse sysfs `current_now` into µA, positive while charging.
+///
+/// The power_supply ABI specifies µA, but kernels disagree on the sign, so
+/// the sign comes from the charging state.
+fn parse_current_now(output: &str, is_charging: Option<bool>) -> Option<i64> {
+    let magnitude = output.trim().parse::<i64>().ok()?.abs();
+    Some(if is_charging? { magnitude } else { -magnitude })
+}
+
+/// Static device info from the three getprop sections; `None` if the model
+/// is missing (the device didn't answer)
+fn parse_device_info(model: &str, version: &str, build: &str) -> Option<DeviceInfo> {
+    if model.is_empty() {
+        return None;
+    }
+    Some(DeviceInfo {
+        model_name: model.to_string(),
+        android_version: version.to_string(),
+        build_number: build.to_string(),
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -279,6 +558,39 @@ mod tests {
         assert_eq!(percentage, 75);
     }
 
+    #[test]
+    fn test_parse_battery_charging_status() {
+        let provider = AdbHealthProvider::new(500);
+        let charging = |status: &str| {
+            provider
+                .parse_battery_charging_status(&format!("  status: {}\n", status))
+                .ok()
+        };
+        assert_eq!(charging("2"), Some(true));
+        assert_eq!(charging("5"), Some(true));
+        assert_eq!(charging("3"), Some(false));
+        assert_eq!(charging("4"), Some(false));
+        assert_eq!(charging("1"), None);
+        assert_eq!(charging("Charging"), Some(true));
+        assert_eq!(charging("Not charging"), Some(false));
+        assert_eq!(charging("DISCHARGING"), Some(false));
+        assert_eq!(charging("Full"), Some(true));
+    }
+
+    #[test]
+    fn test_parse_current_now_normalizes_sign_and_unit() {
+        assert_eq!(parse_current_now("-350000", Some(true)), Some(350_000));
+        assert_eq!(parse_current_now("350000", Some(false)), Some(-350_000));
+        // Trickle charging near full: a few mA, still in µA
+        assert_eq!(parse_current_now("4200", Some(true)), Some(4_200));
+        assert_eq!(parse_current_now("-850", Some(true)), Some(850));
+        assert_eq!(parse_current_now("350000", None), None);
+        assert_eq!(
+            parse_current_now("cat: current_now: No such file", Some(true)),
+            None
+        );
+    }
+
     #[test]
     fn test_parse_battery_percentage_invalid() {
         let provider = AdbHealthProvider::new(500);
@@ -305,6 +617,163 @@ mod tests {
         assert_eq!(info.used, 85256 * 1024);
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn test_query_timeout_is_enforced() {
+        use std::net::TcpListener;
+        use std::os::unix::fs::PermissionsExt;
+
+        // Slow fake adb behind an unreachable server forces the binary path
+        let adb =
+            std::env::temp_dir().join(format!("scrcpy-gui-hung-dumpsys-{}", std::process::id()));
+        std::fs::write(&adb, "#!/bin/sh\nexec sleep 5\n").unwrap();
+        std::fs::set_permissions(&adb, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let addr = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let client: SharedAdbExecutor =
+            Arc::new(AdbClient::with_server_addr(addr).with_adb_path(&adb));
+        let provider = AdbHealthProvider::with_executor(500, &client);
+
+        let start = Instant::now();
+        let result = provider.run_adb_command("abc123", "dumpsys battery");
+        assert!(matches!(result, Err(AdbError::Timeout(_))), "{:?}", result);
+        assert!(start.elapsed() < Duration::from_millis(1500));
+        assert!(provider
+            .get_battery_info("abc123")
+            .unwrap_err()
+            .contains("timed out"));
+        let _ = std::fs::remove_file(adb);
+    }
+
+    #[test]
+    fn test_queries_go_through_executor() {
+        use crate::services::adb_executor::{AdbRequest, ScriptedExecutor};
+
+        let adb = ScriptedExecutor::new();
+        adb.respond_shell(
+            "dumpsys battery",
+            "  level: 42\n  temperature: 310\n  status: 2\n",
+        )
+        .respond_shell("getprop ro.product.model", "Pixel 7\n")
+        .respond_shell("getprop ro.build.version.release", "14\n")
+        .respond_shell("getprop ro.build.id", "UQ1A\n");
+        let provider =
+            AdbHealthProvider::with_executor(500, &(Arc::new(adb.clone()) as SharedAdbExecutor));
+
+        let battery = provider.get_battery_info("abc123").unwrap();
+        assert_eq!(battery.percentage, 42);
+        assert_eq!(battery.temperature, Some(31));
+        assert_eq!(battery.is_charging, Some(true));
+
+        let info = provider.get_device_info("abc123").unwrap();
+        assert_eq!(info.model_name, "Pixel 7");
+        assert_eq!(info.android_version, "14");
+
+        assert!(provider.get_storage_info("abc123").is_err());
+        assert_eq!(
+            adb.calls()[0],
+            AdbRequest::Shell {
+                target: AdbTarget::Serial("abc123".to_string()),
+                command: "dumpsys battery".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_health_query_includes_static_probes_on_request() {
+        let full = build_health_query(true);
+        assert!(full.starts_with("echo '@@scrcpy-gui:clock_start@@'; date +%s%N 2>&1"));
+        assert!(full.contains("echo '@@scrcpy-gui:battery@@'; dumpsys battery 2>&1"));
+        assert!(full.contains("getprop ro.build.id"));
+        assert!(full.ends_with("echo '@@scrcpy-gui:end@@'"));
+        assert!(!build_health_query(false).contains("getprop"));
+    }
+
+    #[test]
+    fn test_health_query_honors_collect_flags() {
+        let config = HealthPollingConfig {
+            collect_storage: false,
+            collect_connection_metrics: false,
+            collect_device_info: false,
+            ..HealthPollingConfig::default()
+        };
+        let script = HealthQuery::from_config(&config, true).script();
+        assert_eq!(
+            script,
+            "echo '@@scrcpy-gui:battery@@'; dumpsys battery 2>&1; \
+             echo '@@scrcpy-gui:current@@'; cat /sys/class/power_supply/battery/current_now 2>&1; \
+             echo '@@scrcpy-gui:end@@'"
+        );
+
+        // Nothing to collect still makes a liveness check
+        let none = HealthQuery {
+            battery: false,
+            storage: false,
+            device_info: false,
+            latency: false,
+        };
+        assert_eq!(none.script(), "echo '@@scrcpy-gui:end@@'");
+    }
+
+    #[test]
+    fn test_parse_health_sections() {
+        let output = "@@scrcpy-gui:battery@@\r\n  level: 75\r\n  status: 2\r\n\
+                      @@scrcpy-gui:model@@\nPixel 7\n@@scrcpy-gui:end@@\n";
+        let sections = parse_health_sections(output).unwrap();
+        assert_eq!(sections["battery"], "  level: 75\n  status: 2\n");
+        assert_eq!(sections["model"], "Pixel 7\n");
+
+        // Truncated output is an error, not a device without storage
+        assert!(parse_health_sections("@@scrcpy-gui:battery@@\n  level: 75\n").is_err());
+    }
+
+    #[test]
+    fn test_query_health_single_round_trip() {
+        use crate::services::adb_executor::ScriptedExecutor;
+
+        let adb = ScriptedExecutor::new();
+        adb.respond_shell(
+            &build_health_query(true),
+            "@@scrcpy-gui:clock_start@@\n1700000000000000000\n\
+             @@scrcpy-gui:battery@@\n  status: 3\n  Charge counter: 2400000\n\
+             level: 42\n  temperature: 310\n@@scrcpy-gui:current@@\n350000\n\
+             @@scrcpy-gui:storage@@\nFilesystem 1K-blocks Used Available Use% Mounted on\n\
+             /dev/block/dm-5 2048 1024 1024 50% /data\n\
+             @@scrcpy-gui:model@@\nPixel 7\n@@scrcpy-gui:version@@\n14\n\
+             @@scrcpy-gui:build@@\nUQ1A\n\
+             @@scrcpy-gui:clock_end@@\n1700000000250000000\n@@scrcpy-gui:end@@\n",
+        );
+        let provider =
+            AdbHealthProvider::with_executor(500, &(Arc::new(adb.clone()) as SharedAdbExecutor));
+
+        let snapshot = provider
+            .query_health("abc123", &HealthQuery::all(true))
+            .unwrap();
+        let battery = snapshot.battery.unwrap();
+        assert_eq!(battery.percentage, 42);
+        assert_eq!(battery.charge_counter, Some(2_400_000));
+        // Discharging, whatever sign the kernel reports
+        assert_eq!(battery.current_now, Some(-350_000));
+        assert_eq!(snapshot.storage.unwrap().total, 2048 * 1024);
+        assert_eq!(snapshot.device.unwrap().android_version, "14");
+        // 250ms of on-device time is subtracted from the instant round trip
+        assert_eq!(snapshot.latency, 0);
+        assert_eq!(adb.calls().len(), 1);
+
+        // Without static probes the device info is left to the cache
+        adb.respond_shell(
+            &build_health_query(false),
+            "@@scrcpy-gui:battery@@\n  level: 41\n@@scrcpy-gui:end@@\n",
+        );
+        let snapshot = provider
+            .query_health("abc123", &HealthQuery::all(false))
+            .unwrap();
+        assert!(snapshot.device.is_none());
+        assert!(snapshot.storage.is_none());
+    }
+
     #[test]
     fn test_derive_quality_level() {
         let provider = AdbHealthProvider::new(500);
@@ -0,0 +1,463 @@
+//! ADB Server Lifecycle
+//!
+//! Queries, starts, kills and restarts the ADB host server, and detects when
+//! the server listening on port 5037 speaks a different protocol version than
+//! the `adb` binary on PATH (another SDK's adb took over the port).
+//!
+//! Restarts, whether requested from the UI or detected while polling, bump a
+//! process-wide generation counter. The device tracker resubscribes as soon as
+//! the generation changes, and the health poller retries server errors for
+//! free once the server is back.
+//!
+//! A server killed from the UI stays down: nothing restarts it automatically,
+//! and the binary fallback (which would start it on demand) is disabled,
+//! until the user starts or restarts it again. That state lives on the
+//! executor (`AdbExecutor::stopped_by_user`), so every clone of the managed
+//! executor sees it.
+
+use crate::error::{AppError, AppResult};
+use crate::services::adb_client::run_blocking;
+use crate::services::adb_executor::{AdbExecutor, SharedAdbExecutor};
+use crate::types::health::ErrorCode;
+use chrono::Utc;
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Restarts noticed within this long of the last one count as the same restart
+pub const RESTART_GRACE: Duration = Duration::from_secs(10);
+
+/// How long to wait for a freshly started server to answer
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long to wait for a killed server to release its port
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+const PROBE_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Pause before retrying an operation that failed because of a restart
+pub const RECOVERY_SETTLE: Duration = Duration::from_millis(1000);
+
+/// Free retries a poller gets per server generation; a server error that
+/// persists past them goes through the normal retry backoff
+const MAX_FREE_RETRIES: u32 = 1;
+
+/// Incremented on every known server (re)start
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Unix time (ms) of the last known server (re)start
+static LAST_RESTART_MS: AtomicU64 = AtomicU64::new(0);
+
+/// Serializes start/kill/restart so concurrent pollers don't race each other
+static LIFECYCLE_LOCK: Mutex<()> = Mutex::new(());
+
+/// `adb version` of the client binary on PATH
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AdbClientVersion {
+    /// Internal protocol version (`41` in `1.0.41`), comparable to `host:version`
+    pub protocol: u32,
+    /// Platform-tools release, e.g. `35.0.2-12147458`
+    pub release: Option<String>,
+    /// Path of the binary (`Installed as ...`)
+    pub path: Option<String>,
+}
+
+/// Result of `get_adb_server_status`
+#[derive(Debug, Clone, Serialize)]
+pub struct AdbServerStatus {
+    pub running: bool,
+    pub port: u16,
+    /// Protocol version reported by the running server
+    pub server_version: Option<u32>,
+    pub client: Option<AdbClientVersion>,
+    /// The running server and the binary on PATH disagree; the next binary
+    /// call will kill and replace the server
+    pub version_mismatch: bool,
+    /// Restarts observed by this app since launch
+    pub generation: u64,
+    /// Unix time (ms) of the last observed restart
+    pub last_restart_at: Option<u64>,
+}
+
+/// Parse `adb version` output
+///
+/// ```text
+/// Android Debug Bridge version 1.0.41
+/// Version 35.0.2-12147458
+/// Installed as /opt/platform-tools/adb
+/// ```
+pub fn parse_client_version(output: &str) -> Option<AdbClientVersion> {
+    let mut protocol = None;
+    let mut release = None;
+    let mut path = None;
+
+    for line in output.lines().map(str::trim) {
+        if let Some(rest) = line.strip_prefix("Android Debug Bridge version ") {
+            protocol = rest.trim().rsplit('.').next().and_then(|v| v.parse().ok());
+        } else if let Some(rest) = line.strip_prefix("Version ") {
+            release = Some(rest.trim().to_string());
+        } else if let Some(rest) = line.strip_prefix("Installed as ") {
+            path = Some(rest.trim().to_string());
+        }
+    }
+
+    Some(AdbClientVersion {
+        protocol: protocol?,
+        release,
+        path,
+    })
+}
+
+/// Whether the server and client binary disagree (unknown sides never mismatch)
+pub fn is_version_mismatch(server: Option<u32>, client: Option<&AdbClientVersion>) -> bool {
+    matches!((server, client), (Some(server), Some(client)) if server != client.protocol)
+}
+
+/// Number of restarts observed so far
+pub fn generation() -> u64 {
+    GENERATION.load(Ordering::SeqCst)
+}
+
+/// Record a server (re)start
+pub fn mark_restarted() {
+    LAST_RESTART_MS.store(Utc::now().timestamp_millis() as u64, Ordering::SeqCst);
+    GENERATION.fetch_add(1, Ordering::SeqCst);
+}
+
+/// Whether `now_ms` falls within the grace period after `restart_ms`
+fn within_grace(restart_ms: u64, now_ms: u64) -> bool {
+    restart_ms != 0 && now_ms.saturating_sub(restart_ms) < RESTART_GRACE.as_millis() as u64
+}
+
+/// Whether the server was (re)started within the last `RESTART_GRACE`
+pub fn in_restart_grace() -> bool {
+    within_grace(
+        LAST_RESTART_MS.load(Ordering::SeqCst),
+        Utc::now().timestamp_millis() as u64,
+    )
+}
+
+/// Free retries one poller has taken in the current server generation
+#[derive(Debug, Default)]
+pub struct FreeRetries {
+    generation: u64,
+    taken: u32,
+}
+
+impl FreeRetries {
+    /// Whether another free retry is allowed in `generation`
+    pub fn available(&self, generation: u64) -> bool {
+        self.generation != generation || self.taken < MAX_FREE_RETRIES
+    }
+
+    /// Record a free retry taken in `generation`
+    pub fn take(&mut self, generation: u64) {
+        if self.generation != generation {
+            self.generation = generation;
+            self.taken = 0;
+        }
+        self.taken += 1;
+    }
+}
+
+fn lifecycle_lock() -> std::sync::MutexGuard<'static, ()> {
+    LIFECYCLE_LOCK.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Poll `host:version` until `ready(result)` holds or `timeout` expires
+fn wait_for(adb: &dyn AdbExecutor, timeout: Duration, ready: impl Fn(bool) -> bool) -> bool {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if ready(adb.server_version().is_ok()) {
+            return true;
+        }
+        if Instant::now() >= deadline {
+            return false;
+        }
+        std::thread::sleep(PROBE_INTERVAL);
+    }
+}
+
+/// Current server and client state (blocking)
+pub fn status(adb: &dyn AdbExecutor) -> AdbServerStatus {
+    let server_version = adb.server_version().ok();
+    let client_version = adb
+        .client_version()
+        .ok()
+        .and_then(|output| parse_client_version(&output));
+    let last_restart = LAST_RESTART_MS.load(Ordering::SeqCst);
+
+    AdbServerStatus {
+        running: server_version.is_some(),
+        port: adb.server_port(),
+        version_mismatch: is_version_mismatch(server_version, client_version.as_ref()),
+        server_version,
+        client: client_version,
+        generation: generation(),
+        last_restart_at: (last_restart != 0).then_some(last_restart),
+    }
+}
+
+fn start_locked(adb: &dyn AdbExecutor) -> AppResult<()> {
+    adb.start_server()?;
+    if !wait_for(adb, STARTUP_TIMEOUT, |up| up) {
+        return Err(AppError::new(
+            ErrorCode::AdbServerUnavailable,
+            format!("ADB server did not come up on port {}", adb.server_port()),
+        ));
+    }
+    mark_restarted();
+    Ok(())
+}
+
+fn kill_locked(adb: &dyn AdbExecutor) -> AppResult<()> {
+    adb.kill_server()?;
+    if !wait_for(adb, SHUTDOWN_TIMEOUT, |up| !up) {
+        return Err(AppError::new(
+            ErrorCode::AdbError,
+            "ADB server is still running after kill-server",
+        ));
+    }
+    Ok(())
+}
+
+/// Start the server if it isn't running (blocking)
+pub fn start(adb: &dyn AdbExecutor) -> AppResult<AdbServerStatus> {
+    {
+        let _guard = lifecycle_lock();
+        adb.set_stopped_by_user(false);
+        if adb.server_version().is_err() {
+            start_locked(adb)?;
+        }
+    }
+    Ok(status(adb))
+}
+
+/// Stop the server and keep it stopped until `start` or `restart` (blocking)
+pub fn kill(adb: &dyn AdbExecutor) -> AppResult<()> {
+    let _guard = lifecycle_lock();
+    // Set first so fallbacks racing the kill don't bring the server back
+    adb.set_stopped_by_user(true);
+    kill_locked(adb).inspect_err(|_| adb.set_stopped_by_user(false))
+}
+
+/// Kill and start the server again (blocking)
+pub fn restart(adb: &dyn AdbExecutor) -> AppResult<AdbServerStatus> {
+    {
+        let _guard = lifecycle_lock();
+        adb.set_stopped_by_user(false);
+        kill_locked(adb)?;
+        start_locked(adb)?;
+    }
+    Ok(status(adb))
+}
+
+/// Make sure a server is answering, starting one if needed (blocking).
+///
+/// Returns `true` when a server had to be started. Callers that race here
+/// wait for the first start instead of each spawning their own. Fails
+/// without starting anything while the user has the server stopped.
+pub fn ensure_running(adb: &dyn AdbExecutor) -> AppResult<bool> {
+    if adb.server_version().is_ok() {
+        return Ok(false);
+    }
+    let _guard = lifecycle_lock();
+    if adb.server_version().is_ok() {
+        return Ok(false);
+    }
+    if adb.stopped_by_user() {
+        return Err(AppError::new(
+            ErrorCode::AdbServerUnavailable,
+            "ADB server was stopped; start it again to resume",
+        ));
+    }
+    start_locked(adb).map(|_| true)
+}
+
+/// Decide whether a failed device operation should be retried for free.
+///
+/// Only `AdbServerUnavailable` errors qualify, and only once the server is
+/// answering again (restarting it if needed). Callers cap how often they
+/// take this with `FreeRetries`.
+pub async fn recover_from(adb: &SharedAdbExecutor, error: &AppError) -> bool {
+    if error.code != ErrorCode::AdbServerUnavailable || adb.stopped_by_user() {
+        return false;
+    }
+    let adb = adb.clone();
+    match run_blocking(move || ensure_running(adb.as_ref())).await {
+        Ok(started) => {
+            // The binary fallback restarts the server on its own; count
+            // that too, once per grace period
+            if !started && !in_restart_grace() {
+                mark_restarted();
+            }
+            true
+        }
+        Err(e) => {
+            eprintln!("Failed to restart ADB server: {}", e);
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::adb_client::AdbError;
+    use crate::services::adb_executor::{AdbRequest, ScriptedExecutor};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_parse_client_version() {
+        let output = "Android Debug Bridge version 1.0.41\n\
+                      Version 35.0.2-12147458\n\
+                      Installed as /opt/platform-tools/adb\n\
+                      Running on Linux 6.8.0 (x86_64)\n";
+        assert_eq!(
+            parse_client_version(output),
+            Some(AdbClientVersion {
+                protocol: 41,
+                release: Some("35.0.2-12147458".to_string()),
+                path: Some("/opt/platform-tools/adb".to_string()),
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_client_version_old_format() {
+        let version = parse_client_version("Android Debug Bridge version 1.0.39\n").unwrap();
+        assert_eq!(version.protocol, 39);
+        assert_eq!(version.release, None);
+        assert_eq!(parse_client_version("adb: command not found"), None);
+    }
+
+    #[test]
+    fn test_version_mismatch() {
+        let client = parse_client_version("Android Debug Bridge version 1.0.41").unwrap();
+        assert!(!is_version_mismatch(Some(41), Some(&client)));
+        assert!(is_version_mismatch(Some(40), Some(&client)));
+        assert!(!is_version_mismatch(None, Some(&client)));
+        assert!(!is_version_mismatch(Some(40), None));
+    }
+
+    #[test]
+    fn test_within_grace() {
+        let grace = RESTART_GRACE.as_millis() as u64;
+        assert!(!within_grace(0, 5_000));
+        assert!(within_grace(1_000, 1_000));
+        assert!(within_grace(1_000, 1_000 + grace - 1));
+        assert!(!within_grace(1_000, 1_000 + grace));
+    }
+
+    #[test]
+    fn test_mark_restarted_bumps_generation() {
+        let before = generation();
+        mark_restarted();
+        assert!(generation() > before);
+        assert!(in_restart_grace());
+    }
+
+    #[test]
+    fn test_free_retries_capped_per_generation() {
+        let mut retries = FreeRetries::default();
+        assert!(retries.available(3));
+        retries.take(3);
+        // A server that keeps failing in the same generation gets no more
+        assert!(!retries.available(3));
+        // A new restart earns another one
+        assert!(retries.available(4));
+        retries.take(4);
+        assert!(!retries.available(4));
+    }
+
+    fn scripted() -> (ScriptedExecutor, SharedAdbExecutor) {
+        let scripted = ScriptedExecutor::new();
+        let adb: SharedAdbExecutor = Arc::new(scripted.clone());
+        (scripted, adb)
+    }
+
+    #[tokio::test]
+    async fn test_recover_from_ignores_device_errors_after_restart() {
+        let (scripted, adb) = scripted();
+        mark_restarted();
+        let not_found = AppError::new(ErrorCode::DeviceNotFound, "device 'abc' not found");
+        assert!(!recover_from(&adb, &not_found).await);
+        let timeout = AppError::new(ErrorCode::Timeout, "ADB request timed out: shell");
+        assert!(!recover_from(&adb, &timeout).await);
+        assert!(scripted.calls().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_recover_from_server_error_once_server_answers() {
+        let (scripted, adb) = scripted();
+        scripted.respond(AdbRequest::ServerVersion, "41");
+        let unavailable = AppError::new(ErrorCode::AdbServerUnavailable, "daemon not running");
+        assert!(recover_from(&adb, &unavailable).await);
+        assert_eq!(scripted.calls(), vec![AdbRequest::ServerVersion]);
+    }
+
+    #[test]
+    fn test_status_reports_version_mismatch() {
+        let (scripted, adb) = scripted();
+        scripted.respond(AdbRequest::ServerVersion, "40").respond(
+            AdbRequest::ClientVersion,
+            "Android Debug Bridge version 1.0.41\n",
+        );
+        let status = status(adb.as_ref());
+        assert!(status.running);
+        assert_eq!(status.server_version, Some(40));
+        assert_eq!(status.client.map(|c| c.protocol), Some(41));
+        assert!(status.version_mismatch);
+    }
+
+    #[test]
+    fn test_status_when_server_down() {
+        let (scripted, adb) = scripted();
+        scripted.fail(
+            AdbRequest::ServerVersion,
+            AdbError::ServerUnavailable("connection refused".to_string()),
+        );
+        let status = status(adb.as_ref());
+        assert!(!status.running);
+        assert_eq!(status.server_version, None);
+        assert!(!status.version_mismatch);
+    }
+
+    #[tokio::test]
+    async fn test_kill_keeps_server_stopped_until_start() {
+        let (scripted, adb) = scripted();
+        scripted.respond(AdbRequest::KillServer, "").fail(
+            AdbRequest::ServerVersion,
+            AdbError::ServerUnavailable("connection refused".to_string()),
+        );
+
+        kill(adb.as_ref()).unwrap();
+        assert!(adb.stopped_by_user());
+        assert!(scripted.calls().contains(&AdbRequest::KillServer));
+
+        // Neither automatic recovery nor ensure_running starts it again
+        let unavailable = AppError::new(ErrorCode::AdbServerUnavailable, "daemon not running");
+        assert!(!recover_from(&adb, &unavailable).await);
+        let error = ensure_running(adb.as_ref()).unwrap_err();
+        assert_eq!(error.code, ErrorCode::AdbServerUnavailable);
+        assert!(!scripted.calls().contains(&AdbRequest::StartServer));
+
+        // A user start clears it; the server is already back here
+        scripted.respond(AdbRequest::ServerVersion, "41");
+        start(adb.as_ref()).unwrap();
+        assert!(!adb.stopped_by_user());
+        assert!(!scripted.calls().contains(&AdbRequest::StartServer));
+    }
+
+    #[test]
+    fn test_failed_kill_does_not_mark_stopped() {
+        let (scripted, adb) = scripted();
+        scripted.fail(
+            AdbRequest::KillServer,
+            AdbError::Failed("permission denied".to_string()),
+        );
+        assert!(kill(adb.as_ref()).is_err());
+        assert!(!adb.stopped_by_user());
+    }
+}
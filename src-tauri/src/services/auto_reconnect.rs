@@ -0,0 +1,419 @@
+//! Auto-Reconnect Service
+//!
+//! Retries `adb connect` for registered wireless devices that have opted in
+//! (`auto_reconnect` in the registry) once they drop off the ADB device list.
+//! Attempts follow the `HealthPollingConfig` retry settings via
+//! `calculate_backoff`, are tracked as `ReconnectionState`, and are reported
+//! through `reconnect-progress` events.
+
+use crate::commands::connection::connect_address;
+use crate::commands::device::{load_registry, parse_adb_output, AdbDeviceState};
+use crate::error::{AppError, AppResult};
+use crate::services::adb_client::run_blocking;
+use crate::services::adb_executor::SharedAdbExecutor;
+use crate::services::health_poller::calculate_backoff;
+use crate::types::health::{ErrorCode, ErrorInfo, HealthPollingConfig, ReconnectionState};
+use chrono::Utc;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+/// How often the device list is checked for dropped devices
+const CHECK_INTERVAL: Duration = Duration::from_millis(2000);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReconnectStatus {
+    /// Running `adb connect`
+    Attempting,
+    /// Attempt failed, another one is scheduled
+    Retrying,
+    /// Device is back online
+    Reconnected,
+    /// All attempts used up; waits until the device reappears or is re-enabled
+    GaveUp,
+}
+
+/// Payload of the `reconnect-progress` event
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReconnectProgressEvent {
+    pub device_id: String,
+    pub status: ReconnectStatus,
+    pub attempt: u32,
+    pub max_attempts: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_retry_at: Option<u64>,
+    pub message: String,
+}
+
+/// Lock ignoring poisoning; the guarded maps stay consistent between statements
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn now_ms() -> u64 {
+    Utc::now().timestamp_millis() as u64
+}
+
+/// Fresh state for a device that just dropped; first attempt is due immediately
+fn new_reconnection_state(
+    device_id: &str,
+    now: u64,
+    config: &HealthPollingConfig,
+) -> ReconnectionState {
+    ReconnectionState {
+        device_id: device_id.to_string(),
+        attempt: 0,
+        max_attempts: config.max_retries,
+        next_retry_at: now,
+        last_error: ErrorInfo {
+            code: ErrorCode::Offline,
+            message: "Device disconnected".to_string(),
+        },
+        started_at: now,
+    }
+}
+
+/// Whether another attempt should run now
+fn is_attempt_due(state: &ReconnectionState, now: u64) -> bool {
+    state.attempt < state.max_attempts && now >= state.next_retry_at
+}
+
+/// Record a failed attempt and schedule the next one
+fn record_failure(
+    state: &mut ReconnectionState,
+    now: u64,
+    error: &AppError,
+    config: &HealthPollingConfig,
+) {
+    state.last_error = ErrorInfo {
+        code: error.code,
+        message: error.message.clone(),
+    };
+    state.next_retry_at = now + calculate_backoff(state.attempt, config).as_millis() as u64;
+}
+
+/// Connect to `serial` and confirm it is listed as `device` again. A
+/// `stale` transport (still listed, but offline) is disconnected first, as
+/// `adb connect` would otherwise just answer "already connected".
+async fn reconnect(adb: &SharedAdbExecutor, serial: &str, stale: bool) -> AppResult<()> {
+    if stale {
+        let executor = adb.clone();
+        let target = serial.to_string();
+        // Best effort; the connect below reports the real failure
+        let _ = run_blocking(move || executor.disconnect(&target).map_err(AppError::from)).await;
+    }
+    connect_address(adb, serial.to_string()).await?;
+
+    let executor = adb.clone();
+    let output = run_blocking(move || executor.devices().map_err(AppError::from))
+        .await
+        .map_err(|e| e.context("Failed to list devices"))?;
+    let online = parse_adb_output(&output)
+        .iter()
+        .any(|d| d.serial == serial && d.state == AdbDeviceState::Device);
+    if online {
+        Ok(())
+    } else {
+        Err(AppError::new(
+            ErrorCode::Offline,
+            format!("{} is still offline after connecting", serial),
+        ))
+    }
+}
+
+pub struct AutoReconnectService {
+    is_running: Arc<AtomicBool>,
+    states: Arc<Mutex<HashMap<String, ReconnectionState>>>,
+    config: Arc<Mutex<HealthPollingConfig>>,
+    app_handle: AppHandle,
+    adb: SharedAdbExecutor,
+}
+
+impl AutoReconnectService {
+    pub fn new(app_handle: AppHandle, adb: SharedAdbExecutor) -> Self {
+        Self {
+            is_running: Arc::new(AtomicBool::new(false)),
+            states: Arc::new(Mutex::new(HashMap::new())),
+            config: Arc::new(Mutex::new(HealthPollingConfig::default())),
+            app_handle,
+            adb,
+        }
+    }
+
+    /// Start the background daemon. It idles while no device has opted in.
+    pub fn start(&mut self) -> Result<(), String> {
+        if self.is_running.load(Ordering::SeqCst) {
+            return Err("Auto-reconnect already running".to_string());
+        }
+
+        let is_running = Arc::new(AtomicBool::new(true));
+        self.is_running = is_running.clone();
+
+        let app_handle = self.app_handle.clone();
+        let adb = self.adb.clone();
+        let states = self.states.clone();
+        let config = self.config.clone();
+        tauri::async_runtime::spawn(async move {
+            Self::reconnect_loop(app_handle, adb, states, config, is_running).await
+        });
+        Ok(())
+    }
+
+    /// Stop the background daemon
+    pub fn stop(&mut self) {
+        self.is_running.store(false, Ordering::SeqCst);
+    }
+
+    /// Replace the retry settings used for future attempts
+    pub fn set_config(&self, config: HealthPollingConfig) {
+        *lock(&self.config) = config;
+    }
+
+    /// Forget any in-progress attempts for a device
+    pub fn reset_device(&self, device_id: &str) {
+        lock(&self.states).remove(device_id);
+    }
+
+    /// Current reconnection attempts, keyed by device
+    pub fn get_states(&self) -> Vec<ReconnectionState> {
+        lock(&self.states).values().cloned().collect()
+    }
+
+    async fn reconnect_loop(
+        app_handle: AppHandle,
+        adb: SharedAdbExecutor,
+        states: Arc<Mutex<HashMap<String, ReconnectionState>>>,
+        config: Arc<Mutex<HealthPollingConfig>>,
+        is_running: Arc<AtomicBool>,
+    ) {
+        while is_running.load(Ordering::SeqCst) {
+            let config = lock(&config).clone();
+            Self::check_devices(&app_handle, &adb, &states, &config).await;
+            tokio::time::sleep(CHECK_INTERVAL).await;
+        }
+    }
+
+    /// One pass: clear states of devices that are back, retry the rest
+    async fn check_devices(
+        app_handle: &AppHandle,
+        adb: &SharedAdbExecutor,
+        states: &Mutex<HashMap<String, ReconnectionState>>,
+        config: &HealthPollingConfig,
+    ) {
+        let Ok(app_data_dir) = app_handle.path().app_data_dir() else {
+            return;
+        };
+        let candidates: Vec<String> = load_registry(&app_data_dir)
+            .into_iter()
+            .filter(|d| d.is_wireless && d.auto_reconnect)
+            .map(|d| d.serial)
+            .collect();
+
+        // Drop states of devices that opted out or were forgotten
+        lock(states).retain(|serial, _| candidates.contains(serial));
+        if candidates.is_empty() {
+            return;
+        }
+
+        let executor = adb.clone();
+        let devices = run_blocking(move || executor.devices().map_err(|e| e.to_string())).await;
+        let listed = match devices {
+            Ok(output) => parse_adb_output(&output),
+            Err(e) => {
+                eprintln!("Auto-reconnect: failed to list devices: {}", e);
+                return;
+            }
+        };
+
+        for serial in candidates {
+            let state = listed.iter().find(|d| d.serial == serial).map(|d| d.state);
+            if state == Some(AdbDeviceState::Device) {
+                lock(states).remove(&serial);
+                continue;
+            }
+            let stale = state.is_some();
+            Self::attempt_reconnect(app_handle, adb, states, config, &serial, stale).await;
+        }
+    }
+
+    async fn attempt_reconnect(
+        app_handle: &AppHandle,
+        adb: &SharedAdbExecutor,
+        states: &Mutex<HashMap<String, ReconnectionState>>,
+        config: &HealthPollingConfig,
+        serial: &str,
+        stale: bool,
+    ) {
+        let now = now_ms();
+        let (attempt, max_attempts) = {
+            let mut map = lock(states);
+            let state = map
+                .entry(serial.to_string())
+                .or_insert_with(|| new_reconnection_state(serial, now, config));
+            if !is_attempt_due(state, now) {
+                return;
+            }
+            state.attempt += 1;
+            (state.attempt, state.max_attempts)
+        };
+
+        let emit = |status: ReconnectStatus, next_retry_at: Option<u64>, message: String| {
+            let _ = app_handle.emit(
+                "reconnect-progress",
+                ReconnectProgressEvent {
+                    device_id: serial.to_string(),
+                    status,
+                    attempt,
+                    max_attempts,
+                    next_retry_at,
+                    message,
+                },
+            );
+        };
+
+        emit(
+            ReconnectStatus::Attempting,
+            None,
+            format!("Reconnecting to {} ({}/{})", serial, attempt, max_attempts),
+        );
+
+        match reconnect(adb, serial, stale).await {
+            Ok(()) => {
+                lock(states).remove(serial);
+                emit(
+                    ReconnectStatus::Reconnected,
+                    None,
+                    format!("Reconnected to {}", serial),
+                );
+            }
+            Err(e) => {
+                let mut map = lock(states);
+                let Some(state) = map.get_mut(serial) else {
+                    return;
+                };
+                record_failure(state, now_ms(), &e, config);
+                if state.attempt >= state.max_attempts {
+                    emit(
+                        ReconnectStatus::GaveUp,
+                        None,
+                        format!("Giving up on {} after {} attempts: {}", serial, attempt, e),
+                    );
+                } else {
+                    emit(
+                        ReconnectStatus::Retrying,
+                        Some(state.next_retry_at),
+                        e.message,
+                    );
+                }
+            }
+        }
+    }
+}
+
+impl Drop for AutoReconnectService {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> HealthPollingConfig {
+        HealthPollingConfig {
+            max_retries: 3,
+            retry_backoff_ms: 500,
+            retry_backoff_multiplier: 2.0,
+            ..HealthPollingConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_new_state_is_due_immediately() {
+        let state = new_reconnection_state("192.168.1.5:5555", 1_000, &config());
+        assert_eq!(state.attempt, 0);
+        assert_eq!(state.max_attempts, 3);
+        assert_eq!(state.started_at, 1_000);
+        assert!(is_attempt_due(&state, 1_000));
+    }
+
+    #[test]
+    fn test_failures_follow_backoff() {
+        let config = config();
+        let mut state = new_reconnection_state("192.168.1.5:5555", 0, &config);
+
+        state.attempt = 1;
+        let refused = AppError::new(ErrorCode::Offline, "Connection to 192.168.1.5:5555 failed")
+            .with_details("failed to connect to 192.168.1.5:5555: Connection refused");
+        record_failure(&mut state, 10_000, &refused, &config);
+        assert_eq!(state.next_retry_at, 10_500);
+        assert_eq!(state.last_error.code, ErrorCode::Offline);
+        assert!(!is_attempt_due(&state, 10_499));
+        assert!(is_attempt_due(&state, 10_500));
+
+        state.attempt = 2;
+        let timeout = AppError::new(ErrorCode::Timeout, "ADB request timed out: connect");
+        record_failure(&mut state, 20_000, &timeout, &config);
+        assert_eq!(state.next_retry_at, 21_000);
+        assert_eq!(state.last_error.code, ErrorCode::Timeout);
+    }
+
+    #[test]
+    fn test_no_attempt_after_max() {
+        let config = config();
+        let mut state = new_reconnection_state("192.168.1.5:5555", 0, &config);
+        state.attempt = 3;
+        let offline = AppError::new(ErrorCode::Offline, "device offline");
+        record_failure(&mut state, 0, &offline, &config);
+        assert!(!is_attempt_due(&state, u64::MAX));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_offline_but_listed_device_is_not_reconnected() {
+        use crate::services::adb_executor::AdbRequest;
+        use crate::services::ScriptedExecutor;
+
+        let addr = "192.168.1.5:5555";
+        let adb = ScriptedExecutor::new();
+        adb.respond_devices(format!("{}\toffline\n", addr))
+            .respond(AdbRequest::Disconnect(addr.to_string()), "")
+            .respond(
+                AdbRequest::Connect(addr.to_string()),
+                format!("already connected to {}\n", addr),
+            );
+        let shared: SharedAdbExecutor = Arc::new(adb.clone());
+
+        let err = reconnect(&shared, addr, true).await.unwrap_err();
+        assert_eq!(err.code, ErrorCode::Offline);
+        let calls = adb.calls();
+        assert_eq!(calls[0], AdbRequest::Disconnect(addr.to_string()));
+        assert_eq!(calls[1], AdbRequest::Connect(addr.to_string()));
+
+        // Only a `device` listing counts as reconnected
+        adb.respond_devices(format!("{}\tdevice\n", addr));
+        reconnect(&shared, addr, true).await.unwrap();
+    }
+
+    #[test]
+    fn test_progress_event_serializes_camel_case() {
+        let event = ReconnectProgressEvent {
+            device_id: "192.168.1.5:5555".to_string(),
+            status: ReconnectStatus::GaveUp,
+            attempt: 3,
+            max_attempts: 3,
+            next_retry_at: None,
+            message: "done".to_string(),
+        };
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["deviceId"], "192.168.1.5:5555");
+        assert_eq!(json["status"], "gave_up");
+        assert_eq!(json["maxAttempts"], 3);
+        assert!(json.get("nextRetryAt").is_none());
+    }
+}
@@ -0,0 +1,238 @@
+//! Battery Time Estimates
+//!
+//! Turns a device's successive battery readings into a smoothed charge or
+//! drain rate and the time until the battery is empty or full. The rate
+//! comes from the best data the device offers:
+//!
+//! 1. `current_now` against the capacity implied by the charge counter,
+//! 2. the charge counter's change over the last minutes,
+//! 3. the whole-percent level's change over the last minutes.
+//!
+//! Rates are smoothed with a time-based exponential moving average; the
+//! estimator starts over whenever the charging state flips or polls stop
+//! for a while.
+
+use crate::types::health::{BatteryEstimate, BatteryInfo, EstimateSource};
+use std::collections::VecDeque;
+
+/// Readings older than this don't count toward the counter/level slopes
+const WINDOW_MS: u64 = 15 * 60 * 1000;
+
+/// Shortest span a charge counter slope is computed over
+const MIN_COUNTER_SPAN_MS: u64 = 60 * 1000;
+
+/// Shortest span a level slope is computed over; levels are whole percent
+const MIN_LEVEL_SPAN_MS: u64 = 5 * 60 * 1000;
+
+/// Polls further apart than this restart the estimate
+const MAX_GAP_MS: u64 = 10 * 60 * 1000;
+
+/// Time constant of the moving average
+const SMOOTHING_MS: f64 = 5.0 * 60.0 * 1000.0;
+
+const MS_PER_HOUR: f64 = 3_600_000.0;
+
+#[derive(Debug, Clone, Copy)]
+struct Reading {
+    timestamp: u64,
+    percentage: u32,
+    charge_counter: Option<u64>,
+}
+
+/// Estimation state of one device
+#[derive(Debug, Default)]
+pub struct BatteryEstimator {
+    charging: Option<bool>,
+    readings: VecDeque<Reading>,
+    /// (timestamp, smoothed %/hour)
+    smoothed: Option<(u64, f64)>,
+}
+
+impl BatteryEstimator {
+    /// Feed a poll's battery info; `None` until there is enough data
+    pub fn update(&mut self, battery: &BatteryInfo, now: u64) -> Option<BatteryEstimate> {
+        let charging = battery.is_charging?;
+        let gap = self
+            .readings
+            .back()
+            .map(|last| now.saturating_sub(last.timestamp));
+        if self.charging != Some(charging) || gap.is_some_and(|gap| gap > MAX_GAP_MS) {
+            *self = Self {
+                charging: Some(charging),
+                ..Self::default()
+            };
+        }
+
+        self.readings.push_back(Reading {
+            timestamp: now,
+            percentage: battery.percentage,
+            charge_counter: battery.charge_counter,
+        });
+        while self
+            .readings
+            .front()
+            .is_some_and(|first| now.saturating_sub(first.timestamp) > WINDOW_MS)
+        {
+            self.readings.pop_front();
+        }
+
+        let (rate, source) = current_rate(battery)
+            .map(|rate| (rate, EstimateSource::Current))
+            .or_else(|| {
+                self.counter_rate(battery)
+                    .map(|r| (r, EstimateSource::ChargeCounter))
+            })
+            .or_else(|| self.level_rate().map(|rate| (rate, EstimateSource::Level)))?;
+
+        let rate = match self.smoothed {
+            Some((last, smoothed)) => {
+                let elapsed = now.saturating_sub(last) as f64;
+                let alpha = 1.0 - (-elapsed / SMOOTHING_MS).exp();
+                smoothed + alpha * (rate - smoothed)
+            }
+            None => rate,
+        };
+        self.smoothed = Some((now, rate));
+
+        Some(estimate(battery.percentage, charging, rate, source))
+    }
+
+    fn counter_rate(&self, battery: &BatteryInfo) -> Option<f64> {
+        let capacity = capacity_uah(battery)?;
+        let first = self.readings.iter().find(|r| r.charge_counter.is_some())?;
+        let last = self.readings.back()?;
+        let span = last.timestamp.saturating_sub(first.timestamp);
+        if span < MIN_COUNTER_SPAN_MS {
+            return None;
+        }
+        let delta = last.charge_counter? as f64 - first.charge_counter? as f64;
+        Some(delta / capacity * 100.0 / (span as f64 / MS_PER_HOUR))
+    }
+
+    fn level_rate(&self) -> Option<f64> {
+        let first = self.readings.front()?;
+        let last = self.readings.back()?;
+        let span = last.timestamp.saturating_sub(first.timestamp);
+        if span < MIN_LEVEL_SPAN_MS {
+            return None;
+        }
+        let delta = last.percentage as f64 - first.percentage as f64;
+        Some(delta / (span as f64 / MS_PER_HOUR))
+    }
+}
+
+/// Full charge in µAh, derived from the remaining charge at this level
+fn capacity_uah(battery: &BatteryInfo) -> Option<f64> {
+    let counter = battery.charge_counter.filter(|&c| c > 0)?;
+    (battery.percentage > 0).then(|| counter as f64 * 100.0 / battery.percentage as f64)
+}
+
+/// %/hour implied by the instantaneous current
+fn current_rate(battery: &BatteryInfo) -> Option<f64> {
+    let current = battery.current_now.filter(|&c| c != 0)?;
+    Some(current as f64 / capacity_uah(battery)? * 100.0)
+}
+
+fn estimate(percentage: u32, charging: bool, rate: f64, source: EstimateSource) -> BatteryEstimate {
+    let hours_to = |remaining: f64, rate: f64| {
+        (rate > 0.0).then(|| (remaining / rate * MS_PER_HOUR).round() as u64)
+    };
+    BatteryEstimate {
+        rate_per_hour: rate,
+        time_to_empty_ms: if charging {
+            None
+        } else {
+            hours_to(percentage as f64, -rate)
+        },
+        time_to_full_ms: if charging {
+            hours_to(100.0 - percentage as f64, rate)
+        } else {
+            None
+        },
+        source,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MINUTE: u64 = 60_000;
+    const HOUR: u64 = 60 * MINUTE;
+
+    fn battery(percentage: u32, charging: bool) -> BatteryInfo {
+        BatteryInfo {
+            percentage,
+            temperature: None,
+            is_charging: Some(charging),
+            health: None,
+            charge_counter: None,
+            current_now: None,
+            charger: None,
+            voltage: None,
+            max_charging_current: None,
+        }
+    }
+
+    #[test]
+    fn test_current_gives_immediate_estimate() {
+        // 2000 mAh left at 50% -> 4000 mAh capacity; 400 mA is 10%/hour
+        let info = BatteryInfo {
+            charge_counter: Some(2_000_000),
+            current_now: Some(-400_000),
+            ..battery(50, false)
+        };
+        let estimate = BatteryEstimator::default().update(&info, 0).unwrap();
+        assert_eq!(estimate.source, EstimateSource::Current);
+        assert!((estimate.rate_per_hour + 10.0).abs() < 1e-9);
+        assert_eq!(estimate.time_to_empty_ms, Some(5 * HOUR));
+        assert_eq!(estimate.time_to_full_ms, None);
+    }
+
+    #[test]
+    fn test_charge_counter_slope() {
+        let mut estimator = BatteryEstimator::default();
+        let at = |counter: u64| BatteryInfo {
+            charge_counter: Some(counter),
+            ..battery(50, true)
+        };
+        assert!(estimator.update(&at(1_980_000), 0).is_none());
+        // +20 mAh in 6 minutes of 4000 mAh: 5%/hour
+        let estimate = estimator.update(&at(2_000_000), 6 * MINUTE).unwrap();
+        assert_eq!(estimate.source, EstimateSource::ChargeCounter);
+        assert!((estimate.rate_per_hour - 5.0).abs() < 1e-6);
+        assert_eq!(estimate.time_to_full_ms, Some(10 * HOUR));
+    }
+
+    #[test]
+    fn test_level_slope_is_smoothed() {
+        let mut estimator = BatteryEstimator::default();
+        assert!(estimator.update(&battery(80, false), 0).is_none());
+        assert!(estimator.update(&battery(79, false), 3 * MINUTE).is_none());
+        // -2% in 6 minutes: 20%/hour
+        let first = estimator.update(&battery(78, false), 6 * MINUTE).unwrap();
+        assert_eq!(first.source, EstimateSource::Level);
+        assert!((first.rate_per_hour + 20.0).abs() < 1e-9);
+        // A sudden jump only moves the smoothed rate part of the way
+        let next = estimator.update(&battery(75, false), 7 * MINUTE).unwrap();
+        assert!(next.rate_per_hour < -20.0 && next.rate_per_hour > -42.8);
+    }
+
+    #[test]
+    fn test_restarts_on_charging_change_and_gaps() {
+        let mut estimator = BatteryEstimator::default();
+        estimator.update(&battery(80, false), 0);
+        assert!(estimator.update(&battery(78, false), 6 * MINUTE).is_some());
+        // Plugged in: the draining readings no longer apply
+        assert!(estimator.update(&battery(78, true), 7 * MINUTE).is_none());
+        assert!(estimator.update(&battery(79, true), 13 * MINUTE).is_some());
+        // Polls resumed after a long pause
+        assert!(estimator.update(&battery(95, true), 2 * HOUR).is_none());
+        // Unknown charging state gives no estimate
+        let unknown = BatteryInfo {
+            is_charging: None,
+            ..battery(95, true)
+        };
+        assert!(estimator.update(&unknown, 2 * HOUR + MINUTE).is_none());
+    }
+}
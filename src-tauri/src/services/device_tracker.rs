@@ -0,0 +1,398 @@
+//! Device Tracker Service
+//!
+//! Subscribes to the ADB server's `host:track-devices-l` stream and keeps the
+//! device registry in sync without the frontend having to poll `list_devices`.
+//! Emits typed `device-added`, `device-removed` and `device-state-changed`
+//! events, and resubscribes automatically when the ADB server restarts,
+//! immediately if the restart went through `adb_server`.
+
+use crate::commands::device::{parse_adb_output, sync_polling, sync_registry, DeviceInfo};
+use crate::error::{AppError, AppResult};
+use crate::services::adb_client::AdbClient;
+use crate::services::adb_executor::SharedAdbExecutor;
+use crate::services::adb_server;
+use crate::types::health::ErrorCode;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+/// How long to wait for a snapshot before re-checking the stop flag
+const SNAPSHOT_WAIT: Duration = Duration::from_millis(500);
+
+/// Resubscription backoff bounds
+const RESUBSCRIBE_BASE_MS: u64 = 500;
+const RESUBSCRIBE_MAX_MS: u64 = 10_000;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceAddedEvent {
+    pub serial: String,
+    pub state: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub device: Option<DeviceInfo>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceRemovedEvent {
+    pub serial: String,
+    pub previous_state: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceStateChangedEvent {
+    pub serial: String,
+    pub previous_state: String,
+    pub state: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub device: Option<DeviceInfo>,
+}
+
+/// A single difference between two device list snapshots
+#[derive(Debug, Clone, PartialEq)]
+pub enum DeviceChange {
+    Added {
+        serial: String,
+        state: String,
+    },
+    Removed {
+        serial: String,
+        previous_state: String,
+    },
+    StateChanged {
+        serial: String,
+        previous_state: String,
+        state: String,
+    },
+}
+
+/// Compute the changes between two `serial -> state` snapshots.
+/// Results are sorted by serial for deterministic event order.
+pub fn diff_snapshots(
+    previous: &HashMap<String, String>,
+    current: &HashMap<String, String>,
+) -> Vec<DeviceChange> {
+    let mut changes = Vec::new();
+
+    for (serial, state) in current {
+        match previous.get(serial) {
+            None => changes.push(DeviceChange::Added {
+                serial: serial.clone(),
+                state: state.clone(),
+            }),
+            Some(previous_state) if previous_state != state => {
+                changes.push(DeviceChange::StateChanged {
+                    serial: serial.clone(),
+                    previous_state: previous_state.clone(),
+                    state: state.clone(),
+                })
+            }
+            Some(_) => {}
+        }
+    }
+
+    for (serial, previous_state) in previous {
+        if !current.contains_key(serial) {
+            changes.push(DeviceChange::Removed {
+                serial: serial.clone(),
+                previous_state: previous_state.clone(),
+            });
+        }
+    }
+
+    changes.sort_by(|a, b| change_serial(a).cmp(change_serial(b)));
+    changes
+}
+
+fn change_serial(change: &DeviceChange) -> &str {
+    match change {
+        DeviceChange::Added { serial, .. }
+        | DeviceChange::Removed { serial, .. }
+        | DeviceChange::StateChanged { serial, .. } => serial,
+    }
+}
+
+/// Backoff before the n-th resubscription attempt (1-based)
+fn resubscribe_backoff(attempt: u32) -> Duration {
+    let exponent = attempt.saturating_sub(1).min(16);
+    let ms = RESUBSCRIBE_BASE_MS.saturating_mul(1 << exponent);
+    Duration::from_millis(ms.min(RESUBSCRIBE_MAX_MS))
+}
+
+pub struct DeviceTracker {
+    tracking_thread: Option<JoinHandle<()>>,
+    is_running: Arc<AtomicBool>,
+    app_handle: AppHandle,
+    adb: SharedAdbExecutor,
+}
+
+impl DeviceTracker {
+    /// Create a new (stopped) device tracker
+    pub fn new(app_handle: AppHandle, adb: SharedAdbExecutor) -> Self {
+        Self {
+            tracking_thread: None,
+            is_running: Arc::new(AtomicBool::new(false)),
+            app_handle,
+            adb,
+        }
+    }
+
+    /// Start tracking in a background thread
+    pub fn start_tracking(&mut self) -> AppResult<()> {
+        if self.tracking_thread.is_some() {
+            return Err(AppError::new(
+                ErrorCode::SessionExists,
+                "Device tracking already running",
+            ));
+        }
+
+        let app_handle = self.app_handle.clone();
+        let adb = self.adb.clone();
+        let is_running = Arc::new(AtomicBool::new(true));
+        let is_running_clone = is_running.clone();
+
+        let thread = std::thread::Builder::new()
+            .name("adb-device-tracker".to_string())
+            .spawn(move || Self::tracking_loop(app_handle, adb, is_running_clone))
+            .map_err(|e| AppError::io("Failed to spawn device tracker", e))?;
+
+        self.tracking_thread = Some(thread);
+        self.is_running = is_running;
+        Ok(())
+    }
+
+    /// Stop tracking; the thread exits within one snapshot wait
+    pub fn stop_tracking(&mut self) -> AppResult<()> {
+        self.is_running.store(false, Ordering::SeqCst);
+        self.tracking_thread = None;
+        Ok(())
+    }
+
+    /// Whether the tracker is currently running
+    pub fn is_running(&self) -> bool {
+        self.is_running.load(Ordering::SeqCst)
+    }
+
+    /// Subscribe, process snapshots, and resubscribe with backoff on failure
+    fn tracking_loop(app_handle: AppHandle, adb: SharedAdbExecutor, is_running: Arc<AtomicBool>) {
+        // Subscriptions are protocol-only; snapshots are synced through `adb`
+        let client = AdbClient::new();
+        let mut known: HashMap<String, String> = HashMap::new();
+        let mut attempt: u32 = 0;
+
+        while is_running.load(Ordering::SeqCst) {
+            match client.track_devices() {
+                Ok(mut tracking) => {
+                    // Back after losing the stream: the server was replaced
+                    // behind our back (e.g. `adb kill-server` in a terminal)
+                    if attempt > 0 && !adb_server::in_restart_grace() {
+                        adb_server::mark_restarted();
+                    }
+                    attempt = 0;
+                    while is_running.load(Ordering::SeqCst) {
+                        match tracking.next_snapshot(SNAPSHOT_WAIT) {
+                            Ok(Some(payload)) => {
+                                Self::apply_snapshot(&app_handle, &adb, &mut known, &payload)
+                            }
+                            Ok(None) => continue,
+                            Err(e) => {
+                                eprintln!("Device tracking interrupted: {}", e);
+                                break;
+                            }
+                        }
+                    }
+                }
+                Err(e) => {
+                    eprintln!("Device tracking subscription failed: {}", e);
+                    // A server stopped from the UI stays down until started again
+                    if !adb.stopped_by_user() {
+                        if let Err(e) = adb_server::ensure_running(adb.as_ref()) {
+                            eprintln!("Failed to start ADB server: {}", e);
+                        }
+                    }
+                }
+            }
+
+            attempt += 1;
+            let generation = adb_server::generation();
+            Self::sleep_while_running(&is_running, resubscribe_backoff(attempt), generation);
+        }
+    }
+
+    /// Apply one snapshot: sync the registry, then emit per-device events
+    fn apply_snapshot(
+        app_handle: &AppHandle,
+        adb: &SharedAdbExecutor,
+        known: &mut HashMap<String, String>,
+        payload: &str,
+    ) {
+        let adb_devices = parse_adb_output(payload);
+        let current: HashMap<String, String> = adb_devices
+            .iter()
+            .map(|d| (d.serial.clone(), d.status().to_string()))
+            .collect();
+
+        let changes = diff_snapshots(known, &current);
+        *known = current;
+        if changes.is_empty() {
+            return;
+        }
+
+        let registry = match app_handle.path().app_data_dir() {
+            Ok(app_data_dir) => {
+                tauri::async_runtime::block_on(sync_registry(adb, &app_data_dir, &adb_devices))
+                    .unwrap_or_else(|e| {
+                        eprintln!("Warning: failed to sync device registry: {}", e);
+                        Vec::new()
+                    })
+            }
+            Err(e) => {
+                eprintln!("Warning: failed to resolve app data dir: {}", e);
+                Vec::new()
+            }
+        };
+        let find_device = |serial: &str| registry.iter().find(|d| d.serial == serial).cloned();
+
+        sync_polling(app_handle, &registry);
+
+        for change in changes {
+            let _ = match change {
+                DeviceChange::Added { serial, state } => app_handle.emit(
+                    "device-added",
+                    DeviceAddedEvent {
+                        device: find_device(&serial),
+                        serial,
+                        state,
+                    },
+                ),
+                DeviceChange::Removed {
+                    serial,
+                    previous_state,
+                } => app_handle.emit(
+                    "device-removed",
+                    DeviceRemovedEvent {
+                        serial,
+                        previous_state,
+                    },
+                ),
+                DeviceChange::StateChanged {
+                    serial,
+                    previous_state,
+                    state,
+                } => app_handle.emit(
+                    "device-state-changed",
+                    DeviceStateChangedEvent {
+                        device: find_device(&serial),
+                        serial,
+                        previous_state,
+                        state,
+                    },
+                ),
+            };
+        }
+    }
+
+    /// Sleep in short slices so a stop request or a server restart (a new
+    /// `adb_server` generation) is honored promptly
+    fn sleep_while_running(is_running: &AtomicBool, duration: Duration, generation: u64) {
+        let slice = Duration::from_millis(100);
+        let mut remaining = duration;
+        while is_running.load(Ordering::SeqCst)
+            && adb_server::generation() == generation
+            && !remaining.is_zero()
+        {
+            let step = remaining.min(slice);
+            std::thread::sleep(step);
+            remaining -= step;
+        }
+    }
+}
+
+impl Drop for DeviceTracker {
+    fn drop(&mut self) {
+        // Signal the tracking thread to stop
+        self.is_running.store(false, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(entries: &[(&str, &str)]) -> HashMap<String, String> {
+        entries
+            .iter()
+            .map(|(serial, state)| (serial.to_string(), state.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_diff_detects_added_device() {
+        let changes = diff_snapshots(&snapshot(&[]), &snapshot(&[("abc123", "device")]));
+        assert_eq!(
+            changes,
+            vec![DeviceChange::Added {
+                serial: "abc123".to_string(),
+                state: "device".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_diff_detects_removed_device() {
+        let changes = diff_snapshots(&snapshot(&[("abc123", "device")]), &snapshot(&[]));
+        assert_eq!(
+            changes,
+            vec![DeviceChange::Removed {
+                serial: "abc123".to_string(),
+                previous_state: "device".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_diff_detects_state_change() {
+        let changes = diff_snapshots(
+            &snapshot(&[("abc123", "unauthorized")]),
+            &snapshot(&[("abc123", "device")]),
+        );
+        assert_eq!(
+            changes,
+            vec![DeviceChange::StateChanged {
+                serial: "abc123".to_string(),
+                previous_state: "unauthorized".to_string(),
+                state: "device".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_diff_unchanged_snapshot_is_empty() {
+        let devices = snapshot(&[("abc123", "device"), ("192.168.1.5:5555", "device")]);
+        assert!(diff_snapshots(&devices, &devices).is_empty());
+    }
+
+    #[test]
+    fn test_diff_is_sorted_by_serial() {
+        let changes = diff_snapshots(
+            &snapshot(&[("b-device", "device")]),
+            &snapshot(&[("c-device", "device"), ("a-device", "device")]),
+        );
+        let serials: Vec<&str> = changes.iter().map(change_serial).collect();
+        assert_eq!(serials, vec!["a-device", "b-device", "c-device"]);
+    }
+
+    #[test]
+    fn test_resubscribe_backoff_is_capped() {
+        assert_eq!(resubscribe_backoff(1), Duration::from_millis(500));
+        assert_eq!(resubscribe_backoff(2), Duration::from_millis(1000));
+        assert_eq!(resubscribe_backoff(3), Duration::from_millis(2000));
+        assert_eq!(resubscribe_backoff(10), Duration::from_millis(10_000));
+        assert_eq!(resubscribe_backoff(u32::MAX), Duration::from_millis(10_000));
+    }
+}
@@ -0,0 +1,646 @@
+//! Health Alert Rules
+//!
+//! Evaluates user-defined `AlertRule`s against every successful poll. Each
+//! (rule, device) pair is a small state machine:
+//!
+//! - The rule fires once its conditions held for `min_polls` consecutive
+//!   polls and at least `min_duration_ms`, unless it already fired for the
+//!   device within `cooldown_ms`.
+//! - While firing, threshold conditions are widened by their `hysteresis`
+//!   so a metric hovering around the threshold doesn't flap.
+//! - When the conditions stop holding the alert resolves. Polls missing a
+//!   metric the rule needs leave the state untouched.
+//!
+//! Rules are persisted as `alert-rules.json` in the app data dir; the
+//! defaults mirror `types::health::thresholds`.
+
+use crate::error::{AppError, AppResult};
+use crate::services::history_store::write_atomically;
+use crate::types::health::{
+    thresholds, AlertCondition, AlertMetric, AlertRule, AlertSeverity, AlertState, Comparison,
+    DeviceHealth, HealthAlertEvent, QualityLevel,
+};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const RULES_FILE: &str = "alert-rules.json";
+
+const MINUTE_MS: u64 = 60 * 1000;
+
+/// Progress of one rule on one device
+#[derive(Debug, Default)]
+struct RuleState {
+    matching_since: Option<u64>,
+    matching_polls: u32,
+    active: bool,
+    /// Whether the current activation was reported (not cooled down)
+    reported: bool,
+    last_fired: Option<u64>,
+}
+
+/// An alert to emit as `health-alert`
+#[derive(Debug, Clone, PartialEq)]
+pub struct FiredAlert {
+    pub event: HealthAlertEvent,
+    /// Raise a native notification as well
+    pub notify: bool,
+}
+
+pub struct AlertEngine {
+    rules: Vec<AlertRule>,
+    /// Keyed by (rule id, device id)
+    states: HashMap<(String, String), RuleState>,
+    /// Where rules are saved; `None` keeps them in memory only
+    path: Option<PathBuf>,
+}
+
+impl AlertEngine {
+    pub fn new(rules: Vec<AlertRule>) -> Self {
+        Self {
+            rules,
+            states: HashMap::new(),
+            path: None,
+        }
+    }
+
+    /// Rules saved in `app_data_dir`, or the defaults
+    pub fn load(app_data_dir: &Path) -> Self {
+        let path = app_data_dir.join(RULES_FILE);
+        let rules = match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                eprintln!("Warning: corrupt {}, using defaults: {}", RULES_FILE, e);
+                default_rules()
+            }),
+            Err(_) => default_rules(),
+        };
+        Self {
+            path: Some(path),
+            ..Self::new(rules)
+        }
+    }
+
+    pub fn rules(&self) -> &[AlertRule] {
+        &self.rules
+    }
+
+    /// Validate, persist and apply a new rule set. Rules that didn't change
+    /// keep their progress and cooldowns.
+    pub fn set_rules(&mut self, rules: Vec<AlertRule>) -> AppResult<()> {
+        validate_rules(&rules).map_err(AppError::invalid_input)?;
+        if let Some(path) = &self.path {
+            save_rules(path, &rules)?;
+        }
+
+        let unchanged: HashSet<&str> = rules
+            .iter()
+            .filter(|rule| self.rules.contains(rule))
+            .map(|rule| rule.id.as_str())
+            .collect();
+        self.states
+            .retain(|(rule_id, _), _| unchanged.contains(rule_id.as_str()));
+        self.rules = rules;
+        Ok(())
+    }
+
+    /// Run every applicable rule against a fresh poll of `device_id`
+    pub fn evaluate(
+        &mut self,
+        device_id: &str,
+        health: &DeviceHealth,
+        now: u64,
+    ) -> Vec<FiredAlert> {
+        let mut fired = Vec::new();
+        for rule in &self.rules {
+            if !rule.enabled || rule.device_id.as_deref().is_some_and(|id| id != device_id) {
+                continue;
+            }
+            let state = self
+                .states
+                .entry((rule.id.clone(), device_id.to_string()))
+                .or_default();
+            let Some(matches) = rule_matches(rule, health, state.active) else {
+                continue;
+            };
+
+            if !matches {
+                let resolved = state.active && state.reported;
+                *state = RuleState {
+                    last_fired: state.last_fired,
+                    ..RuleState::default()
+                };
+                if resolved {
+                    fired.push(FiredAlert {
+                        event: alert_event(rule, device_id, AlertState::Resolved, health, now),
+                        notify: false,
+                    });
+                }
+                continue;
+            }
+            if !state.active {
+                state.matching_polls += 1;
+                let since = *state.matching_since.get_or_insert(now);
+                if state.matching_polls < rule.min_polls
+                    || now.saturating_sub(since) < rule.min_duration_ms
+                {
+                    continue;
+                }
+                state.active = true;
+            }
+            if state.reported {
+                continue;
+            }
+
+            // A rule that became active within the cooldown fires once it expires
+            state.reported = state
+                .last_fired
+                .is_none_or(|last| now.saturating_sub(last) >= rule.cooldown_ms);
+            if state.reported {
+                state.last_fired = Some(now);
+                fired.push(FiredAlert {
+                    event: alert_event(rule, device_id, AlertState::Triggered, health, now),
+                    notify: rule.notify,
+                });
+            }
+        }
+        fired
+    }
+}
+
+/// Built-in rules, used until the user saves their own
+pub fn default_rules() -> Vec<AlertRule> {
+    vec![
+        AlertRule {
+            id: "battery-low".to_string(),
+            name: "Battery low".to_string(),
+            enabled: true,
+            device_id: None,
+            conditions: vec![
+                AlertCondition::Threshold {
+                    metric: AlertMetric::BatteryPercentage,
+                    comparison: Comparison::Below,
+                    value: thresholds::BATTERY_WARNING as f64,
+                    hysteresis: 2.0,
+                },
+                AlertCondition::Charging { charging: false },
+            ],
+            severity: AlertSeverity::Warning,
+            min_polls: 1,
+            min_duration_ms: 0,
+            cooldown_ms: 10 * MINUTE_MS,
+            notify: false,
+        },
+        AlertRule {
+            id: "storage-critical".to_string(),
+            name: "Storage almost full".to_string(),
+            enabled: true,
+            device_id: None,
+            conditions: vec![AlertCondition::Threshold {
+                metric: AlertMetric::StorageFree,
+                comparison: Comparison::Below,
+                value: thresholds::STORAGE_CRITICAL_BYTES as f64,
+                hysteresis: (50 * 1024 * 1024) as f64,
+            }],
+            severity: AlertSeverity::Critical,
+            min_polls: 1,
+            min_duration_ms: 0,
+            cooldown_ms: 30 * MINUTE_MS,
+            notify: false,
+        },
+    ]
+}
+
+pub fn validate_rules(rules: &[AlertRule]) -> Result<(), String> {
+    let mut ids = HashSet::new();
+    for rule in rules {
+        if rule.id.trim().is_empty() {
+            return Err("Alert rule id must not be empty".to_string());
+        }
+        if !ids.insert(rule.id.as_str()) {
+            return Err(format!("Duplicate alert rule id: {}", rule.id));
+        }
+        if rule.conditions.is_empty() {
+            return Err(format!("Alert rule {} has no conditions", rule.id));
+        }
+        if rule.min_polls < 1 {
+            return Err(format!("Alert rule {}: min_polls must be >= 1", rule.id));
+        }
+        for condition in &rule.conditions {
+            if let AlertCondition::Threshold {
+                value, hysteresis, ..
+            } = condition
+            {
+                if !value.is_finite() || !hysteresis.is_finite() || *hysteresis < 0.0 {
+                    return Err(format!(
+                        "Alert rule {}: threshold and hysteresis must be finite, hysteresis >= 0",
+                        rule.id
+                    ));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn save_rules(path: &Path, rules: &[AlertRule]) -> AppResult<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| AppError::io("Failed to create data directory", e))?;
+    }
+    let json = serde_json::to_string_pretty(rules)
+        .map_err(|e| AppError::internal(format!("Failed to serialize alert rules: {}", e)))?;
+    write_atomically(path, json.as_bytes())
+}
+
+/// Whether all conditions hold; `None` if the poll lacks a needed metric
+fn rule_matches(rule: &AlertRule, health: &DeviceHealth, active: bool) -> Option<bool> {
+    let mut matches = true;
+    for condition in &rule.conditions {
+        matches &= condition_holds(condition, health, active)?;
+    }
+    Some(matches)
+}
+
+fn condition_holds(
+    condition: &AlertCondition,
+    health: &DeviceHealth,
+    active: bool,
+) -> Option<bool> {
+    match condition {
+        AlertCondition::Threshold {
+            metric,
+            comparison,
+            value,
+            hysteresis,
+        } => {
+            let current = metric_value(*metric, health)?;
+            let margin = if active { *hysteresis } else { 0.0 };
+            Some(match comparison {
+                Comparison::Below => current < value + margin,
+                Comparison::Above => current > value - margin,
+            })
+        }
+        AlertCondition::Charging { charging } => {
+            Some(health.battery.as_ref()?.is_charging? == *charging)
+        }
+        AlertCondition::Quality { level } => {
+            let current = health.connection.as_ref()?.quality_level;
+            Some(quality_rank(current) >= quality_rank(*level))
+        }
+    }
+}
+
+fn metric_value(metric: AlertMetric, health: &DeviceHealth) -> Option<f64> {
+    match metric {
+        AlertMetric::BatteryPercentage => health.battery.as_ref().map(|b| b.percentage as f64),
+        AlertMetric::BatteryTemperature => health
+            .battery
+            .as_ref()
+            .and_then(|b| b.temperature)
+            .map(f64::from),
+        AlertMetric::StorageFree => health.storage.as_ref().map(|s| s.free as f64),
+        AlertMetric::Latency => health.connection.as_ref().map(|c| c.latency as f64),
+    }
+}
+
+/// Higher is worse
+fn quality_rank(level: QualityLevel) -> u8 {
+    match level {
+        QualityLevel::Excellent => 0,
+        QualityLevel::Good => 1,
+        QualityLevel::Fair => 2,
+        QualityLevel::Poor => 3,
+    }
+}
+
+fn alert_event(
+    rule: &AlertRule,
+    device_id: &str,
+    state: AlertState,
+    health: &DeviceHealth,
+    now: u64,
+) -> HealthAlertEvent {
+    let message = match state {
+        AlertState::Triggered => {
+            let readings: Vec<String> = rule
+                .conditions
+                .iter()
+                .filter_map(|condition| describe(condition, health))
+                .collect();
+            format!("{} on {}: {}", rule.name, device_id, readings.join(", "))
+        }
+        AlertState::Resolved => format!("{} on {} resolved", rule.name, device_id),
+    };
+    HealthAlertEvent {
+        rule_id: rule.id.clone(),
+        rule_name: rule.name.clone(),
+        device_id: device_id.to_string(),
+        severity: rule.severity,
+        state,
+        message,
+        timestamp: now,
+    }
+}
+
+/// Current reading behind a condition, e.g. "battery 12%"
+fn describe(condition: &AlertCondition, health: &DeviceHealth) -> Option<String> {
+    match condition {
+        AlertCondition::Threshold { metric, .. } => {
+            let value = metric_value(*metric, health)?;
+            Some(match metric {
+                AlertMetric::BatteryPercentage => format!("battery {}%", value),
+                AlertMetric::BatteryTemperature => format!("temperature {}°C", value),
+                AlertMetric::StorageFree => {
+                    format!("{} MB free", (value / (1024.0 * 1024.0)).round())
+                }
+                AlertMetric::Latency => format!("latency {} ms", value),
+            })
+        }
+        AlertCondition::Charging { charging } => Some(
+            if *charging {
+                "charging"
+            } else {
+                "not charging"
+            }
+            .to_string(),
+        ),
+        AlertCondition::Quality { .. } => {
+            let level = health.connection.as_ref()?.quality_level;
+            Some(format!("connection {:?}", level).to_lowercase())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::health::{BatteryInfo, ConnectionMetrics, ConnectionType};
+
+    fn health(battery: u32, temperature: i32, charging: bool) -> DeviceHealth {
+        let mut health = DeviceHealth::new("abc".to_string());
+        health.battery = Some(BatteryInfo {
+            percentage: battery,
+            temperature: Some(temperature),
+            is_charging: Some(charging),
+            health: None,
+            charge_counter: None,
+            current_now: None,
+            charger: None,
+            voltage: None,
+            max_charging_current: None,
+        });
+        health
+    }
+
+    fn with_quality(mut health: DeviceHealth, quality_level: QualityLevel) -> DeviceHealth {
+        health.connection = Some(ConnectionMetrics {
+            connection_type: ConnectionType::Wireless,
+            latency: 250,
+            signal_strength: None,
+            quality_level,
+            estimated_bandwidth: None,
+        });
+        health
+    }
+
+    fn rule(conditions: Vec<AlertCondition>) -> AlertRule {
+        AlertRule {
+            id: "rule".to_string(),
+            name: "Rule".to_string(),
+            enabled: true,
+            device_id: None,
+            conditions,
+            severity: AlertSeverity::Warning,
+            min_polls: 1,
+            min_duration_ms: 0,
+            cooldown_ms: 0,
+            notify: true,
+        }
+    }
+
+    fn battery_below(value: f64, hysteresis: f64) -> AlertCondition {
+        AlertCondition::Threshold {
+            metric: AlertMetric::BatteryPercentage,
+            comparison: Comparison::Below,
+            value,
+            hysteresis,
+        }
+    }
+
+    fn states(fired: Vec<FiredAlert>) -> Vec<AlertState> {
+        fired.into_iter().map(|alert| alert.event.state).collect()
+    }
+
+    #[test]
+    fn test_all_conditions_must_match() {
+        let mut engine = AlertEngine::new(vec![rule(vec![
+            battery_below(15.0, 0.0),
+            AlertCondition::Charging { charging: false },
+        ])]);
+
+        assert!(engine.evaluate("abc", &health(10, 30, true), 0).is_empty());
+        let fired = engine.evaluate("abc", &health(10, 30, false), 1);
+        assert_eq!(fired.len(), 1);
+        assert_eq!(fired[0].event.state, AlertState::Triggered);
+        assert_eq!(
+            fired[0].event.message,
+            "Rule on abc: battery 10%, not charging"
+        );
+        assert!(fired[0].notify);
+        // Still matching: no repeat
+        assert!(engine.evaluate("abc", &health(9, 30, false), 2).is_empty());
+        // Other devices have their own state
+        assert_eq!(engine.evaluate("def", &health(9, 30, false), 2).len(), 1);
+    }
+
+    #[test]
+    fn test_hysteresis_prevents_flapping() {
+        let mut engine = AlertEngine::new(vec![rule(vec![battery_below(15.0, 3.0)])]);
+
+        assert_eq!(
+            states(engine.evaluate("abc", &health(14, 30, false), 0)),
+            vec![AlertState::Triggered]
+        );
+        assert!(engine.evaluate("abc", &health(16, 30, false), 1).is_empty());
+        assert!(engine.evaluate("abc", &health(14, 30, false), 2).is_empty());
+        assert_eq!(
+            states(engine.evaluate("abc", &health(18, 30, false), 3)),
+            vec![AlertState::Resolved]
+        );
+    }
+
+    #[test]
+    fn test_sustained_for_duration_and_polls() {
+        let hot = AlertCondition::Threshold {
+            metric: AlertMetric::BatteryTemperature,
+            comparison: Comparison::Above,
+            value: 42.0,
+            hysteresis: 0.0,
+        };
+        let mut engine = AlertEngine::new(vec![AlertRule {
+            min_duration_ms: 2 * MINUTE_MS,
+            ..rule(vec![hot])
+        }]);
+        assert!(engine.evaluate("abc", &health(50, 43, false), 0).is_empty());
+        assert!(engine
+            .evaluate("abc", &health(50, 44, false), MINUTE_MS)
+            .is_empty());
+        // A cool poll restarts the clock
+        assert!(engine
+            .evaluate("abc", &health(50, 40, false), 90_000)
+            .is_empty());
+        assert!(engine
+            .evaluate("abc", &health(50, 43, false), 2 * MINUTE_MS)
+            .is_empty());
+        assert_eq!(
+            engine
+                .evaluate("abc", &health(50, 43, false), 4 * MINUTE_MS)
+                .len(),
+            1
+        );
+
+        let poor = AlertCondition::Quality {
+            level: QualityLevel::Poor,
+        };
+        let mut engine = AlertEngine::new(vec![AlertRule {
+            min_polls: 5,
+            ..rule(vec![poor])
+        }]);
+        let poor_poll = with_quality(health(50, 30, false), QualityLevel::Poor);
+        for t in 0..4 {
+            assert!(engine.evaluate("abc", &poor_poll, t).is_empty());
+        }
+        let fired = engine.evaluate("abc", &poor_poll, 4);
+        assert_eq!(fired[0].event.message, "Rule on abc: connection poor");
+    }
+
+    #[test]
+    fn test_cooldown_suppresses_refiring() {
+        let mut engine = AlertEngine::new(vec![AlertRule {
+            cooldown_ms: 10 * MINUTE_MS,
+            ..rule(vec![battery_below(15.0, 0.0)])
+        }]);
+        let low = health(10, 30, false);
+        let ok = health(50, 30, false);
+
+        assert_eq!(engine.evaluate("abc", &low, 0).len(), 1);
+        // Resolved
+        assert_eq!(engine.evaluate("abc", &ok, MINUTE_MS).len(), 1);
+        // Within the cooldown: neither the trigger nor its resolution
+        assert!(engine.evaluate("abc", &low, 2 * MINUTE_MS).is_empty());
+        assert!(engine.evaluate("abc", &ok, 3 * MINUTE_MS).is_empty());
+        assert_eq!(
+            states(engine.evaluate("abc", &low, 11 * MINUTE_MS)),
+            vec![AlertState::Triggered]
+        );
+
+        // Still low when the cooldown runs out: fires then, and only once
+        assert_eq!(engine.evaluate("abc", &ok, 12 * MINUTE_MS).len(), 1);
+        assert!(engine.evaluate("abc", &low, 13 * MINUTE_MS).is_empty());
+        assert!(engine.evaluate("abc", &low, 20 * MINUTE_MS).is_empty());
+        assert_eq!(
+            states(engine.evaluate("abc", &low, 21 * MINUTE_MS)),
+            vec![AlertState::Triggered]
+        );
+        assert!(engine.evaluate("abc", &low, 22 * MINUTE_MS).is_empty());
+        assert_eq!(
+            states(engine.evaluate("abc", &ok, 23 * MINUTE_MS)),
+            vec![AlertState::Resolved]
+        );
+    }
+
+    #[test]
+    fn test_missing_metrics_and_scope() {
+        let mut engine = AlertEngine::new(vec![
+            rule(vec![battery_below(15.0, 0.0)]),
+            AlertRule {
+                id: "other-device".to_string(),
+                device_id: Some("def".to_string()),
+                ..rule(vec![battery_below(15.0, 0.0)])
+            },
+        ]);
+        assert_eq!(engine.evaluate("abc", &health(10, 30, false), 0).len(), 1);
+        // A poll without battery data doesn't resolve the alert
+        let mut no_battery = health(10, 30, false);
+        no_battery.battery = None;
+        assert!(engine.evaluate("abc", &no_battery, 1).is_empty());
+        assert!(engine.evaluate("abc", &health(10, 30, false), 2).is_empty());
+    }
+
+    #[test]
+    fn test_default_battery_rule_on_parsed_dumpsys() {
+        use crate::services::{AdbHealthProvider, ScriptedExecutor, SharedAdbExecutor};
+        use std::sync::Arc;
+
+        // `status` is BatteryManager's BATTERY_STATUS_*: 2 charging, 3 discharging
+        let polled = |status: u8| {
+            let adb = ScriptedExecutor::new();
+            adb.respond_shell(
+                "dumpsys battery",
+                format!("  level: 8\n  temperature: 300\n  status: {}\n", status),
+            );
+            let provider =
+                AdbHealthProvider::with_executor(500, &(Arc::new(adb) as SharedAdbExecutor));
+            let mut health = DeviceHealth::new("abc".to_string());
+            health.battery = Some(provider.get_battery_info("abc").unwrap());
+            health
+        };
+
+        let mut engine = AlertEngine::new(default_rules());
+        assert!(engine.evaluate("abc", &polled(2), 0).is_empty());
+        let fired = engine.evaluate("abc", &polled(3), 1);
+        assert_eq!(fired.len(), 1);
+        assert_eq!(fired[0].event.rule_id, "battery-low");
+        assert_eq!(
+            states(engine.evaluate("abc", &polled(2), 2)),
+            vec![AlertState::Resolved]
+        );
+    }
+
+    #[test]
+    fn test_set_rules_validates_and_persists() {
+        let dir = std::env::temp_dir().join(format!("scrcpy-gui-alerts-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let mut engine = AlertEngine::load(&dir);
+        assert_eq!(engine.rules(), default_rules().as_slice());
+
+        let low = rule(vec![battery_below(15.0, 0.0)]);
+        let duplicate = vec![low.clone(), low];
+        assert!(engine.set_rules(duplicate).is_err());
+        assert!(engine.set_rules(vec![rule(vec![])]).is_err());
+
+        let rules = vec![rule(vec![battery_below(15.0, 1.0)])];
+        engine.set_rules(rules.clone()).unwrap();
+        assert_eq!(AlertEngine::load(&dir).rules(), rules.as_slice());
+        // Written through a temp file that is renamed into place
+        assert!(!dir.join(RULES_FILE).with_extension("tmp").exists());
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_rules_wire_format() {
+        let json = serde_json::json!({
+            "id": "hot",
+            "name": "Hot",
+            "conditions": [
+                { "type": "threshold", "metric": "batteryTemperature",
+                  "comparison": "above", "value": 42 },
+                { "type": "quality", "level": "poor" }
+            ],
+            "severity": "critical",
+            "minDurationMs": 120000
+        });
+        let rule: AlertRule = serde_json::from_value(json).unwrap();
+        assert!(rule.enabled);
+        assert_eq!(rule.min_polls, 1);
+        assert_eq!(rule.min_duration_ms, 120_000);
+        assert_eq!(
+            rule.conditions[0],
+            AlertCondition::Threshold {
+                metric: AlertMetric::BatteryTemperature,
+                comparison: Comparison::Above,
+                value: 42.0,
+                hysteresis: 0.0,
+            }
+        );
+    }
+}
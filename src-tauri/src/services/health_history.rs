@@ -0,0 +1,367 @@
+//! Device Health History
+//!
+//! Keeps a bounded ring buffer of timestamped samples (battery, temperature,
+//! free storage, latency) per device, recorded on every successful poll, so
+//! trends over a mirroring session can be charted and summarized.
+//!
+//! The query helpers are plain functions over a slice of samples: range
+//! selection, time-bucketed downsampling, min/max/avg per metric and the
+//! battery drain rate while discharging.
+
+use crate::types::health::{DeviceHealth, HealthSample, HealthTrendsResponse, MetricStats};
+use std::collections::{HashMap, VecDeque};
+
+/// Samples kept per device: six hours at the 1 s USB interval
+pub const HISTORY_CAPACITY: usize = 21_600;
+
+/// Shortest discharging time a drain rate is reported for; battery level is
+/// whole percent, so shorter windows mostly measure rounding
+const MIN_DRAIN_WINDOW_MS: u64 = 5 * 60 * 1000;
+
+/// Consecutive samples further apart than this don't count toward the drain
+/// rate: the device may have been charged or switched off in between
+const MAX_SAMPLE_GAP_MS: u64 = 10 * 60 * 1000;
+
+const MS_PER_HOUR: f64 = 3_600_000.0;
+
+impl HealthSample {
+    /// Sample of a polled snapshot; `None` if it carries no metrics
+    pub fn from_health(health: &DeviceHealth, timestamp: u64) -> Option<Self> {
+        let battery = health.battery.as_ref();
+        let sample = Self {
+            timestamp,
+            battery_percentage: battery.map(|b| b.percentage),
+            battery_temperature: battery.and_then(|b| b.temperature),
+            is_charging: battery.and_then(|b| b.is_charging),
+            storage_free: health.storage.as_ref().map(|s| s.free),
+            latency: health.connection.as_ref().map(|c| c.latency),
+        };
+        (sample.battery_percentage.is_some()
+            || sample.storage_free.is_some()
+            || sample.latency.is_some())
+        .then_some(sample)
+    }
+}
+
+/// Per-device sample buffers, oldest first
+pub struct HealthHistory {
+    capacity: usize,
+    devices: HashMap<String, VecDeque<HealthSample>>,
+}
+
+impl HealthHistory {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            devices: HashMap::new(),
+        }
+    }
+
+    /// Append a sample, dropping the oldest once the buffer is full
+    pub fn record(&mut self, device_id: &str, sample: HealthSample) {
+        let samples = self.devices.entry(device_id.to_string()).or_default();
+        // Polls finish out of order only across restarts; keep time sorted
+        if samples
+            .back()
+            .is_some_and(|last| last.timestamp > sample.timestamp)
+        {
+            return;
+        }
+        if samples.len() == self.capacity {
+            samples.pop_front();
+        }
+        samples.push_back(sample);
+    }
+
+    /// Samples with `from <= timestamp <= to`; open ends are unbounded
+    pub fn range(&self, device_id: &str, from: Option<u64>, to: Option<u64>) -> Vec<HealthSample> {
+        let Some(samples) = self.devices.get(device_id) else {
+            return Vec::new();
+        };
+        let from = from.unwrap_or(0);
+        let to = to.unwrap_or(u64::MAX);
+        let start = samples.partition_point(|s| s.timestamp < from);
+        let end = samples.partition_point(|s| s.timestamp <= to);
+        samples.range(start..end.max(start)).cloned().collect()
+    }
+}
+
+impl Default for HealthHistory {
+    fn default() -> Self {
+        Self::new(HISTORY_CAPACITY)
+    }
+}
+
+/// Average samples into at most `max_points` equal time buckets.
+///
+/// Each bucket becomes one sample at its mean timestamp, with every metric
+/// averaged over the samples that have it and the last charging state.
+pub fn downsample(samples: &[HealthSample], max_points: usize) -> Vec<HealthSample> {
+    if max_points == 0 || samples.len() <= max_points {
+        return samples.to_vec();
+    }
+    let first = samples[0].timestamp;
+    let span = samples[samples.len() - 1].timestamp - first + 1;
+
+    let mut buckets: Vec<Vec<&HealthSample>> = vec![Vec::new(); max_points];
+    for sample in samples {
+        let offset = (sample.timestamp - first) as u128;
+        let index = (offset * max_points as u128 / span as u128) as usize;
+        buckets[index.min(max_points - 1)].push(sample);
+    }
+
+    buckets
+        .into_iter()
+        .filter(|bucket| !bucket.is_empty())
+        .map(|bucket| merge(&bucket))
+        .collect()
+}
+
+fn merge(bucket: &[&HealthSample]) -> HealthSample {
+    fn mean<T: Copy + Into<f64>>(values: impl Iterator<Item = T>) -> Option<f64> {
+        let (sum, count) = values.fold((0.0, 0usize), |(sum, n), v| (sum + v.into(), n + 1));
+        (count > 0).then(|| sum / count as f64)
+    }
+
+    let timestamps = bucket.iter().map(|s| s.timestamp as f64);
+    HealthSample {
+        timestamp: mean(timestamps).unwrap_or_default().round() as u64,
+        battery_percentage: mean(bucket.iter().filter_map(|s| s.battery_percentage))
+            .map(|v| v.round() as u32),
+        battery_temperature: mean(bucket.iter().filter_map(|s| s.battery_temperature))
+            .map(|v| v.round() as i32),
+        is_charging: bucket.iter().rev().find_map(|s| s.is_charging),
+        // u64 has no lossless f64 conversion; byte precision is irrelevant here
+        storage_free: mean(
+            bucket
+                .iter()
+                .filter_map(|s| s.storage_free.map(|v| v as f64)),
+        )
+        .map(|v| v.round() as u64),
+        latency: mean(bucket.iter().filter_map(|s| s.latency)).map(|v| v.round() as u32),
+    }
+}
+
+/// Min/max/avg of the values present
+pub fn metric_stats(values: impl Iterator<Item = f64>) -> Option<MetricStats> {
+    let mut stats: Option<MetricStats> = None;
+    let mut sum = 0.0;
+    for value in values {
+        sum += value;
+        let s = stats.get_or_insert(MetricStats {
+            min: value,
+            max: value,
+            avg: 0.0,
+            count: 0,
+        });
+        s.min = s.min.min(value);
+        s.max = s.max.max(value);
+        s.count += 1;
+    }
+    stats.map(|mut s| {
+        s.avg = sum / s.count as f64;
+        s
+    })
+}
+
+/// Battery drain in %/hour over the stretches where the device was not
+/// charging; positive while draining.
+///
+/// `None` until at least `MIN_DRAIN_WINDOW_MS` of discharging was observed.
+pub fn battery_drain_per_hour(samples: &[HealthSample]) -> Option<f64> {
+    let mut dropped = 0.0;
+    let mut elapsed_ms = 0u64;
+    let mut previous: Option<(u64, u32)> = None;
+
+    for sample in samples {
+        let current = match (sample.battery_percentage, sample.is_charging) {
+            (Some(level), Some(false) | None) => Some((sample.timestamp, level)),
+            _ => None,
+        };
+        if let (Some((t0, level0)), Some((t1, level1))) = (previous, current) {
+            let gap = t1.saturating_sub(t0);
+            if gap <= MAX_SAMPLE_GAP_MS {
+                dropped += level0 as f64 - level1 as f64;
+                elapsed_ms += gap;
+            }
+        }
+        previous = current;
+    }
+
+    (elapsed_ms >= MIN_DRAIN_WINDOW_MS).then(|| dropped / elapsed_ms as f64 * MS_PER_HOUR)
+}
+
+/// Summary of a device's samples for `get_health_trends`
+pub fn trends(device_id: &str, samples: &[HealthSample]) -> HealthTrendsResponse {
+    HealthTrendsResponse {
+        device_id: device_id.to_string(),
+        sample_count: samples.len(),
+        battery_percentage: metric_stats(
+            samples
+                .iter()
+                .filter_map(|s| s.battery_percentage.map(f64::from)),
+        ),
+        battery_temperature: metric_stats(
+            samples
+                .iter()
+                .filter_map(|s| s.battery_temperature.map(f64::from)),
+        ),
+        storage_free: metric_stats(
+            samples
+                .iter()
+                .filter_map(|s| s.storage_free.map(|v| v as f64)),
+        ),
+        latency: metric_stats(samples.iter().filter_map(|s| s.latency.map(f64::from))),
+        battery_drain_per_hour: battery_drain_per_hour(samples),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MINUTE: u64 = 60_000;
+
+    fn sample(timestamp: u64, battery: u32, charging: bool) -> HealthSample {
+        HealthSample {
+            timestamp,
+            battery_percentage: Some(battery),
+            battery_temperature: Some(30),
+            is_charging: Some(charging),
+            storage_free: Some(1_000),
+            latency: Some(20),
+        }
+    }
+
+    #[test]
+    fn test_ring_buffer_drops_oldest() {
+        let mut history = HealthHistory::new(3);
+        for t in 1..=5 {
+            history.record("abc", sample(t, 90, false));
+        }
+
+        let samples = history.range("abc", None, None);
+        let timestamps: Vec<u64> = samples.iter().map(|s| s.timestamp).collect();
+        assert_eq!(timestamps, vec![3, 4, 5]);
+        assert!(history.range("other", None, None).is_empty());
+    }
+
+    #[test]
+    fn test_range_is_inclusive() {
+        let mut history = HealthHistory::new(10);
+        for t in [10, 20, 30, 40] {
+            history.record("abc", sample(t, 90, false));
+        }
+
+        let timestamps = |from, to| -> Vec<u64> {
+            history
+                .range("abc", from, to)
+                .iter()
+                .map(|s| s.timestamp)
+                .collect()
+        };
+        assert_eq!(timestamps(Some(20), Some(30)), vec![20, 30]);
+        assert_eq!(timestamps(Some(25), None), vec![30, 40]);
+        assert_eq!(timestamps(None, Some(5)), Vec::<u64>::new());
+        assert_eq!(timestamps(Some(40), Some(10)), Vec::<u64>::new());
+    }
+
+    #[test]
+    fn test_downsample_averages_buckets() {
+        let samples: Vec<HealthSample> = (0..100)
+            .map(|i| HealthSample {
+                latency: Some(i as u32),
+                ..sample(i * 1000, 100 - i as u32 / 10, false)
+            })
+            .collect();
+
+        let points = downsample(&samples, 10);
+        assert_eq!(points.len(), 10);
+        assert_eq!(points[0].latency, Some(5)); // mean of 0..=9, rounded
+        assert_eq!(points[0].battery_percentage, Some(100));
+        assert_eq!(points[9].battery_percentage, Some(91));
+        assert!(points.windows(2).all(|w| w[0].timestamp < w[1].timestamp));
+
+        // Nothing to do when the series already fits
+        assert_eq!(downsample(&samples[..5], 10).len(), 5);
+    }
+
+    #[test]
+    fn test_stats_skip_missing_metrics() {
+        let mut samples = vec![sample(0, 80, false), sample(1, 60, false)];
+        samples.push(HealthSample {
+            battery_percentage: None,
+            ..sample(2, 0, false)
+        });
+
+        let trends = trends("abc", &samples);
+        let battery = trends.battery_percentage.unwrap();
+        assert_eq!((battery.min, battery.max, battery.avg), (60.0, 80.0, 70.0));
+        assert_eq!(battery.count, 2);
+        assert_eq!(trends.latency.unwrap().count, 3);
+        assert_eq!(trends.sample_count, 3);
+    }
+
+    #[test]
+    fn test_drain_rate_ignores_charging() {
+        // 100% -> 90% over 30 min discharging, then charging back up
+        let samples = vec![
+            sample(0, 100, false),
+            sample(10 * MINUTE, 97, false),
+            sample(20 * MINUTE, 93, false),
+            sample(30 * MINUTE, 90, false),
+            sample(40 * MINUTE, 95, true),
+            sample(50 * MINUTE, 100, true),
+        ];
+
+        let rate = battery_drain_per_hour(&samples).unwrap();
+        assert!((rate - 20.0).abs() < 1e-9, "rate was {}", rate);
+    }
+
+    #[test]
+    fn test_drain_rate_from_parsed_dumpsys() {
+        use crate::services::{AdbHealthProvider, ScriptedExecutor, SharedAdbExecutor};
+        use std::sync::Arc;
+
+        // `status` is BatteryManager's BATTERY_STATUS_*: 2 charging, 3 discharging
+        let polled = |timestamp: u64, level: u32, status: u8| {
+            let adb = ScriptedExecutor::new();
+            adb.respond_shell(
+                "dumpsys battery",
+                format!(
+                    "  level: {}\n  temperature: 300\n  status: {}\n",
+                    level, status
+                ),
+            );
+            let provider =
+                AdbHealthProvider::with_executor(500, &(Arc::new(adb) as SharedAdbExecutor));
+            let mut health = DeviceHealth::new("abc".to_string());
+            health.battery = Some(provider.get_battery_info("abc").unwrap());
+            HealthSample::from_health(&health, timestamp).unwrap()
+        };
+
+        // Charging 50% -> 60%, then unplugged and draining 60% -> 55%
+        let samples = vec![
+            polled(0, 50, 2),
+            polled(10 * MINUTE, 54, 2),
+            polled(20 * MINUTE, 57, 2),
+            polled(30 * MINUTE, 60, 2),
+            polled(35 * MINUTE, 59, 3),
+            polled(45 * MINUTE, 56, 3),
+            polled(50 * MINUTE, 55, 3),
+        ];
+
+        let rate = battery_drain_per_hour(&samples).unwrap();
+        assert!((rate - 16.0).abs() < 1e-9, "rate was {}", rate);
+    }
+
+    #[test]
+    fn test_drain_rate_needs_enough_data() {
+        let samples = vec![sample(0, 100, false), sample(MINUTE, 99, false)];
+        assert!(battery_drain_per_hour(&samples).is_none());
+
+        // A gap (app closed, phone charged elsewhere) doesn't count as drain
+        let samples = vec![sample(0, 100, false), sample(120 * MINUTE, 40, false)];
+        assert!(battery_drain_per_hour(&samples).is_none());
+    }
+}
@@ -1,12 +1,13 @@
 //! Health Poller Service
 //!
 //! Implements polling logic with exponential backoff and error recovery.
-//! Handles transient failures (offline, timeout) with retry attempts.
-//! Emits error events on each retry for UI feedback.
+//! Failures are classified by their `AppError` code: transient ones (offline,
+//! timeout) are retried, and each scheduled retry is reported for UI
+//! feedback.
 
+use crate::error::AppError;
 use crate::types::*;
 use std::time::Duration;
-use tauri::Emitter;
 
 /// Error information
 #[derive(Debug, Clone, serde::Serialize)]
@@ -30,43 +31,6 @@ pub struct PollingErrorEvent {
     pub will_retry: bool,
 }
 
-/// Transient errors that should trigger a retry
-#[derive(Debug, Clone, Copy, PartialEq)]
-pub enum ErrorType {
-    /// Device is offline/unreachable
-    Offline,
-    /// Command execution timeout
-    Timeout,
-    /// Network connection issue
-    NetworkError,
-    /// Permanent error - do not retry
-    Permanent,
-}
-
-/// Determine if an error is transient (should retry) or permanent
-pub fn classify_error(error: &str) -> ErrorType {
-    let lower = error.to_lowercase();
-
-    if lower.contains("offline")
-        || lower.contains("not found")
-        || lower.contains("device not found")
-    {
-        ErrorType::Offline
-    } else if lower.contains("timeout") || lower.contains("timed out") {
-        ErrorType::Timeout
-    } else if lower.contains("connection") || lower.contains("network") {
-        ErrorType::NetworkError
-    } else if lower.contains("parsing")
-        || lower.contains("format")
-        || lower.contains("not found in output")
-    {
-        // Parsing errors are permanent - keep retrying until max_retries
-        ErrorType::Timeout
-    } else {
-        ErrorType::Permanent
-    }
-}
-
 /// Calculate exponential backoff duration
 ///
 /// Formula: base_ms * multiplier^(attempt - 1)
@@ -91,76 +55,78 @@ pub fn calculate_backoff(attempt: u32, config: &HealthPollingConfig) -> Duration
     Duration::from_millis(capped_ms as u64)
 }
 
-/// Poll device with automatic retry and exponential backoff
-///
-/// Generic retry wrapper for async operations with exponential backoff.
-/// Attempts to execute async operation with retry logic:
-/// 1. Try to execute operation
-/// 2. On transient error, wait and retry up to max_retries times
-/// 3. Emit polling-error event on each retry
-/// 4. Return final error after max_retries exhausted
-///
-/// # Arguments
-/// * `app_handle` - Tauri app handle for emitting events
-/// * `device_id` - Device identifier for error reporting
-/// * `config` - Health polling config with retry settings
-/// * `operation` - Async closure that performs the actual operation
-pub async fn poll_device_with_retry<F, T>(
-    app_handle: &tauri::AppHandle,
-    device_id: &str,
-    config: &HealthPollingConfig,
-    operation: F,
-) -> Result<T, String>
-where
-    F: Fn() -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<T, String>>>>,
-{
-    let max_attempts = config.max_retries;
-
-    for attempt in 1..=max_attempts {
-        // Try to execute operation
-        match operation().await {
-            Ok(result) => {
-                // Success - return immediately
-                return Ok(result);
-            }
-            Err(error) => {
-                let error_type = classify_error(&error);
-                let is_last_attempt = attempt >= max_attempts;
-                let will_retry = !is_last_attempt && error_type != ErrorType::Permanent;
-
-                // Emit error event for UI feedback (only if there will be a retry)
-                if will_retry {
-                    let event = PollingErrorEvent {
-                        device_id: device_id.to_string(),
-                        error: ErrorInfo {
-                            code: format!("{:?}", error_type),
-                            message: error.clone(),
-                        },
-                        attempt,
-                        max_attempts,
-                        next_retry_at: None, // Would be calculated backoff time if needed
-                        will_retry: true,
-                    };
-
-                    let _ = app_handle.emit("polling-error", event);
-                }
-
-                // If this was the last attempt or error is permanent, return error
-                if is_last_attempt || error_type == ErrorType::Permanent {
-                    return Err(format!("Failed after {} attempts: {}", attempt, error));
-                }
-
-                // Calculate backoff and wait before retry
-                let backoff = calculate_backoff(attempt, config);
-                tokio::time::sleep(backoff).await;
-            }
+/// Where a device with failing polls stands after its latest failure
+#[derive(Debug, Clone)]
+pub struct RetryStep {
+    pub state: ReconnectionState,
+    /// Backing off toward `state.next_retry_at`. False once the retries are
+    /// used up or the error is permanent; the device is then left to the
+    /// regular polling interval until it answers again.
+    pub will_retry: bool,
+}
+
+impl RetryStep {
+    /// Advance the retry state after a failed poll.
+    ///
+    /// `previous` is the step of the last failure in the current streak (a
+    /// success ends the streak). `regular_interval_ms` is when the next
+    /// poll happens once the device is no longer backed off.
+    pub fn after_failure(
+        previous: Option<&RetryStep>,
+        device_id: &str,
+        error: &AppError,
+        now: u64,
+        regular_interval_ms: u64,
+        config: &HealthPollingConfig,
+    ) -> Self {
+        let attempt = previous.map_or(1, |step| step.state.attempt + 1);
+        let will_retry = attempt < config.max_retries && error.code.is_transient();
+        let delay_ms = if will_retry {
+            calculate_backoff(attempt, config).as_millis() as u64
+        } else {
+            regular_interval_ms
+        };
+
+        Self {
+            state: ReconnectionState {
+                device_id: device_id.to_string(),
+                attempt,
+                max_attempts: config.max_retries,
+                next_retry_at: now + delay_ms,
+                last_error: crate::types::health::ErrorInfo {
+                    code: error.code,
+                    message: error.message.clone(),
+                },
+                started_at: previous.map_or(now, |step| step.state.started_at),
+            },
+            will_retry,
         }
     }
 
-    Err(format!(
-        "Failed to complete operation after {} retries",
-        max_attempts
-    ))
+    /// Whether this failure is news to the UI: every scheduled retry, plus
+    /// the failure that ended the retries. Later failures of a device that
+    /// is no longer retried are left to its health state.
+    pub fn should_report(&self, previous: Option<&RetryStep>) -> bool {
+        self.will_retry || previous.is_none_or(|step| step.will_retry)
+    }
+
+    /// `polling-error` payload for this failure
+    pub fn error_event(&self) -> PollingErrorEvent {
+        PollingErrorEvent {
+            device_id: self.state.device_id.clone(),
+            error: ErrorInfo {
+                code: serde_json::to_value(self.state.last_error.code)
+                    .ok()
+                    .and_then(|code| code.as_str().map(str::to_string))
+                    .unwrap_or_default(),
+                message: self.state.last_error.message.clone(),
+            },
+            attempt: self.state.attempt,
+            max_attempts: self.state.max_attempts,
+            next_retry_at: self.will_retry.then_some(self.state.next_retry_at),
+            will_retry: self.will_retry,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -197,27 +163,6 @@ mod tests {
         assert_eq!(calculate_backoff(0, &config), Duration::from_millis(0));
     }
 
-    #[test]
-    fn test_classify_offline_error() {
-        assert_eq!(classify_error("Device not found"), ErrorType::Offline);
-        assert_eq!(classify_error("offline"), ErrorType::Offline);
-    }
-
-    #[test]
-    fn test_classify_timeout_error() {
-        assert_eq!(classify_error("timeout"), ErrorType::Timeout);
-        assert_eq!(classify_error("Command timed out"), ErrorType::Timeout);
-    }
-
-    #[test]
-    fn test_classify_network_error() {
-        assert_eq!(
-            classify_error("connection refused"),
-            ErrorType::NetworkError
-        );
-        assert_eq!(classify_error("network error"), ErrorType::NetworkError);
-    }
-
     /// Test exponential backoff calculation with multiple attempts
     /// Corresponds to Task T067
     #[test]
@@ -279,25 +224,56 @@ mod tests {
         assert_eq!(calculate_backoff(4, &config), Duration::from_millis(3375));
     }
 
-    /// Test error classification for retry logic
-    /// Part of Task T067-T069
+    fn failing_streak(errors: &[AppError], config: &HealthPollingConfig) -> Vec<RetryStep> {
+        let mut steps: Vec<RetryStep> = Vec::new();
+        for (i, error) in errors.iter().enumerate() {
+            let now = 10_000 + i as u64 * 1000;
+            let step = RetryStep::after_failure(steps.last(), "abc", error, now, 1000, config);
+            steps.push(step);
+        }
+        steps
+    }
+
     #[test]
-    fn test_error_classification_for_retries() {
-        // Transient errors should be retried
-        assert_eq!(classify_error("offline"), ErrorType::Offline);
-        assert_eq!(classify_error("device not found"), ErrorType::Offline);
-        assert_eq!(classify_error("timeout"), ErrorType::Timeout);
-        assert_eq!(
-            classify_error("connection refused"),
-            ErrorType::NetworkError
-        );
+    fn test_retry_step_backs_off_until_max_retries() {
+        let config = HealthPollingConfig {
+            retry_backoff_ms: 500,
+            retry_backoff_multiplier: 2.0,
+            max_retries: 3,
+            ..Default::default()
+        };
+        let offline = AppError::new(ErrorCode::Offline, "Device offline: closed");
+        let steps = failing_streak(&vec![offline; 4], &config);
+
+        assert_eq!(steps[0].state.attempt, 1);
+        assert_eq!(steps[0].state.next_retry_at, 10_500);
+        assert_eq!(steps[1].state.next_retry_at, 12_000);
+        assert!(steps[1].will_retry);
+        assert_eq!(steps[1].state.started_at, 10_000);
+
+        // Third failure uses up the retries: back to the regular interval
+        assert!(!steps[2].will_retry);
+        assert_eq!(steps[2].state.next_retry_at, 13_000);
+        assert!(steps[2].should_report(Some(&steps[1])));
+        assert!(!steps[3].should_report(Some(&steps[2])));
+
+        let event = steps[0].error_event();
+        assert_eq!(event.error.code, "offline");
+        assert_eq!(event.next_retry_at, Some(10_500));
+        assert_eq!(steps[2].error_event().next_retry_at, None);
+    }
 
-        // Parsing errors are treated as timeout (transient)
-        assert_eq!(classify_error("parsing error"), ErrorType::Timeout);
-        assert_eq!(classify_error("format error"), ErrorType::Timeout);
+    #[test]
+    fn test_retry_step_does_not_retry_permanent_errors() {
+        let config = HealthPollingConfig::default();
+        let unauthorized = AppError::new(ErrorCode::DeviceUnauthorized, "device unauthorized");
+        let steps = failing_streak(&[unauthorized], &config);
 
-        // Permanent errors should not be retried
-        assert_eq!(classify_error("permission denied"), ErrorType::Permanent);
-        assert_eq!(classify_error("invalid argument"), ErrorType::Permanent);
+        assert!(!steps[0].will_retry);
+        assert!(steps[0].should_report(None));
+        assert_eq!(
+            steps[0].state.last_error.code,
+            ErrorCode::DeviceUnauthorized
+        );
     }
 }
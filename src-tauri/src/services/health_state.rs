@@ -0,0 +1,259 @@
+//! Device Health State Machine
+//!
+//! Drives each polled device through Connecting → Online → Stale →
+//! Offline/Error from poll results and the configured thresholds:
+//!
+//! - a successful poll makes the device Online with fresh data
+//! - an unanswered poll keeps the last snapshot but marks it Stale, until
+//!   nothing has been heard for `offline_threshold` and the device goes Offline
+//! - data older than `stale_threshold` is Stale even if polls are still due
+//! - an error retrying can't fix puts the device in Error
+//!
+//! All functions take the current time so transitions can be tested without
+//! waiting on a clock. Each returns the new health and the reason to report
+//! in `device-health-update`, or `None` when nothing changed.
+
+use crate::types::health::{
+    DeviceHealth, DeviceState, HealthPollingConfig, HealthUpdateReason, StalenessLevel,
+};
+
+/// Result of one poll, as far as the state machine cares
+#[derive(Debug, Clone)]
+pub enum PollOutcome {
+    /// The device answered with a fresh snapshot
+    Healthy(Box<DeviceHealth>),
+    /// The device didn't answer (offline or timed out); may come back
+    Unreachable(String),
+    /// The device answered with an error that retrying won't fix
+    Failed(String),
+}
+
+/// A state change to store and emit
+#[derive(Debug, Clone)]
+pub struct Transition {
+    pub health: DeviceHealth,
+    pub reason: HealthUpdateReason,
+}
+
+/// Initial entry for a device that hasn't answered yet
+pub fn connecting(device_id: &str, now: u64) -> DeviceHealth {
+    let mut health = DeviceHealth::new(device_id.to_string());
+    health.last_seen = now;
+    health
+}
+
+/// Apply the result of a poll to the device's current health
+pub fn on_poll(
+    current: &DeviceHealth,
+    outcome: PollOutcome,
+    now: u64,
+    config: &HealthPollingConfig,
+) -> Option<Transition> {
+    match outcome {
+        PollOutcome::Healthy(mut health) => {
+            // Every answer is reported: the metrics themselves changed
+            health.state = DeviceState::Online;
+            health.last_seen = now;
+            health.last_updated = now;
+            health.error_reason = None;
+            Some(Transition {
+                health: *health,
+                reason: HealthUpdateReason::Poll,
+            })
+        }
+        PollOutcome::Unreachable(reason) => {
+            let silent_for = now.saturating_sub(current.last_seen);
+            if silent_for >= config.offline_threshold as u64 {
+                if current.state == DeviceState::Offline {
+                    return None;
+                }
+                let mut health = current.clone();
+                health.mark_offline(current.last_seen);
+                health.error_reason = Some(reason);
+                return Some(Transition {
+                    health,
+                    reason: HealthUpdateReason::Offline,
+                });
+            }
+
+            // Still within the offline threshold: keep showing the last
+            // snapshot, flagged as stale
+            if current.state != DeviceState::Online || current.staleness == StalenessLevel::Stale {
+                return None;
+            }
+            let mut health = current.clone();
+            health.staleness = StalenessLevel::Stale;
+            health.error_reason = Some(reason);
+            Some(Transition {
+                health,
+                reason: HealthUpdateReason::Stale,
+            })
+        }
+        PollOutcome::Failed(reason) => {
+            if current.state == DeviceState::Error
+                && current.error_reason.as_deref() == Some(reason.as_str())
+            {
+                return None;
+            }
+            let mut health = current.clone();
+            health.mark_error(now, reason);
+            if health.staleness == StalenessLevel::Fresh {
+                health.staleness = StalenessLevel::Stale;
+            }
+            Some(Transition {
+                health,
+                reason: HealthUpdateReason::Error,
+            })
+        }
+    }
+}
+
+/// Age the current snapshot without a poll result
+pub fn on_tick(
+    current: &DeviceHealth,
+    now: u64,
+    config: &HealthPollingConfig,
+) -> Option<Transition> {
+    let age = now.saturating_sub(current.last_updated);
+    if current.state != DeviceState::Online
+        || current.staleness != StalenessLevel::Fresh
+        || age < config.stale_threshold as u64
+    {
+        return None;
+    }
+
+    let mut health = current.clone();
+    health.staleness = StalenessLevel::Stale;
+    Some(Transition {
+        health,
+        reason: HealthUpdateReason::Stale,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const START: u64 = 1_000_000;
+
+    fn config() -> HealthPollingConfig {
+        HealthPollingConfig {
+            offline_threshold: 5000,
+            stale_threshold: 30000,
+            ..HealthPollingConfig::default()
+        }
+    }
+
+    fn snapshot(device_id: &str) -> Box<DeviceHealth> {
+        let mut health = DeviceHealth::new(device_id.to_string());
+        health.staleness = StalenessLevel::Fresh;
+        Box::new(health)
+    }
+
+    /// Feed outcomes at the given times, returning the reasons emitted
+    fn run(events: Vec<(u64, PollOutcome)>) -> (DeviceHealth, Vec<(u64, HealthUpdateReason)>) {
+        let mut health = connecting("abc", START);
+        let mut emitted = Vec::new();
+        for (at, outcome) in events {
+            if let Some(transition) = on_poll(&health, outcome, START + at, &config()) {
+                emitted.push((at, transition.reason.clone()));
+                health = transition.health;
+            }
+        }
+        (health, emitted)
+    }
+
+    fn unreachable() -> PollOutcome {
+        PollOutcome::Unreachable("Device offline: closed".to_string())
+    }
+
+    #[test]
+    fn test_connecting_to_online() {
+        let (health, emitted) = run(vec![(1000, PollOutcome::Healthy(snapshot("abc")))]);
+
+        assert_eq!(health.state, DeviceState::Online);
+        assert_eq!(health.staleness, StalenessLevel::Fresh);
+        assert_eq!(health.last_seen, START + 1000);
+        assert_eq!(emitted, vec![(1000, HealthUpdateReason::Poll)]);
+    }
+
+    #[test]
+    fn test_online_goes_stale_then_offline() {
+        let (health, emitted) = run(vec![
+            (1000, PollOutcome::Healthy(snapshot("abc"))),
+            (2000, unreachable()),
+            (3000, unreachable()),
+            (5999, unreachable()),
+            (6000, unreachable()),
+            (7000, unreachable()),
+        ]);
+
+        assert_eq!(
+            emitted,
+            vec![
+                (1000, HealthUpdateReason::Poll),
+                (2000, HealthUpdateReason::Stale),
+                (6000, HealthUpdateReason::Offline),
+            ]
+        );
+        assert_eq!(health.state, DeviceState::Offline);
+        assert_eq!(health.staleness, StalenessLevel::Offline);
+        // Offline keeps the time the device was last heard from
+        assert_eq!(health.last_seen, START + 1000);
+    }
+
+    #[test]
+    fn test_stale_device_recovers() {
+        let (health, emitted) = run(vec![
+            (1000, PollOutcome::Healthy(snapshot("abc"))),
+            (2000, unreachable()),
+            (3000, PollOutcome::Healthy(snapshot("abc"))),
+        ]);
+
+        assert_eq!(emitted.last(), Some(&(3000, HealthUpdateReason::Poll)));
+        assert_eq!(health.state, DeviceState::Online);
+        assert_eq!(health.staleness, StalenessLevel::Fresh);
+        assert!(health.error_reason.is_none());
+    }
+
+    #[test]
+    fn test_connecting_device_that_never_answers_goes_offline() {
+        let (health, emitted) = run(vec![(1000, unreachable()), (5000, unreachable())]);
+
+        assert_eq!(emitted, vec![(5000, HealthUpdateReason::Offline)]);
+        assert_eq!(health.state, DeviceState::Offline);
+    }
+
+    #[test]
+    fn test_permanent_failure_is_an_error_once() {
+        let denied = || PollOutcome::Failed("permission denied".to_string());
+        let (health, emitted) = run(vec![
+            (1000, PollOutcome::Healthy(snapshot("abc"))),
+            (2000, denied()),
+            (3000, denied()),
+        ]);
+
+        assert_eq!(
+            emitted,
+            vec![
+                (1000, HealthUpdateReason::Poll),
+                (2000, HealthUpdateReason::Error)
+            ]
+        );
+        assert_eq!(health.state, DeviceState::Error);
+        assert_eq!(health.staleness, StalenessLevel::Stale);
+        assert!(health.battery.is_none());
+    }
+
+    #[test]
+    fn test_old_data_turns_stale_on_tick() {
+        let (health, _) = run(vec![(0, PollOutcome::Healthy(snapshot("abc")))]);
+
+        assert!(on_tick(&health, START + 29_999, &config()).is_none());
+        let transition = on_tick(&health, START + 30_000, &config()).unwrap();
+        assert_eq!(transition.reason, HealthUpdateReason::Stale);
+        assert_eq!(transition.health.state, DeviceState::Online);
+        assert_eq!(transition.health.staleness, StalenessLevel::Stale);
+        assert!(on_tick(&transition.health, START + 40_000, &config()).is_none());
+    }
+}
@@ -0,0 +1,746 @@
+//! Persistent Health History
+//!
+//! Appends every recorded `HealthSample` as one JSON line to
+//! `health-history/samples.jsonl` in the app data dir, next to
+//! `devices.json`, so soak-test data survives restarts.
+//!
+//! - Appends are single line writes; a line torn by a crash is skipped when
+//!   reading and dropped by the next compaction.
+//! - Compaction streams the file into a new one without samples past the
+//!   retention age, dropping the oldest while it is over the size limit. The
+//!   new file is synced next to the old one, then renamed over it, so a crash
+//!   leaves either the old or the new file.
+//! - Compaction is due after opening, when the file outgrows the size limit,
+//!   and hourly. `compact_unlocked` runs the rewrite without holding the
+//!   store: `begin_compaction` snapshots the file length, `Compaction::run`
+//!   rewrites that prefix, and finishing carries over lines appended meanwhile.
+
+use crate::error::{AppError, AppResult};
+use crate::types::health::HealthSample;
+use chrono::{TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+const SAMPLES_FILE: &str = "samples.jsonl";
+const RETENTION_FILE: &str = "retention.json";
+
+/// How often appends trigger an age-based compaction
+const COMPACTION_INTERVAL_MS: u64 = 60 * 60 * 1000;
+
+/// Minimum spacing of size-based compactions, so one that keeps failing
+/// isn't retried on every append
+const COMPACTION_RETRY_MS: u64 = 60 * 1000;
+
+/// Size compaction shrinks the file to this share of the limit, so it isn't
+/// rewritten again on the very next append
+const COMPACTION_TARGET: f64 = 0.8;
+
+const MS_PER_HOUR: u64 = 60 * 60 * 1000;
+const BYTES_PER_MB: u64 = 1024 * 1024;
+
+/// How much history is kept on disk
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RetentionPolicy {
+    /// Samples older than this are dropped (default: 7 days)
+    pub max_age_hours: u32,
+    /// The oldest samples are dropped beyond this size (default: 100 MB)
+    pub max_size_mb: u32,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            max_age_hours: 7 * 24,
+            max_size_mb: 100,
+        }
+    }
+}
+
+impl RetentionPolicy {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_age_hours < 1 {
+            return Err("max_age_hours must be >= 1".to_string());
+        }
+        if self.max_size_mb < 1 {
+            return Err("max_size_mb must be >= 1".to_string());
+        }
+        Ok(())
+    }
+
+    fn max_age_ms(&self) -> u64 {
+        self.max_age_hours as u64 * MS_PER_HOUR
+    }
+
+    fn max_bytes(&self) -> u64 {
+        self.max_size_mb as u64 * BYTES_PER_MB
+    }
+}
+
+/// One line of `samples.jsonl`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StoredSample {
+    pub device_id: String,
+    #[serde(flatten)]
+    pub sample: HealthSample,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Csv,
+    Jsonl,
+}
+
+impl ExportFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Jsonl => "jsonl",
+        }
+    }
+}
+
+/// Which samples to export; `None` fields match everything
+#[derive(Debug, Clone, Default)]
+pub struct ExportFilter {
+    pub device_id: Option<String>,
+    pub from: Option<u64>,
+    pub to: Option<u64>,
+}
+
+impl ExportFilter {
+    fn matches(&self, stored: &StoredSample) -> bool {
+        let t = stored.sample.timestamp;
+        self.device_id
+            .as_deref()
+            .is_none_or(|id| id == stored.device_id)
+            && self.from.is_none_or(|from| t >= from)
+            && self.to.is_none_or(|to| t <= to)
+    }
+}
+
+pub struct HistoryStore {
+    dir: PathBuf,
+    retention: RetentionPolicy,
+    /// Append handle to `samples.jsonl`
+    file: File,
+    size: u64,
+    last_compaction: u64,
+    /// Id of the compaction in flight, if any
+    compacting: Option<u64>,
+    next_compaction: u64,
+}
+
+/// A rewrite of `samples.jsonl` up to a snapshotted length, run without
+/// holding the store
+pub struct Compaction {
+    id: u64,
+    path: PathBuf,
+    tmp: PathBuf,
+    /// File length when the compaction began; later lines are carried over
+    snapshot: u64,
+    cutoff: u64,
+    max_bytes: u64,
+    /// Bytes written to `tmp` by `run`
+    written: u64,
+}
+
+impl HistoryStore {
+    /// Open (or create) the store in `dir`. Also returns the samples within
+    /// the retention age, oldest first, so callers don't read the file again.
+    /// Compaction is due on the first append.
+    pub fn open(dir: &Path, now: u64) -> AppResult<(Self, Vec<StoredSample>)> {
+        fs::create_dir_all(dir)
+            .map_err(|e| AppError::io("Failed to create health history directory", e))?;
+        let retention = load_retention(dir);
+        let path = dir.join(SAMPLES_FILE);
+        let mut file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| AppError::io("Failed to open health history", e))?;
+        let mut size = file
+            .metadata()
+            .map_err(|e| AppError::io("Failed to open health history", e))?
+            .len();
+        // End a line torn by a crash so the next append starts a fresh one
+        if size > 0 && !ends_with_newline(&mut file, size) {
+            file.write_all(b"\n")
+                .map_err(|e| AppError::io("Failed to append health sample", e))?;
+            size += 1;
+        }
+
+        let cutoff = now.saturating_sub(retention.max_age_ms());
+        let samples = read_samples(&path)?
+            .into_iter()
+            .filter(|stored| stored.sample.timestamp >= cutoff)
+            .collect();
+        let store = Self {
+            dir: dir.to_path_buf(),
+            retention,
+            file,
+            size,
+            last_compaction: 0,
+            compacting: None,
+            next_compaction: 0,
+        };
+        Ok((store, samples))
+    }
+
+    /// Every readable sample on disk, oldest first
+    pub fn load(&self) -> AppResult<Vec<StoredSample>> {
+        read_samples(&self.dir.join(SAMPLES_FILE))
+    }
+
+    /// Append a sample. Compaction is left to the caller; see `compaction_due`.
+    pub fn append(&mut self, device_id: &str, sample: &HealthSample) -> AppResult<()> {
+        let stored = StoredSample {
+            device_id: device_id.to_string(),
+            sample: sample.clone(),
+        };
+        let mut line = serde_json::to_string(&stored)
+            .map_err(|e| AppError::internal(format!("Failed to serialize sample: {}", e)))?;
+        line.push('\n');
+        // One write per line keeps a crash from interleaving partial records
+        self.file
+            .write_all(line.as_bytes())
+            .map_err(|e| AppError::io("Failed to append health sample", e))?;
+        self.size += line.len() as u64;
+        Ok(())
+    }
+
+    /// Whether the file outgrew the size limit or an hourly compaction is
+    /// due, and none is running
+    pub fn compaction_due(&self, now: u64) -> bool {
+        let since = now.saturating_sub(self.last_compaction);
+        self.compacting.is_none()
+            && (since >= COMPACTION_INTERVAL_MS
+                || (self.size > self.retention.max_bytes() && since >= COMPACTION_RETRY_MS))
+    }
+
+    /// Snapshot the file for a compaction, to be run by `compact_unlocked`.
+    /// A compaction begun later supersedes this one.
+    pub fn begin_compaction(&mut self, now: u64) -> AppResult<Compaction> {
+        let path = self.dir.join(SAMPLES_FILE);
+        let snapshot = self
+            .file
+            .metadata()
+            .map_err(|e| AppError::io("Failed to read health history", e))?
+            .len();
+        let id = self.next_compaction;
+        self.next_compaction += 1;
+        self.compacting = Some(id);
+        // Spaces out retries if it fails
+        self.last_compaction = now;
+        Ok(Compaction {
+            id,
+            tmp: self.dir.join(format!("{}.{}.tmp", SAMPLES_FILE, id)),
+            path,
+            snapshot,
+            cutoff: now.saturating_sub(self.retention.max_age_ms()),
+            max_bytes: self.retention.max_bytes(),
+            written: 0,
+        })
+    }
+
+    /// Carry lines appended since the snapshot over to the compacted file and
+    /// put it in place. Superseded compactions are discarded.
+    fn finish_compaction(&mut self, compaction: Compaction) -> AppResult<()> {
+        if self.compacting != Some(compaction.id) {
+            let _ = fs::remove_file(&compaction.tmp);
+            return Ok(());
+        }
+        self.compacting = None;
+
+        let tail = copy_tail(&compaction).map_err(|e| {
+            let _ = fs::remove_file(&compaction.tmp);
+            AppError::io("Failed to compact health history", e)
+        })?;
+        replace_file(&compaction.tmp, &compaction.path)?;
+        self.file = OpenOptions::new()
+            .append(true)
+            .open(&compaction.path)
+            .map_err(|e| AppError::io("Failed to open health history", e))?;
+        self.size = compaction.written + tail;
+        Ok(())
+    }
+
+    /// Drop a compaction whose `run` failed
+    fn abandon_compaction(&mut self, compaction: Compaction) {
+        if self.compacting == Some(compaction.id) {
+            self.compacting = None;
+        }
+        let _ = fs::remove_file(&compaction.tmp);
+    }
+
+    /// Apply the retention policy now, holding the store throughout
+    pub fn compact(&mut self, now: u64) -> AppResult<()> {
+        let mut compaction = self.begin_compaction(now)?;
+        let result = compaction.run();
+        self.end_compaction(compaction, result)
+    }
+
+    fn end_compaction(&mut self, compaction: Compaction, run: AppResult<()>) -> AppResult<()> {
+        match run {
+            Ok(()) => self.finish_compaction(compaction),
+            Err(e) => {
+                self.abandon_compaction(compaction);
+                Err(e)
+            }
+        }
+    }
+
+    pub fn retention(&self) -> &RetentionPolicy {
+        &self.retention
+    }
+
+    /// Persist a new retention policy and apply it
+    pub fn set_retention(&mut self, retention: RetentionPolicy, now: u64) -> AppResult<()> {
+        retention.validate().map_err(AppError::invalid_input)?;
+        let json = serde_json::to_string_pretty(&retention)
+            .map_err(|e| AppError::internal(format!("Failed to serialize retention: {}", e)))?;
+        write_atomically(&self.dir.join(RETENTION_FILE), json.as_bytes())?;
+        self.retention = retention;
+        self.compact(now)
+    }
+
+    /// Write the matching samples to `out`; returns how many were written
+    pub fn export(
+        &self,
+        filter: &ExportFilter,
+        format: ExportFormat,
+        out: &mut impl Write,
+    ) -> AppResult<usize> {
+        let samples: Vec<StoredSample> = self
+            .load()?
+            .into_iter()
+            .filter(|stored| filter.matches(stored))
+            .collect();
+        let written = match format {
+            ExportFormat::Csv => write_csv(&samples, out),
+            ExportFormat::Jsonl => samples.iter().try_for_each(|stored| {
+                let line = serde_json::to_string(stored).map_err(std::io::Error::other)?;
+                writeln!(out, "{}", line)
+            }),
+        };
+        written.map_err(|e| AppError::io("Failed to write export", e))?;
+        Ok(samples.len())
+    }
+}
+
+fn load_retention(dir: &Path) -> RetentionPolicy {
+    match fs::read_to_string(dir.join(RETENTION_FILE)) {
+        Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+            eprintln!("Warning: corrupt {}, using defaults: {}", RETENTION_FILE, e);
+            RetentionPolicy::default()
+        }),
+        Err(_) => RetentionPolicy::default(),
+    }
+}
+
+/// Readable samples of a file; unparsable (torn) lines are skipped
+fn read_samples(path: &Path) -> AppResult<Vec<StoredSample>> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(AppError::io("Failed to open health history", e)),
+    };
+    let mut samples = Vec::new();
+    for line in BufReader::new(file).split(b'\n') {
+        let line = line.map_err(|e| AppError::io("Failed to read health history", e))?;
+        if let Ok(stored) = serde_json::from_slice::<StoredSample>(&line) {
+            samples.push(stored);
+        }
+    }
+    Ok(samples)
+}
+
+/// Run `compaction` without holding `store`, which is only locked to finish
+/// it; appends go on meanwhile
+pub fn compact_unlocked(store: &Mutex<HistoryStore>, mut compaction: Compaction) -> AppResult<()> {
+    let result = compaction.run();
+    store
+        .lock()
+        .map_err(AppError::lock)?
+        .end_compaction(compaction, result)
+}
+
+impl Compaction {
+    /// Stream the snapshotted lines within the retention age into the temp
+    /// file, dropping the oldest while over the size limit. Doesn't touch the
+    /// store, so appends continue meanwhile.
+    pub fn run(&mut self) -> AppResult<()> {
+        let read_error = |e| AppError::io("Failed to read health history", e);
+        // First pass: how much survives the age cutoff
+        let mut kept = 0u64;
+        for line in self.kept_lines().map_err(read_error)? {
+            kept += line.map_err(read_error)?.len() as u64 + 1;
+        }
+        let target = (self.max_bytes as f64 * COMPACTION_TARGET) as u64;
+        let mut excess = if kept > self.max_bytes {
+            kept - target
+        } else {
+            0
+        };
+
+        let write_error = |e| AppError::io("Failed to write compacted health history", e);
+        let mut out = BufWriter::new(File::create(&self.tmp).map_err(write_error)?);
+        self.written = 0;
+        for line in self.kept_lines().map_err(read_error)? {
+            let line = line.map_err(read_error)?;
+            let len = line.len() as u64 + 1;
+            if excess > 0 {
+                excess = excess.saturating_sub(len);
+                continue;
+            }
+            out.write_all(&line)
+                .and_then(|_| out.write_all(b"\n"))
+                .map_err(write_error)?;
+            self.written += len;
+        }
+        out.flush().map_err(write_error)
+    }
+
+    /// Lines of the snapshot that parse and are within the retention age
+    fn kept_lines(&self) -> std::io::Result<impl Iterator<Item = std::io::Result<Vec<u8>>>> {
+        let cutoff = self.cutoff;
+        let file = File::open(&self.path)?;
+        Ok(BufReader::new(file.take(self.snapshot))
+            .split(b'\n')
+            .filter(move |line| match line {
+                Ok(line) => serde_json::from_slice::<StoredSample>(line)
+                    .is_ok_and(|stored| stored.sample.timestamp >= cutoff),
+                Err(_) => true,
+            }))
+    }
+}
+
+/// Append what was written to `samples.jsonl` after the snapshot to the
+/// compacted file and sync it; returns the bytes carried over
+fn copy_tail(compaction: &Compaction) -> std::io::Result<u64> {
+    let mut current = File::open(&compaction.path)?;
+    current.seek(SeekFrom::Start(compaction.snapshot))?;
+    let mut out = OpenOptions::new().append(true).open(&compaction.tmp)?;
+    let tail = std::io::copy(&mut current, &mut out)?;
+    out.sync_all()?;
+    Ok(tail)
+}
+
+/// Whether the last of the `size` bytes of `file` is a newline
+fn ends_with_newline(file: &mut File, size: u64) -> bool {
+    let mut last = [0u8; 1];
+    file.seek(SeekFrom::Start(size - 1))
+        .and_then(|_| file.read_exact(&mut last))
+        .is_ok_and(|_| last[0] == b'\n')
+}
+
+/// The newest `limit` samples of each device, oldest first
+pub fn newest_per_device(samples: Vec<StoredSample>, limit: usize) -> Vec<StoredSample> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    let mut kept: Vec<StoredSample> = samples
+        .into_iter()
+        .rev()
+        .filter(|stored| {
+            let count = counts.entry(stored.device_id.clone()).or_default();
+            *count += 1;
+            *count <= limit
+        })
+        .collect();
+    kept.reverse();
+    kept
+}
+
+/// Replace `path` with `contents` via a synced temp file and a rename
+pub(crate) fn write_atomically(path: &Path, contents: &[u8]) -> AppResult<()> {
+    let tmp = path.with_extension("tmp");
+    let mut file = File::create(&tmp).map_err(|e| AppError::io("Failed to create temp file", e))?;
+    file.write_all(contents)
+        .and_then(|_| file.sync_all())
+        .map_err(|e| AppError::io("Failed to write temp file", e))?;
+    replace_file(&tmp, path)
+}
+
+/// Rename the synced `tmp` over `path`
+fn replace_file(tmp: &Path, path: &Path) -> AppResult<()> {
+    fs::rename(tmp, path).map_err(|e| AppError::io("Failed to replace file", e))?;
+    // Make the rename itself durable; not supported on every platform
+    if let Some(dir) = path.parent() {
+        let _ = File::open(dir).and_then(|dir| dir.sync_all());
+    }
+    Ok(())
+}
+
+fn write_csv(samples: &[StoredSample], out: &mut impl Write) -> std::io::Result<()> {
+    fn field<T: ToString>(value: Option<T>) -> String {
+        value.map(|v| v.to_string()).unwrap_or_default()
+    }
+
+    writeln!(
+        out,
+        "device_id,timestamp,time,battery_percentage,battery_temperature,is_charging,storage_free,latency"
+    )?;
+    for stored in samples {
+        let sample = &stored.sample;
+        let time = Utc
+            .timestamp_millis_opt(sample.timestamp as i64)
+            .single()
+            .map(|t| t.to_rfc3339())
+            .unwrap_or_default();
+        writeln!(
+            out,
+            "{},{},{},{},{},{},{},{}",
+            csv_escape(&stored.device_id),
+            sample.timestamp,
+            time,
+            field(sample.battery_percentage),
+            field(sample.battery_temperature),
+            field(sample.is_charging),
+            field(sample.storage_free),
+            field(sample.latency),
+        )?;
+    }
+    Ok(())
+}
+
+/// Quote a CSV field if needed (mDNS serials may contain commas or quotes)
+fn csv_escape(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOUR: u64 = MS_PER_HOUR;
+    const NOW: u64 = 1_700_000_000_000;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "scrcpy-gui-history-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn sample(timestamp: u64, battery: u32) -> HealthSample {
+        HealthSample {
+            timestamp,
+            battery_percentage: Some(battery),
+            battery_temperature: None,
+            is_charging: Some(false),
+            storage_free: Some(4096),
+            latency: Some(12),
+        }
+    }
+
+    #[test]
+    fn test_samples_survive_reopen() {
+        let dir = temp_dir("reopen");
+        let (mut store, _) = HistoryStore::open(&dir, NOW).unwrap();
+        store.append("abc", &sample(NOW, 90)).unwrap();
+        store.append("def", &sample(NOW + 1, 80)).unwrap();
+        drop(store);
+
+        let (store, samples) = HistoryStore::open(&dir, NOW + 2).unwrap();
+        assert_eq!(samples.len(), 2);
+        assert_eq!(store.load().unwrap().len(), 2);
+        assert_eq!(samples[1].device_id, "def");
+        assert_eq!(samples[1].sample, sample(NOW + 1, 80));
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_torn_line_is_dropped_on_open() {
+        let dir = temp_dir("torn");
+        let (mut store, _) = HistoryStore::open(&dir, NOW).unwrap();
+        store.append("abc", &sample(NOW, 90)).unwrap();
+        drop(store);
+        // Simulate a crash in the middle of an append
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(dir.join(SAMPLES_FILE))
+            .unwrap();
+        file.write_all(br#"{"deviceId":"abc","timest"#).unwrap();
+        drop(file);
+
+        let (mut store, _) = HistoryStore::open(&dir, NOW + 1).unwrap();
+        store.append("abc", &sample(NOW + 1, 89)).unwrap();
+        let samples = store.load().unwrap();
+        assert_eq!(samples.len(), 2);
+        assert_eq!(samples[1].sample.battery_percentage, Some(89));
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_retention_by_age_and_size() {
+        let dir = temp_dir("retention");
+        let (mut store, _) = HistoryStore::open(&dir, NOW).unwrap();
+        for i in 0..10 {
+            let t = NOW - (10 - i) * HOUR;
+            store.append("abc", &sample(t, 50 + i as u32)).unwrap();
+        }
+
+        store
+            .set_retention(
+                RetentionPolicy {
+                    max_age_hours: 5,
+                    max_size_mb: 1,
+                },
+                NOW,
+            )
+            .unwrap();
+        let samples = store.load().unwrap();
+        assert_eq!(samples.len(), 5);
+        assert!(samples.iter().all(|s| s.sample.timestamp >= NOW - 5 * HOUR));
+
+        // The policy is persisted with the data
+        let (reopened, _) = HistoryStore::open(&dir, NOW).unwrap();
+        assert_eq!(reopened.retention().max_age_hours, 5);
+        drop(reopened);
+
+        // Size: appending past the 1 MB limit drops the oldest samples
+        let line_len = serde_json::to_string(&StoredSample {
+            device_id: "abc".to_string(),
+            sample: sample(NOW, 50),
+        })
+        .unwrap()
+        .len() as u64
+            + 1;
+        let per_mb = BYTES_PER_MB / line_len + 1;
+        for i in 0..per_mb {
+            store.append("abc", &sample(NOW + i, 50)).unwrap();
+        }
+        assert!(!store.compaction_due(NOW));
+        assert!(store.compaction_due(NOW + COMPACTION_RETRY_MS));
+        store.compact(NOW).unwrap();
+        assert!(store.size <= BYTES_PER_MB);
+        let samples = store.load().unwrap();
+        assert!((samples.len() as u64) < per_mb);
+        assert_eq!(samples.last().unwrap().sample.timestamp, NOW + per_mb - 1);
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_compaction_keeps_lines_appended_while_running() {
+        let dir = temp_dir("concurrent");
+        let (mut store, _) = HistoryStore::open(&dir, NOW).unwrap();
+        // Due right after opening
+        assert!(store.compaction_due(NOW));
+        store
+            .append("abc", &sample(NOW - 10 * 24 * HOUR, 99))
+            .unwrap();
+        store.append("abc", &sample(NOW, 90)).unwrap();
+
+        let mut compaction = store.begin_compaction(NOW).unwrap();
+        assert!(!store.compaction_due(NOW + 2 * HOUR));
+        // The rewrite runs while polls keep appending
+        store.append("abc", &sample(NOW + 1, 89)).unwrap();
+        compaction.run().unwrap();
+        store.append("abc", &sample(NOW + 2, 88)).unwrap();
+        store.finish_compaction(compaction).unwrap();
+
+        let levels: Vec<Option<u32>> = store
+            .load()
+            .unwrap()
+            .iter()
+            .map(|s| s.sample.battery_percentage)
+            .collect();
+        assert_eq!(levels, vec![Some(90), Some(89), Some(88)]);
+        store.append("abc", &sample(NOW + 3, 87)).unwrap();
+        assert_eq!(store.load().unwrap().len(), 4);
+        assert_eq!(
+            store.size,
+            fs::metadata(dir.join(SAMPLES_FILE)).unwrap().len()
+        );
+
+        // A compaction superseded by a newer one is discarded
+        let stale = store.begin_compaction(NOW).unwrap();
+        store.compact(NOW).unwrap();
+        store.finish_compaction(stale).unwrap();
+        assert_eq!(store.load().unwrap().len(), 4);
+        let leftovers = fs::read_dir(&dir)
+            .unwrap()
+            .filter(|entry| entry.as_ref().unwrap().path().extension() == Some("tmp".as_ref()))
+            .count();
+        assert_eq!(leftovers, 0);
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_newest_per_device() {
+        let stored = |device: &str, t: u64| StoredSample {
+            device_id: device.to_string(),
+            sample: sample(t, 50),
+        };
+        let samples = vec![
+            stored("abc", 1),
+            stored("def", 2),
+            stored("abc", 3),
+            stored("abc", 4),
+        ];
+        let kept = newest_per_device(samples, 2);
+        let kept: Vec<(&str, u64)> = kept
+            .iter()
+            .map(|s| (s.device_id.as_str(), s.sample.timestamp))
+            .collect();
+        assert_eq!(kept, vec![("def", 2), ("abc", 3), ("abc", 4)]);
+    }
+
+    #[test]
+    fn test_export_csv_and_jsonl() {
+        let dir = temp_dir("export");
+        let (mut store, _) = HistoryStore::open(&dir, NOW).unwrap();
+        store.append("abc", &sample(NOW, 90)).unwrap();
+        store.append("a,b", &sample(NOW + 1, 80)).unwrap();
+        store.append("abc", &sample(NOW + 2, 70)).unwrap();
+
+        let mut csv = Vec::new();
+        let filter = ExportFilter {
+            to: Some(NOW + 1),
+            ..ExportFilter::default()
+        };
+        assert_eq!(
+            store.export(&filter, ExportFormat::Csv, &mut csv).unwrap(),
+            2
+        );
+        let csv = String::from_utf8(csv).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("device_id,timestamp,time,"));
+        assert_eq!(
+            lines[1],
+            format!("abc,{},2023-11-14T22:13:20+00:00,90,,false,4096,12", NOW)
+        );
+        assert!(lines[2].starts_with("\"a,b\","));
+
+        let mut jsonl = Vec::new();
+        let filter = ExportFilter {
+            device_id: Some("abc".to_string()),
+            ..ExportFilter::default()
+        };
+        assert_eq!(
+            store
+                .export(&filter, ExportFormat::Jsonl, &mut jsonl)
+                .unwrap(),
+            2
+        );
+        let first: StoredSample =
+            serde_json::from_str(String::from_utf8(jsonl).unwrap().lines().next().unwrap())
+                .unwrap();
+        assert_eq!(first.sample.battery_percentage, Some(90));
+        let _ = fs::remove_dir_all(dir);
+    }
+}
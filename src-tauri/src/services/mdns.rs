@@ -0,0 +1,118 @@
+//! ADB mDNS Service Parsing
+//!
+//! Parses `adb mdns services` output (or the `host:mdns:services` payload)
+//! into typed wireless debugging services advertised on the LAN.
+
+use serde::Serialize;
+
+/// Kind of ADB service advertised over mDNS
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MdnsServiceType {
+    /// `_adb-tls-connect._tcp` - wireless debugging connect port
+    TlsConnect,
+    /// `_adb-tls-pairing._tcp` - pairing port (shown while pairing is open)
+    TlsPairing,
+    /// `_adb._tcp` - legacy `adb tcpip` devices
+    Legacy,
+}
+
+impl MdnsServiceType {
+    fn from_registration_type(value: &str) -> Option<Self> {
+        match value.trim_end_matches('.') {
+            "_adb-tls-connect._tcp" => Some(Self::TlsConnect),
+            "_adb-tls-pairing._tcp" => Some(Self::TlsPairing),
+            "_adb._tcp" => Some(Self::Legacy),
+            _ => None,
+        }
+    }
+}
+
+/// A single discovered ADB service
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MdnsService {
+    pub instance_name: String,
+    pub service_type: MdnsServiceType,
+    pub ip: String,
+    pub port: u16,
+}
+
+impl MdnsService {
+    /// `ip:port` address for `adb connect` / `adb pair`
+    pub fn address(&self) -> String {
+        format!("{}:{}", self.ip, self.port)
+    }
+}
+
+/// Parse `adb mdns services` output
+///
+/// Example output:
+/// ```text
+/// List of discovered mdns services
+/// adb-R58M123-AbCdEf    _adb-tls-connect._tcp    192.168.1.5:41234
+/// studio-Xy7Q           _adb-tls-pairing._tcp    192.168.1.5:37123
+/// ```
+pub fn parse_mdns_services(output: &str) -> Vec<MdnsService> {
+    let mut services = Vec::new();
+    for line in output.lines() {
+        if line.starts_with("List of discovered") || line.starts_with('*') {
+            continue;
+        }
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        if parts.len() < 3 {
+            continue;
+        }
+        let Some(service_type) = MdnsServiceType::from_registration_type(parts[1]) else {
+            continue;
+        };
+        let Some((ip, port)) = parts[2].rsplit_once(':') else {
+            continue;
+        };
+        let Ok(port) = port.parse::<u16>() else {
+            continue;
+        };
+        services.push(MdnsService {
+            instance_name: parts[0].to_string(),
+            service_type,
+            ip: ip.to_string(),
+            port,
+        });
+    }
+    services
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_mdns_services() {
+        let output = "List of discovered mdns services\n\
+            adb-R58M123-AbCdEf\t_adb-tls-connect._tcp\t192.168.1.5:41234\n\
+            studio-Xy7Q\t_adb-tls-pairing._tcp\t192.168.1.5:37123\n";
+        let services = parse_mdns_services(output);
+        assert_eq!(services.len(), 2);
+        assert_eq!(services[0].instance_name, "adb-R58M123-AbCdEf");
+        assert_eq!(services[0].service_type, MdnsServiceType::TlsConnect);
+        assert_eq!(services[0].address(), "192.168.1.5:41234");
+        assert_eq!(services[1].service_type, MdnsServiceType::TlsPairing);
+        assert_eq!(services[1].port, 37123);
+    }
+
+    #[test]
+    fn test_parse_mdns_services_trailing_dot_and_legacy() {
+        let output = "adb-1234\t_adb._tcp.\t10.0.0.7:5555\n";
+        let services = parse_mdns_services(output);
+        assert_eq!(services.len(), 1);
+        assert_eq!(services[0].service_type, MdnsServiceType::Legacy);
+    }
+
+    #[test]
+    fn test_parse_mdns_services_skips_malformed_lines() {
+        let output = "List of discovered mdns services\n\
+            garbage\n\
+            adb-x\t_other._tcp\t10.0.0.1:1\n\
+            adb-y\t_adb-tls-connect._tcp\tno-port\n";
+        assert!(parse_mdns_services(output).is_empty());
+    }
+}
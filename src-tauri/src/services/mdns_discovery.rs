@@ -0,0 +1,332 @@
+//! Wireless Device Discovery
+//!
+//! Browses `adb mdns services` for Android 11+ wireless debugging services and
+//! matches them against the `devices.json` registry, so a known phone can be
+//! reconnected after its wireless debugging port changes. The background
+//! watcher emits `wireless-devices-discovered` whenever the set changes.
+
+use crate::commands::device::{app_data_dir, load_registry, DeviceInfo};
+use crate::error::{AppError, AppResult};
+use crate::services::adb_client::run_blocking;
+use crate::services::adb_executor::SharedAdbExecutor;
+use crate::services::mdns::{parse_mdns_services, MdnsService, MdnsServiceType};
+use crate::types::health::ErrorCode;
+use serde::Serialize;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+/// How often the watcher re-reads `adb mdns services`
+const DISCOVERY_INTERVAL: Duration = Duration::from_millis(3000);
+
+/// A wireless debugging service found on the LAN
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiscoveredDevice {
+    pub instance_name: String,
+    pub service_type: MdnsServiceType,
+    pub ip: String,
+    pub port: u16,
+    /// `ip:port` to pass to `adb connect` / `adb pair`
+    pub address: String,
+    /// Serial of the matching registry entry, if the phone is already known
+    pub known_serial: Option<String>,
+    /// Model of the matching registry entry
+    pub model: Option<String>,
+}
+
+/// Payload of the `wireless-devices-discovered` event
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WirelessDevicesDiscoveredEvent {
+    pub devices: Vec<DiscoveredDevice>,
+}
+
+/// Extract the hardware serial from an instance name like `adb-R58M123-AbCdEf`
+fn hardware_serial_from_instance(instance_name: &str) -> Option<&str> {
+    let rest = instance_name.strip_prefix("adb-")?;
+    let (serial, _suffix) = rest.rsplit_once('-')?;
+    (!serial.is_empty()).then_some(serial)
+}
+
+/// Find the registry entry a discovered service belongs to.
+///
+/// Checked in order: exact `ip:port`, mDNS-style serial
+/// (`<instance>._adb-tls-connect._tcp`), hardware serial embedded in the
+/// instance name (USB entry), then a wireless entry on the same IP whose
+/// port has since changed.
+pub fn match_known_device<'a>(
+    service: &MdnsService,
+    registry: &'a [DeviceInfo],
+) -> Option<&'a DeviceInfo> {
+    let address = service.address();
+    if let Some(device) = registry.iter().find(|d| d.serial == address) {
+        return Some(device);
+    }
+
+    let mdns_prefix = format!("{}.", service.instance_name);
+    if let Some(device) = registry.iter().find(|d| d.serial.starts_with(&mdns_prefix)) {
+        return Some(device);
+    }
+
+    if let Some(hardware_serial) = hardware_serial_from_instance(&service.instance_name) {
+        if let Some(device) = registry.iter().find(|d| d.serial == hardware_serial) {
+            return Some(device);
+        }
+    }
+
+    // Pairing ports are unrelated to any previous connection
+    if service.service_type == MdnsServiceType::TlsPairing {
+        return None;
+    }
+    registry.iter().find(|d| {
+        d.is_wireless
+            && d.serial
+                .rsplit_once(':')
+                .is_some_and(|(ip, _)| ip == service.ip)
+    })
+}
+
+/// Annotate parsed services with their registry matches
+pub fn annotate_services(
+    services: Vec<MdnsService>,
+    registry: &[DeviceInfo],
+) -> Vec<DiscoveredDevice> {
+    services
+        .into_iter()
+        .map(|service| {
+            let known = match_known_device(&service, registry);
+            DiscoveredDevice {
+                address: service.address(),
+                known_serial: known.map(|d| d.serial.clone()),
+                model: known.and_then(|d| d.model.clone()),
+                instance_name: service.instance_name,
+                service_type: service.service_type,
+                ip: service.ip,
+                port: service.port,
+            }
+        })
+        .collect()
+}
+
+/// Run one discovery pass against the ADB server
+pub async fn discover_devices(
+    adb: &SharedAdbExecutor,
+    app_data_dir: &Path,
+) -> AppResult<Vec<DiscoveredDevice>> {
+    let adb = adb.clone();
+    let output = run_blocking(move || adb.mdns_services().map_err(AppError::from))
+        .await
+        .map_err(|e| e.context("mDNS discovery failed"))?;
+    let registry = load_registry(app_data_dir);
+    Ok(annotate_services(parse_mdns_services(&output), &registry))
+}
+
+pub struct MdnsDiscoveryService {
+    is_running: Arc<AtomicBool>,
+    app_handle: AppHandle,
+    adb: SharedAdbExecutor,
+}
+
+impl MdnsDiscoveryService {
+    pub fn new(app_handle: AppHandle, adb: SharedAdbExecutor) -> Self {
+        Self {
+            is_running: Arc::new(AtomicBool::new(false)),
+            app_handle,
+            adb,
+        }
+    }
+
+    /// Start the background watcher
+    pub fn start_watching(&mut self) -> AppResult<()> {
+        if self.is_running() {
+            return Err(AppError::new(
+                ErrorCode::SessionExists,
+                "Wireless discovery already running",
+            ));
+        }
+
+        let is_running = Arc::new(AtomicBool::new(true));
+        self.is_running = is_running.clone();
+
+        let app_handle = self.app_handle.clone();
+        let adb = self.adb.clone();
+        tauri::async_runtime::spawn(
+            async move { Self::watch_loop(app_handle, adb, is_running).await },
+        );
+        Ok(())
+    }
+
+    /// Stop the background watcher
+    pub fn stop_watching(&mut self) {
+        self.is_running.store(false, Ordering::SeqCst);
+    }
+
+    /// Whether the watcher is currently running
+    pub fn is_running(&self) -> bool {
+        self.is_running.load(Ordering::SeqCst)
+    }
+
+    async fn watch_loop(
+        app_handle: AppHandle,
+        adb: SharedAdbExecutor,
+        is_running: Arc<AtomicBool>,
+    ) {
+        let mut last: Option<Vec<DiscoveredDevice>> = None;
+
+        while is_running.load(Ordering::SeqCst) {
+            let devices = match app_data_dir(&app_handle) {
+                Ok(app_data_dir) => discover_devices(&adb, &app_data_dir).await,
+                Err(e) => Err(e),
+            };
+
+            match devices {
+                Ok(devices) => {
+                    if last.as_ref() != Some(&devices) {
+                        let _ = app_handle.emit(
+                            "wireless-devices-discovered",
+                            WirelessDevicesDiscoveredEvent {
+                                devices: devices.clone(),
+                            },
+                        );
+                        last = Some(devices);
+                    }
+                }
+                Err(e) => eprintln!("Wireless discovery failed: {}", e),
+            }
+
+            tokio::time::sleep(DISCOVERY_INTERVAL).await;
+        }
+    }
+}
+
+impl Drop for MdnsDiscoveryService {
+    fn drop(&mut self) {
+        self.stop_watching();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn service(name: &str, service_type: MdnsServiceType, ip: &str, port: u16) -> MdnsService {
+        MdnsService {
+            instance_name: name.to_string(),
+            service_type,
+            ip: ip.to_string(),
+            port,
+        }
+    }
+
+    fn registry_entry(serial: &str, model: &str) -> DeviceInfo {
+        DeviceInfo {
+            serial: serial.to_string(),
+            status: "disconnected".to_string(),
+            model: Some(model.to_string()),
+            android_version: None,
+            battery_level: None,
+            is_wireless: serial.contains(':') || serial.contains("._adb-tls-connect"),
+            last_seen: None,
+            first_seen: "2026-01-01T00:00:00Z".to_string(),
+            linked_serial: None,
+            auto_reconnect: false,
+            hardware_serial: None,
+            usb_path: None,
+            product: None,
+            device_codename: None,
+            transport_id: None,
+            status_detail: None,
+        }
+    }
+
+    #[test]
+    fn test_hardware_serial_from_instance() {
+        assert_eq!(
+            hardware_serial_from_instance("adb-R58M123-AbCdEf"),
+            Some("R58M123")
+        );
+        assert_eq!(
+            hardware_serial_from_instance("adb-emulator-5554-x"),
+            Some("emulator-5554")
+        );
+        assert_eq!(hardware_serial_from_instance("studio-Xy7Q"), None);
+        assert_eq!(hardware_serial_from_instance("adb-nosuffix"), None);
+    }
+
+    #[test]
+    fn test_match_prefers_exact_address() {
+        let registry = vec![
+            registry_entry("192.168.1.5:40000", "Old"),
+            registry_entry("192.168.1.5:41234", "Pixel 7"),
+        ];
+        let s = service("adb-X-1", MdnsServiceType::TlsConnect, "192.168.1.5", 41234);
+        assert_eq!(
+            match_known_device(&s, &registry).unwrap().model.as_deref(),
+            Some("Pixel 7")
+        );
+    }
+
+    #[test]
+    fn test_match_by_mdns_serial_and_hardware_serial() {
+        let registry = vec![
+            registry_entry("adb-R58M123-AbCdEf._adb-tls-connect._tcp", "Galaxy"),
+            registry_entry("HW998877", "Pixel 8"),
+        ];
+        let by_mdns = service(
+            "adb-R58M123-AbCdEf",
+            MdnsServiceType::TlsConnect,
+            "10.0.0.2",
+            37000,
+        );
+        assert_eq!(
+            match_known_device(&by_mdns, &registry).unwrap().serial,
+            "adb-R58M123-AbCdEf._adb-tls-connect._tcp"
+        );
+
+        let by_hw = service(
+            "adb-HW998877-QwErTy",
+            MdnsServiceType::TlsConnect,
+            "10.0.0.3",
+            38000,
+        );
+        assert_eq!(
+            match_known_device(&by_hw, &registry).unwrap().serial,
+            "HW998877"
+        );
+    }
+
+    #[test]
+    fn test_match_same_ip_after_port_change() {
+        let registry = vec![registry_entry("192.168.1.5:40000", "Pixel 7")];
+        let connect = service("adb-X-1", MdnsServiceType::TlsConnect, "192.168.1.5", 41234);
+        assert_eq!(
+            match_known_device(&connect, &registry).unwrap().serial,
+            "192.168.1.5:40000"
+        );
+
+        let pairing = service("adb-X-1", MdnsServiceType::TlsPairing, "192.168.1.5", 37123);
+        assert!(match_known_device(&pairing, &registry).is_none());
+    }
+
+    #[test]
+    fn test_annotate_services_marks_unknown() {
+        let registry = vec![registry_entry("192.168.1.5:40000", "Pixel 7")];
+        let devices = annotate_services(
+            vec![
+                service("adb-A-1", MdnsServiceType::TlsConnect, "192.168.1.5", 41234),
+                service("adb-B-2", MdnsServiceType::TlsConnect, "192.168.1.9", 42000),
+            ],
+            &registry,
+        );
+        assert_eq!(
+            devices[0].known_serial.as_deref(),
+            Some("192.168.1.5:40000")
+        );
+        assert_eq!(devices[0].model.as_deref(), Some("Pixel 7"));
+        assert_eq!(devices[0].address, "192.168.1.5:41234");
+        assert_eq!(devices[1].known_serial, None);
+    }
+}
@@ -1,8 +1,29 @@
+pub mod adb_client;
+pub mod adb_executor;
 pub mod adb_health_provider;
+pub mod adb_server;
+pub mod auto_reconnect;
+pub mod battery_estimator;
+pub mod device_tracker;
+pub mod health_alerts;
+pub mod health_history;
 pub mod health_poller;
+pub mod health_state;
+pub mod history_store;
+pub mod mdns;
+pub mod mdns_discovery;
 pub mod polling;
+pub mod process;
+pub mod qr_pairing;
+pub mod usb_diagnostics;
 
 // Re-exports for convenience
+pub use adb_client::AdbClient;
+pub use adb_executor::{AdbExecutor, ProcessExecutor, ScriptedExecutor, SharedAdbExecutor};
 pub use adb_health_provider::AdbHealthProvider;
-pub use health_poller::{calculate_backoff, poll_device_with_retry, PollingErrorEvent};
+pub use auto_reconnect::AutoReconnectService;
+pub use device_tracker::DeviceTracker;
+pub use health_poller::{calculate_backoff, PollingErrorEvent};
+pub use mdns_discovery::MdnsDiscoveryService;
 pub use polling::HealthPollingService;
+pub use qr_pairing::QrPairingService;
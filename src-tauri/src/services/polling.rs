@@ -1,67 +1,412 @@
 //! Health Polling Service
 //!
 //! Manages background polling of device health metrics with exponential backoff
-//! for transient failures and event emission to React frontend.
+//! for transient failures and event emission to React frontend. While a
+//! device's polls fail, its `ReconnectionState` is kept for
+//! `get_polling_retry_states` and regular ticks wait for the next retry.
+//! Every successful poll is also recorded in the device's `HealthHistory`
+//! and appended to the on-disk `HistoryStore`, which seeds the in-memory
+//! history on the next start. Alert rules are evaluated against each
+//! successful poll and matches are emitted as `health-alert`. Each device
+//! task keeps a `BatteryEstimator` that fills in `battery_estimate`.
 
-use crate::services::adb_health_provider::AdbHealthProvider;
-use crate::services::health_poller::{classify_error, ErrorType};
-use crate::types::health::{ConnectionMetrics, QualityLevel};
-use crate::types::health::{DeviceHealth, DeviceState, HealthPollingConfig};
+use crate::commands::device::DeviceInfo as RegistryDevice;
+use crate::error::{AppError, AppResult};
+use crate::services::adb_executor::SharedAdbExecutor;
+use crate::services::adb_health_provider::{AdbHealthProvider, HealthQuery};
+use crate::services::adb_server::{self, FreeRetries};
+use crate::services::battery_estimator::BatteryEstimator;
+use crate::services::health_alerts::{AlertEngine, FiredAlert};
+use crate::services::health_history::{HealthHistory, HISTORY_CAPACITY};
+use crate::services::health_poller::RetryStep;
+use crate::services::health_state::{self, PollOutcome, Transition};
+use crate::services::history_store::{compact_unlocked, newest_per_device, HistoryStore};
+use crate::types::health::{
+    ConnectionMetrics, ConnectionType, ErrorCode, QualityLevel, StartPollingDevice,
+};
+use crate::types::health::{
+    DeviceHealth, DeviceHealthUpdateEvent, DeviceInfo, DeviceState, HealthPollingConfig,
+    HealthSample, ReconnectionState,
+};
 use chrono::Utc;
-use std::collections::HashMap;
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use tauri::async_runtime::JoinHandle;
 use tauri::{AppHandle, Emitter};
-use tokio::sync::RwLock;
-use tokio::task::JoinHandle;
+use tauri_plugin_notification::NotificationExt;
+use tokio::sync::{RwLock, Semaphore};
 
 pub struct HealthPollingService {
-    polling_task: Option<JoinHandle<()>>,
-    is_running: Arc<AtomicBool>,
+    /// One polling task per device, each on its own schedule
+    polling_tasks: HashMap<String, JoinHandle<()>>,
+    /// Shared with the tasks so config updates apply on their next cycle
+    config: Arc<RwLock<HealthPollingConfig>>,
+    /// Add and remove devices as the registry reports them online/offline
+    follow_registry: bool,
     device_health: Arc<RwLock<HashMap<String, DeviceHealth>>>,
+    /// Static device info (model, version, build), queried once per connection
+    device_info: Arc<RwLock<HashMap<String, DeviceInfo>>>,
+    /// Retry/backoff state of devices whose polls are failing
+    retry_states: Arc<RwLock<HashMap<String, ReconnectionState>>>,
+    /// Recent samples per device; kept when a device stops being polled
+    history: Arc<RwLock<HealthHistory>>,
+    /// Persisted samples; `None` if the store couldn't be opened
+    store: Option<Arc<Mutex<HistoryStore>>>,
+    /// User alert rules and their per-device progress
+    alerts: Arc<Mutex<AlertEngine>>,
+    /// Caps how many devices are polled at once (`batch_size`)
+    limiter: Arc<PollLimiter>,
+    /// Devices with a poll running; outlives tasks so a replaced task can't
+    /// overlap a poll its predecessor left running
+    in_flight: Arc<Mutex<HashSet<String>>>,
     app_handle: AppHandle,
+    adb: SharedAdbExecutor,
+}
+
+/// State shared by every per-device polling task
+#[derive(Clone)]
+struct PollingContext {
+    config: Arc<RwLock<HealthPollingConfig>>,
+    app_handle: AppHandle,
+    adb: SharedAdbExecutor,
+    health_map: Arc<RwLock<HashMap<String, DeviceHealth>>>,
+    info_cache: Arc<RwLock<HashMap<String, DeviceInfo>>>,
+    retry_states: Arc<RwLock<HashMap<String, ReconnectionState>>>,
+    history: Arc<RwLock<HealthHistory>>,
+    store: Option<Arc<Mutex<HistoryStore>>>,
+    alerts: Arc<Mutex<AlertEngine>>,
+    limiter: Arc<PollLimiter>,
+    in_flight: Arc<Mutex<HashSet<String>>>,
+}
+
+/// Semaphore sized to the current `batch_size`, swapped out when the config
+/// changes it. Polls holding permits of a replaced semaphore just finish.
+struct PollLimiter {
+    current: Mutex<(u32, Arc<Semaphore>)>,
+}
+
+impl PollLimiter {
+    fn new() -> Self {
+        Self {
+            current: Mutex::new((0, Arc::new(Semaphore::new(0)))),
+        }
+    }
+
+    /// Semaphore allowing `batch_size` concurrent polls
+    fn semaphore(&self, batch_size: u32) -> Arc<Semaphore> {
+        let batch_size = batch_size.max(1);
+        let mut current = self.current.lock().unwrap_or_else(|e| e.into_inner());
+        if current.0 != batch_size {
+            *current = (batch_size, Arc::new(Semaphore::new(batch_size as usize)));
+        }
+        current.1.clone()
+    }
+}
+
+/// Marks a device as being polled until dropped
+struct InFlightGuard {
+    device_id: String,
+    in_flight: Arc<Mutex<HashSet<String>>>,
+}
+
+impl InFlightGuard {
+    /// `None` if the device is already being polled
+    fn acquire(in_flight: &Arc<Mutex<HashSet<String>>>, device_id: &str) -> Option<Self> {
+        let mut devices = in_flight.lock().unwrap_or_else(|e| e.into_inner());
+        if !devices.insert(device_id.to_string()) {
+            return None;
+        }
+        Some(Self {
+            device_id: device_id.to_string(),
+            in_flight: in_flight.clone(),
+        })
+    }
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        let mut devices = self.in_flight.lock().unwrap_or_else(|e| e.into_inner());
+        devices.remove(&self.device_id);
+    }
 }
 
 impl HealthPollingService {
     /// Create a new polling service
-    pub fn new(app_handle: AppHandle) -> Self {
+    pub fn new(app_handle: AppHandle, adb: SharedAdbExecutor) -> Self {
+        let mut history = HealthHistory::default();
+        let store = Self::open_store(&app_handle, &mut history);
+        let alerts = match crate::commands::device::app_data_dir(&app_handle) {
+            Ok(dir) => AlertEngine::load(&dir),
+            Err(e) => {
+                eprintln!("Warning: alert rules won't be persisted: {}", e);
+                AlertEngine::new(crate::services::health_alerts::default_rules())
+            }
+        };
         Self {
-            polling_task: None,
-            is_running: Arc::new(AtomicBool::new(false)),
+            polling_tasks: HashMap::new(),
+            config: Arc::new(RwLock::new(HealthPollingConfig::default())),
+            follow_registry: false,
             device_health: Arc::new(RwLock::new(HashMap::new())),
+            device_info: Arc::new(RwLock::new(HashMap::new())),
+            retry_states: Arc::new(RwLock::new(HashMap::new())),
+            history: Arc::new(RwLock::new(history)),
+            store,
+            alerts: Arc::new(Mutex::new(alerts)),
+            limiter: Arc::new(PollLimiter::new()),
+            in_flight: Arc::new(Mutex::new(HashSet::new())),
             app_handle,
+            adb,
         }
     }
 
-    /// Start polling for the given device IDs
+    /// Open the persisted history and load it into `history`. Polling works
+    /// without it, so failures are only logged.
+    fn open_store(
+        app_handle: &AppHandle,
+        history: &mut HealthHistory,
+    ) -> Option<Arc<Mutex<HistoryStore>>> {
+        let now = Utc::now().timestamp_millis() as u64;
+        let opened = crate::commands::device::app_data_dir(app_handle)
+            .and_then(|dir| HistoryStore::open(&dir.join("health-history"), now));
+        match opened {
+            Ok((store, samples)) => {
+                // Older samples would only be evicted again by the ring buffer
+                for stored in newest_per_device(samples, HISTORY_CAPACITY) {
+                    history.record(&stored.device_id, stored.sample);
+                }
+                Some(Arc::new(Mutex::new(store)))
+            }
+            Err(e) => {
+                eprintln!("Warning: health history won't be persisted: {}", e);
+                None
+            }
+        }
+    }
+
+    /// The persisted history, for retention settings and export
+    pub fn history_store(&self) -> AppResult<Arc<Mutex<HistoryStore>>> {
+        self.store
+            .clone()
+            .ok_or_else(|| AppError::internal("Health history store is unavailable"))
+    }
+
+    /// The alert rules engine, for reading and replacing rules
+    pub fn alert_engine(&self) -> Arc<Mutex<AlertEngine>> {
+        self.alerts.clone()
+    }
+
+    /// Start polling the given devices, each at the interval for its
+    /// connection type
     pub fn start_polling(
         &mut self,
-        device_ids: Vec<String>,
+        devices: Vec<StartPollingDevice>,
         config: HealthPollingConfig,
-    ) -> Result<(), String> {
-        if self.polling_task.is_some() {
-            return Err("Polling already running".to_string());
+    ) -> AppResult<()> {
+        if !self.polling_tasks.is_empty() {
+            return Err(AppError::new(
+                ErrorCode::SessionExists,
+                "Polling already running",
+            ));
+        }
+
+        self.set_config(config);
+        for device in devices {
+            self.spawn_device(device);
+        }
+        Ok(())
+    }
+
+    /// Stop the polling service
+    pub fn stop_polling(&mut self) -> AppResult<()> {
+        for (_, task) in self.polling_tasks.drain() {
+            task.abort();
         }
+        self.follow_registry = false;
+        let _ = self.app_handle.emit("polling-stopped", ()).ok();
+        Ok(())
+    }
 
-        let app_handle = self.app_handle.clone();
-        let health_map = self.device_health.clone();
-        let is_running = Arc::new(AtomicBool::new(true));
-        let is_running_clone = is_running.clone();
+    /// Whether any device is being polled
+    pub fn is_polling(&self) -> bool {
+        !self.polling_tasks.is_empty()
+    }
+
+    /// IDs of the devices currently polled
+    pub fn polled_devices(&self) -> Vec<String> {
+        let mut ids: Vec<String> = self.polling_tasks.keys().cloned().collect();
+        ids.sort();
+        ids
+    }
+
+    /// Start polling one more device without disturbing the others.
+    ///
+    /// Returns false if the device was already polled.
+    pub fn add_device(&mut self, device: StartPollingDevice) -> bool {
+        if self.polling_tasks.contains_key(&device.device_id) {
+            return false;
+        }
+        self.spawn_device(device);
+        true
+    }
 
-        let task = tokio::spawn(async move {
-            Self::polling_loop(device_ids, config, app_handle, health_map, is_running_clone).await
+    /// Stop polling one device. Its cached health is kept and goes Offline
+    /// through the state machine, so the change is reported like any other.
+    ///
+    /// Returns false if the device wasn't polled.
+    pub fn remove_device(&mut self, device_id: &str) -> bool {
+        let Some(task) = self.polling_tasks.remove(device_id) else {
+            return false;
+        };
+        task.abort();
+
+        let context = self.context();
+        let device_id = device_id.to_string();
+        Self::block_on(async move {
+            context.info_cache.write().await.remove(&device_id);
+            context.retry_states.write().await.remove(&device_id);
+            let config = context.config.read().await.clone();
+            Self::transition(&context, &device_id, |health, now| {
+                disconnected(health, now, &config)
+            })
+            .await;
         });
+        true
+    }
 
-        self.polling_task = Some(task);
-        self.is_running = is_running;
+    /// Replace the config; running tasks pick it up on their next cycle
+    pub fn update_config(&self, config: HealthPollingConfig) -> AppResult<()> {
+        config.validate().map_err(AppError::invalid_input)?;
+        self.set_config(config);
         Ok(())
     }
 
-    /// Stop the polling service
-    pub fn stop_polling(&mut self) -> Result<(), String> {
-        self.is_running.store(false, Ordering::SeqCst);
-        self.polling_task = None;
-        Ok(())
+    /// Follow the device registry: poll registered devices while they are
+    /// online and stop polling them once they go away.
+    ///
+    /// The registry is updated by the device tracker, which calls
+    /// `sync_with_registry` on every change.
+    pub fn set_follow_registry(&mut self, enabled: bool, registry: &[RegistryDevice]) {
+        self.follow_registry = enabled;
+        self.sync_with_registry(registry);
+    }
+
+    /// Reconcile the polled set with the registry (no-op unless following)
+    pub fn sync_with_registry(&mut self, registry: &[RegistryDevice]) {
+        if !self.follow_registry {
+            return;
+        }
+        let (added, removed) = registry_changes(registry, &self.polling_tasks);
+        for device in added {
+            self.add_device(device);
+        }
+        for device_id in removed {
+            self.remove_device(&device_id);
+        }
+    }
+
+    fn set_config(&self, config: HealthPollingConfig) {
+        let shared = self.config.clone();
+        Self::block_on(async move { *shared.write().await = config });
+    }
+
+    /// Retry state of the polled devices whose polls are currently failing
+    pub fn get_retry_states(&self) -> Vec<ReconnectionState> {
+        let retries = self.retry_states.clone();
+        let mut states: Vec<ReconnectionState> =
+            Self::block_on(async move { retries.read().await.values().cloned().collect() });
+        states.sort_by(|a, b| a.device_id.cmp(&b.device_id));
+        states
+    }
+
+    /// Recorded samples of a device with `from <= timestamp <= to`
+    pub fn get_history(
+        &self,
+        device_id: &str,
+        from: Option<u64>,
+        to: Option<u64>,
+    ) -> Vec<HealthSample> {
+        let history = self.history.clone();
+        let device_id = device_id.to_string();
+        Self::block_on(async move { history.read().await.range(&device_id, from, to) })
+    }
+
+    /// Append a sample to the on-disk store; a failed write only loses
+    /// that sample, so it is logged rather than failing the poll
+    fn persist(store: &Arc<Mutex<HistoryStore>>, device_id: &str, sample: &HealthSample) {
+        let now = Utc::now().timestamp_millis() as u64;
+        let result = tokio::task::block_in_place(|| {
+            let mut guard = store.lock().map_err(AppError::lock)?;
+            guard.append(device_id, sample)?;
+            guard
+                .compaction_due(now)
+                .then(|| guard.begin_compaction(now))
+                .transpose()
+        });
+        match result {
+            Ok(Some(compaction)) => {
+                // Rewrite the file off the poll path
+                let store = store.clone();
+                tokio::task::spawn_blocking(move || {
+                    if let Err(e) = compact_unlocked(&store, compaction) {
+                        eprintln!("Warning: failed to compact health history: {}", e);
+                    }
+                });
+            }
+            Ok(None) => {}
+            Err(e) => eprintln!("Warning: failed to persist health sample: {}", e),
+        }
+    }
+
+    /// Emit `health-alert` for each alert, plus a native notification for
+    /// rules that ask for one
+    fn raise_alerts(app_handle: &AppHandle, fired: Vec<FiredAlert>) {
+        for alert in fired {
+            if alert.notify {
+                let shown = app_handle
+                    .notification()
+                    .builder()
+                    .title(&alert.event.rule_name)
+                    .body(&alert.event.message)
+                    .show();
+                if let Err(e) = shown {
+                    eprintln!("Warning: failed to show alert notification: {}", e);
+                }
+            }
+            let _ = app_handle.emit("health-alert", alert.event).ok();
+        }
+    }
+
+    /// Run a short lock-only future from sync code on any thread
+    fn block_on<T, F: std::future::Future<Output = T>>(future: F) -> T {
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => tokio::task::block_in_place(|| handle.block_on(future)),
+            Err(_) => tauri::async_runtime::block_on(future),
+        }
+    }
+
+    fn context(&self) -> PollingContext {
+        PollingContext {
+            config: self.config.clone(),
+            app_handle: self.app_handle.clone(),
+            adb: self.adb.clone(),
+            health_map: self.device_health.clone(),
+            info_cache: self.device_info.clone(),
+            retry_states: self.retry_states.clone(),
+            history: self.history.clone(),
+            store: self.store.clone(),
+            alerts: self.alerts.clone(),
+            limiter: self.limiter.clone(),
+            in_flight: self.in_flight.clone(),
+        }
+    }
+
+    fn spawn_device(&mut self, device: StartPollingDevice) {
+        let context = self.context();
+        let device_id = device.device_id.clone();
+        // The async runtime handle works from command and tracker threads alike
+        let task =
+            tauri::async_runtime::spawn(async move { Self::polling_loop(device, context).await });
+        self.polling_tasks.insert(device_id, task);
     }
 
     /// Get current health for a device
@@ -80,157 +425,236 @@ impl HealthPollingService {
         map.get(device_id).cloned()
     }
 
-    /// Main polling loop - runs in background task
-    async fn polling_loop(
-        device_ids: Vec<String>,
-        config: HealthPollingConfig,
-        app_handle: AppHandle,
-        health_map: Arc<RwLock<HashMap<String, DeviceHealth>>>,
-        is_running: Arc<AtomicBool>,
-    ) {
-        // Initialize all devices as connecting
+    /// Polling loop for one device - runs in its own background task
+    async fn polling_loop(device: StartPollingDevice, context: PollingContext) {
+        let device_id = device.device_id;
+
+        // Initialize the device as connecting, keeping health cached by an
+        // earlier run
         {
-            let mut map = health_map.write().await;
-            let now = Utc::now().timestamp_millis() as u64;
-            for device_id in &device_ids {
-                let health = DeviceHealth {
-                    device_id: device_id.clone(),
-                    state: DeviceState::Connecting,
-                    battery: None,
-                    storage: None,
-                    connection: None,
-                    device: None,
-                    staleness: crate::types::health::StalenessLevel::Stale,
-                    last_seen: now,
-                    last_updated: now,
-                    error_reason: None,
-                };
-                map.insert(device_id.clone(), health);
+            let mut map = context.health_map.write().await;
+            if !map.contains_key(&device_id) {
+                let now = Utc::now().timestamp_millis() as u64;
+                map.insert(device_id.clone(), health_state::connecting(&device_id, now));
             }
         }
 
+        // Latest failure of the current failing streak
+        let mut retry: Option<RetryStep> = None;
+        let mut estimator = BatteryEstimator::default();
+        let mut free_retries = FreeRetries::default();
+
         // Main polling loop with cancellation support
         loop {
-            // Check if we should continue
-            if !is_running.load(Ordering::SeqCst) {
-                let _ = app_handle.emit("polling-stopped", ()).ok();
-                break;
-            }
+            // Sleep for this device's polling interval, or until the next
+            // retry while backing off
+            let config = context.config.read().await.clone();
+            let interval = config.get_polling_interval(device.connection_type) as u64;
+            let delay = match &retry {
+                Some(step) if step.will_retry => {
+                    let now = Utc::now().timestamp_millis() as u64;
+                    step.state.next_retry_at.saturating_sub(now)
+                }
+                _ => interval,
+            };
+            tokio::time::sleep(std::time::Duration::from_millis(delay)).await;
 
-            // Sleep for polling interval
-            tokio::time::sleep(std::time::Duration::from_millis(
-                config.polling_interval_usb as u64,
-            ))
+            // Data may have aged past the stale threshold while we slept
+            Self::transition(&context, &device_id, |health, now| {
+                health_state::on_tick(health, now, &config)
+            })
             .await;
 
-            // Poll each device
-            for device_id in &device_ids {
-                let health_map_clone = health_map.clone();
-                let app_handle_clone = app_handle.clone();
-                let config_clone = config.clone();
-                let device_id_clone = device_id.clone();
-
-                // Spawn per-device polling task (non-blocking)
-                tokio::spawn(async move {
-                    match Self::poll_single_device(&device_id_clone, &config_clone).await {
-                        Ok(health) => {
-                            // Update cache
-                            {
-                                let mut map = health_map_clone.write().await;
-                                map.insert(device_id_clone.clone(), health.clone());
-                            }
-
-                            // Emit health update event
-                            let _ = app_handle_clone
-                                .emit(
-                                    "device-health-update",
-                                    serde_json::json!({
-                                        "deviceId": device_id_clone,
-                                        "health": health,
-                                        "reason": "poll_success",
-                                        "timestamp": Utc::now().timestamp_millis(),
-                                    }),
-                                )
-                                .ok();
-                        }
-                        Err(error_msg) => {
-                            // Determine error type for retries
-                            let error_type = classify_error(&error_msg);
-
-                            // Only emit error for transient errors
-                            if matches!(error_type, ErrorType::Offline | ErrorType::Timeout) {
-                                // Emit error event with retry info
-                                let _ = app_handle_clone
-                                    .emit(
-                                        "polling-error",
-                                        serde_json::json!({
-                                            "deviceId": device_id_clone,
-                                            "error": {
-                                                "code": format!("{:?}", error_type).to_lowercase(),
-                                                "message": error_msg,
-                                            },
-                                            "attempt": 1,
-                                            "maxAttempts": config_clone.max_retries,
-                                            "willRetry": true,
-                                            "nextRetryAt": Utc::now().timestamp_millis()
-                                                + 500, // Initial backoff
-                                        }),
-                                    )
-                                    .ok();
-                            }
+            // Never overlap a poll of the same device; skip this cycle instead
+            let Some(_in_flight) = InFlightGuard::acquire(&context.in_flight, &device_id) else {
+                continue;
+            };
+            // Wait for a slot among the `batch_size` concurrent polls
+            let semaphore = context.limiter.semaphore(config.batch_size);
+            let Ok(_permit) = semaphore.acquire_owned().await else {
+                continue;
+            };
+
+            let outcome = match Self::poll_single_device(
+                &context.adb,
+                &context.info_cache,
+                &device_id,
+                device.connection_type,
+                &config,
+            )
+            .await
+            {
+                Ok(mut health) => {
+                    health.battery_estimate = health
+                        .battery
+                        .as_ref()
+                        .and_then(|battery| estimator.update(battery, health.last_updated));
+                    if retry.take().is_some() {
+                        context.retry_states.write().await.remove(&device_id);
+                    }
+                    if let Some(sample) = HealthSample::from_health(&health, health.last_updated) {
+                        context
+                            .history
+                            .write()
+                            .await
+                            .record(&device_id, sample.clone());
+                        if let Some(store) = &context.store {
+                            Self::persist(store, &device_id, &sample);
                         }
                     }
-                });
-            }
+                    let fired = context
+                        .alerts
+                        .lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .evaluate(&device_id, &health, health.last_updated);
+                    Self::raise_alerts(&context.app_handle, fired);
+                    PollOutcome::Healthy(Box::new(health))
+                }
+                Err(error) => {
+                    // Re-read static info once the device is back; it may
+                    // have been reflashed or swapped behind the same serial
+                    context.info_cache.write().await.remove(&device_id);
+
+                    // Errors caused by an ADB server restart are not the
+                    // device's fault; the next cycle will pick it up again
+                    if free_retries.available(adb_server::generation())
+                        && adb_server::recover_from(&context.adb, &error).await
+                    {
+                        free_retries.take(adb_server::generation());
+                        continue;
+                    }
+
+                    let now = Utc::now().timestamp_millis() as u64;
+                    let step = RetryStep::after_failure(
+                        retry.as_ref(),
+                        &device_id,
+                        &error,
+                        now,
+                        interval,
+                        &config,
+                    );
+                    if step.should_report(retry.as_ref()) {
+                        let _ = context
+                            .app_handle
+                            .emit("polling-error", step.error_event())
+                            .ok();
+                    }
+                    context
+                        .retry_states
+                        .write()
+                        .await
+                        .insert(device_id.clone(), step.state.clone());
+                    retry = Some(step);
+
+                    if error.code.is_transient() {
+                        PollOutcome::Unreachable(error.message)
+                    } else {
+                        PollOutcome::Failed(error.message)
+                    }
+                }
+            };
+
+            Self::transition(&context, &device_id, |health, now| {
+                health_state::on_poll(health, outcome, now, &config)
+            })
+            .await;
         }
     }
 
+    /// Run one state machine step against the cached health, storing and
+    /// emitting the result if the device changed
+    async fn transition<F>(context: &PollingContext, device_id: &str, step: F)
+    where
+        F: FnOnce(&DeviceHealth, u64) -> Option<Transition>,
+    {
+        let now = Utc::now().timestamp_millis() as u64;
+        let transition = {
+            let mut map = context.health_map.write().await;
+            let current = map
+                .entry(device_id.to_string())
+                .or_insert_with(|| health_state::connecting(device_id, now));
+            let Some(transition) = step(current, now) else {
+                return;
+            };
+            *current = transition.health.clone();
+            transition
+        };
+
+        let _ = context
+            .app_handle
+            .emit(
+                "device-health-update",
+                DeviceHealthUpdateEvent {
+                    device_id: device_id.to_string(),
+                    health: transition.health,
+                    reason: transition.reason,
+                    timestamp: now,
+                },
+            )
+            .ok();
+    }
+
     /// Poll a single device for health metrics
+    ///
+    /// One batched shell round trip per poll; static device info is only
+    /// requested until it has been cached.
     async fn poll_single_device(
+        adb: &SharedAdbExecutor,
+        info_cache: &RwLock<HashMap<String, DeviceInfo>>,
         device_id: &str,
+        connection_type: ConnectionType,
         config: &HealthPollingConfig,
-    ) -> Result<DeviceHealth, String> {
+    ) -> AppResult<DeviceHealth> {
         let now = Utc::now().timestamp_millis() as u64;
 
-        // Check if device is online first
-        let is_online = tokio::task::block_in_place(|| {
-            std::process::Command::new("adb")
-                .args(["-s", device_id, "shell", "echo", "ok"])
-                .output()
-                .map(|o| o.status.success())
-                .unwrap_or(false)
-        });
-
-        if !is_online {
-            return Err("Device offline".to_string());
-        }
-
         // Create provider with configured timeout
-        let provider = AdbHealthProvider::new(config.query_timeout);
+        let provider = AdbHealthProvider::with_executor(config.query_timeout, adb);
 
-        // Get battery info
-        let battery = provider.get_battery_info(device_id).ok();
+        let cached_info = info_cache.read().await.get(device_id).cloned();
+        let query = HealthQuery::from_config(config, cached_info.is_none());
 
-        // Get storage info
-        let storage = provider.get_storage_info(device_id).ok();
+        // A failed batch means the device is unreachable
+        let snapshot = tokio::task::block_in_place(|| provider.query_health(device_id, &query))
+            .map_err(|e| {
+                let error = AppError::from(e);
+                match error.code {
+                    ErrorCode::AdbError | ErrorCode::DeviceNotFound | ErrorCode::Offline => {
+                        AppError::new(ErrorCode::Offline, error.message).context("Device offline")
+                    }
+                    _ => error,
+                }
+            })?;
 
-        // Get device info
-        let device = provider.get_device_info(device_id).ok();
+        let device = match (cached_info, snapshot.device) {
+            _ if !config.collect_device_info => None,
+            (Some(info), _) => Some(info),
+            (None, Some(info)) => {
+                info_cache
+                    .write()
+                    .await
+                    .insert(device_id.to_string(), info.clone());
+                Some(info)
+            }
+            (None, None) => None,
+        };
 
-        // Get latency
-        let connection = match provider.get_latency(device_id) {
-            Ok(latency) => Some(ConnectionMetrics {
-                connection_type: crate::types::health::ConnectionType::Usb,
-                latency,
+        let connection = config
+            .collect_connection_metrics
+            .then(|| ConnectionMetrics {
+                connection_type,
+                latency: snapshot.latency,
                 signal_strength: None,
-                quality_level: Self::derive_quality_level(latency),
+                quality_level: Self::derive_quality_level(snapshot.latency),
                 estimated_bandwidth: None,
-            }),
-            Err(_) => None,
-        };
+            });
+
+        let battery = snapshot.battery;
+        let storage = snapshot.storage;
 
-        // Determine staleness
-        let staleness = if battery.is_some() && storage.is_some() {
+        // Determine staleness: anything we asked for but didn't get
+        let staleness = if (battery.is_some() || !config.collect_battery)
+            && (storage.is_some() || !config.collect_storage)
+        {
             crate::types::health::StalenessLevel::Fresh
         } else {
             crate::types::health::StalenessLevel::Stale
@@ -240,6 +664,7 @@ impl HealthPollingService {
             device_id: device_id.to_string(),
             state: DeviceState::Online,
             battery,
+            battery_estimate: None,
             storage,
             connection,
             device,
@@ -263,16 +688,60 @@ impl HealthPollingService {
 
 impl Drop for HealthPollingService {
     fn drop(&mut self) {
-        // Signal the polling loop to stop
-        self.is_running.store(false, Ordering::SeqCst);
-        
-        // Abort the task if it's still running
-        if let Some(task) = self.polling_task.take() {
+        // Abort the tasks if they're still running
+        for (_, task) in self.polling_tasks.drain() {
             task.abort();
         }
     }
 }
 
+/// A device that stopped being polled because it is gone. It is known to be
+/// unreachable, so the offline threshold needn't be waited out.
+fn disconnected(
+    current: &DeviceHealth,
+    now: u64,
+    config: &HealthPollingConfig,
+) -> Option<Transition> {
+    let config = HealthPollingConfig {
+        offline_threshold: 0,
+        ..config.clone()
+    };
+    let outcome = PollOutcome::Unreachable("Device disconnected".to_string());
+    health_state::on_poll(current, outcome, now, &config)
+}
+
+/// Devices to start and stop polling so the polled set follows the registry.
+/// Only online (`device`) entries are polled; anything else polled, including
+/// entries that were forgotten or renamed, is stopped.
+fn registry_changes<T>(
+    registry: &[RegistryDevice],
+    polled: &HashMap<String, T>,
+) -> (Vec<StartPollingDevice>, Vec<String>) {
+    let online: Vec<&RegistryDevice> = registry
+        .iter()
+        .filter(|device| device.status == "device")
+        .collect();
+    let added = online
+        .iter()
+        .filter(|device| !polled.contains_key(&device.serial))
+        .map(|device| StartPollingDevice {
+            device_id: device.serial.clone(),
+            connection_type: if device.is_wireless {
+                ConnectionType::Wireless
+            } else {
+                ConnectionType::Usb
+            },
+        })
+        .collect();
+    let mut removed: Vec<String> = polled
+        .keys()
+        .filter(|id| !online.iter().any(|device| &device.serial == *id))
+        .cloned()
+        .collect();
+    removed.sort();
+    (added, removed)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -308,4 +777,362 @@ mod tests {
             QualityLevel::Poor
         );
     }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_static_info_is_queried_once() {
+        use crate::services::adb_executor::{AdbRequest, ScriptedExecutor};
+        use crate::services::adb_health_provider::build_health_query;
+
+        let scripted = ScriptedExecutor::new();
+        scripted
+            .respond_shell(
+                &build_health_query(true),
+                "@@scrcpy-gui:battery@@\n  level: 80\n@@scrcpy-gui:storage@@\n\
+                 Filesystem 1K-blocks Used Available Use% Mounted on\n\
+                 /dev/block/dm-5 1000 400 600 40% /data\n\
+                 @@scrcpy-gui:model@@\nPixel 7\n@@scrcpy-gui:version@@\n14\n\
+                 @@scrcpy-gui:build@@\nUQ1A\n@@scrcpy-gui:end@@\n",
+            )
+            .respond_shell(
+                &build_health_query(false),
+                "@@scrcpy-gui:battery@@\n  level: 79\n@@scrcpy-gui:end@@\n",
+            );
+        let adb: SharedAdbExecutor = Arc::new(scripted.clone());
+        let cache = RwLock::new(HashMap::new());
+        let config = HealthPollingConfig::default();
+
+        let poll = || {
+            HealthPollingService::poll_single_device(
+                &adb,
+                &cache,
+                "abc123",
+                ConnectionType::Wireless,
+                &config,
+            )
+        };
+        let first = poll().await.unwrap();
+        let second = poll().await.unwrap();
+
+        assert_eq!(first.device.unwrap().model_name, "Pixel 7");
+        assert_eq!(
+            first.connection.unwrap().connection_type,
+            ConnectionType::Wireless
+        );
+        assert_eq!(first.storage.unwrap().used, 400 * 1024);
+        assert_eq!(second.device.unwrap().build_number, "UQ1A");
+        assert_eq!(second.battery.unwrap().percentage, 79);
+        assert!(second.storage.is_none());
+
+        let calls = scripted.calls();
+        assert_eq!(calls.len(), 2);
+        assert!(matches!(
+            &calls[1],
+            AdbRequest::Shell { command, .. } if !command.contains("getprop")
+        ));
+    }
+
+    fn registry_entry(serial: &str, status: &str, is_wireless: bool) -> RegistryDevice {
+        RegistryDevice {
+            serial: serial.to_string(),
+            status: status.to_string(),
+            first_seen: String::new(),
+            last_seen: None,
+            model: None,
+            android_version: None,
+            battery_level: None,
+            is_wireless,
+            linked_serial: None,
+            auto_reconnect: false,
+            hardware_serial: None,
+            usb_path: None,
+            product: None,
+            device_codename: None,
+            transport_id: None,
+            status_detail: None,
+        }
+    }
+
+    #[test]
+    fn test_registry_changes_follow_online_devices() {
+        let registry = vec![
+            registry_entry("usb1", "device", false),
+            registry_entry("10.0.0.2:5555", "device", true),
+            registry_entry("gone", "disconnected", false),
+            registry_entry("locked", "unauthorized", false),
+        ];
+        let polled: HashMap<String, ()> = [("usb1".to_string(), ()), ("gone".to_string(), ())]
+            .into_iter()
+            .collect();
+
+        let (added, removed) = registry_changes(&registry, &polled);
+
+        assert_eq!(added.len(), 1);
+        assert_eq!(added[0].device_id, "10.0.0.2:5555");
+        assert_eq!(added[0].connection_type, ConnectionType::Wireless);
+        assert_eq!(removed, vec!["gone".to_string()]);
+    }
+
+    #[test]
+    fn test_registry_changes_stop_forgotten_and_renamed_devices() {
+        // "forgotten" was removed from the registry, "10.0.0.2:5555" was
+        // renamed to the port it reconnected on
+        let registry = vec![
+            registry_entry("usb1", "device", false),
+            registry_entry("10.0.0.2:40123", "device", true),
+        ];
+        let polled: HashMap<String, ()> = ["usb1", "forgotten", "10.0.0.2:5555"]
+            .into_iter()
+            .map(|id| (id.to_string(), ()))
+            .collect();
+
+        let (added, removed) = registry_changes(&registry, &polled);
+
+        assert_eq!(added.len(), 1);
+        assert_eq!(added[0].device_id, "10.0.0.2:40123");
+        assert_eq!(
+            removed,
+            vec!["10.0.0.2:5555".to_string(), "forgotten".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_disconnected_device_goes_offline_with_its_snapshot() {
+        use crate::types::health::{BatteryInfo, HealthUpdateReason};
+
+        let config = HealthPollingConfig::default();
+        let mut online = health_state::connecting("usb1", 1_000);
+        online.state = DeviceState::Online;
+        online.battery = Some(BatteryInfo {
+            percentage: 64,
+            temperature: None,
+            is_charging: Some(false),
+            health: None,
+            charge_counter: None,
+            current_now: None,
+            charger: None,
+            voltage: None,
+            max_charging_current: None,
+        });
+
+        let transition = disconnected(&online, 1_500, &config).unwrap();
+        assert_eq!(transition.reason, HealthUpdateReason::Offline);
+        assert_eq!(transition.health.state, DeviceState::Offline);
+        assert_eq!(transition.health.battery.as_ref().unwrap().percentage, 64);
+        assert!(disconnected(&transition.health, 2_000, &config).is_none());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_collect_flags_limit_the_query() {
+        use crate::services::adb_executor::{AdbRequest, ScriptedExecutor};
+
+        let config = HealthPollingConfig {
+            collect_storage: false,
+            collect_connection_metrics: false,
+            collect_device_info: false,
+            ..HealthPollingConfig::default()
+        };
+        let scripted = ScriptedExecutor::new();
+        scripted.respond_shell(
+            &HealthQuery::from_config(&config, true).script(),
+            "@@scrcpy-gui:battery@@\n  level: 55\n@@scrcpy-gui:end@@\n",
+        );
+        let adb: SharedAdbExecutor = Arc::new(scripted.clone());
+        let cache = RwLock::new(HashMap::new());
+
+        let health = HealthPollingService::poll_single_device(
+            &adb,
+            &cache,
+            "abc123",
+            ConnectionType::Usb,
+            &config,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(health.battery.unwrap().percentage, 55);
+        assert!(health.device.is_none());
+        assert!(health.connection.is_none());
+        // Storage wasn't asked for, so its absence doesn't make data stale
+        assert_eq!(
+            health.staleness,
+            crate::types::health::StalenessLevel::Fresh
+        );
+        assert!(matches!(
+            &scripted.calls()[0],
+            AdbRequest::Shell { command, .. }
+                if !command.contains("getprop") && !command.contains("df /data")
+        ));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_poll_errors_keep_their_code() {
+        use crate::services::adb_client::{AdbError, AdbTarget};
+        use crate::services::adb_executor::{AdbRequest, ScriptedExecutor};
+
+        let config = HealthPollingConfig::default();
+        let scripted = ScriptedExecutor::new();
+        let script = HealthQuery::from_config(&config, true).script();
+        let shell = |device_id: &str| AdbRequest::Shell {
+            target: AdbTarget::Serial(device_id.to_string()),
+            command: script.clone(),
+        };
+        scripted
+            .fail(
+                shell("locked"),
+                AdbError::Failed("device unauthorized.".into()),
+            )
+            .fail(shell("gone"), AdbError::Protocol("connection reset".into()));
+        let adb: SharedAdbExecutor = Arc::new(scripted);
+        let cache = RwLock::new(HashMap::new());
+        let poll = |device_id: &'static str| {
+            HealthPollingService::poll_single_device(
+                &adb,
+                &cache,
+                device_id,
+                ConnectionType::Usb,
+                &config,
+            )
+        };
+
+        // An unauthorized device needs the user, not another retry
+        let error = poll("locked").await.unwrap_err();
+        assert_eq!(error.code, ErrorCode::DeviceUnauthorized);
+        assert!(!error.code.is_transient());
+
+        let error = poll("gone").await.unwrap_err();
+        assert_eq!(error.code, ErrorCode::Offline);
+        assert!(error.message.starts_with("Device offline: "));
+    }
+
+    #[test]
+    fn test_in_flight_guard_blocks_overlapping_polls() {
+        let in_flight = Arc::new(Mutex::new(HashSet::new()));
+
+        let guard = InFlightGuard::acquire(&in_flight, "abc123").unwrap();
+        assert!(InFlightGuard::acquire(&in_flight, "abc123").is_none());
+        assert!(InFlightGuard::acquire(&in_flight, "def456").is_some());
+
+        drop(guard);
+        assert!(InFlightGuard::acquire(&in_flight, "abc123").is_some());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_batch_size_polls_devices_concurrently() {
+        use crate::services::adb_client::{AdbError, AdbTarget};
+        use crate::services::adb_executor::AdbExecutor;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::time::Duration;
+
+        /// Records how many shell queries overlap
+        #[derive(Clone, Default)]
+        struct SlowExecutor {
+            active: Arc<AtomicUsize>,
+            peak: Arc<AtomicUsize>,
+        }
+
+        impl AdbExecutor for SlowExecutor {
+            fn devices(&self) -> Result<String, AdbError> {
+                Ok(String::new())
+            }
+            fn shell(&self, _: &AdbTarget, _: &str) -> Result<String, AdbError> {
+                let active = self.active.fetch_add(1, Ordering::SeqCst) + 1;
+                self.peak.fetch_max(active, Ordering::SeqCst);
+                std::thread::sleep(Duration::from_millis(200));
+                self.active.fetch_sub(1, Ordering::SeqCst);
+                Err(AdbError::Timeout("shell".to_string()))
+            }
+            fn connect(&self, _: &str) -> Result<String, AdbError> {
+                unimplemented!()
+            }
+            fn disconnect(&self, _: &str) -> Result<String, AdbError> {
+                unimplemented!()
+            }
+            fn pair(&self, _: &str, _: &str) -> Result<String, AdbError> {
+                unimplemented!()
+            }
+            fn tcpip(&self, _: &AdbTarget, _: u16) -> Result<String, AdbError> {
+                unimplemented!()
+            }
+            fn mdns_services(&self) -> Result<String, AdbError> {
+                unimplemented!()
+            }
+            fn with_timeout(&self, _: Duration) -> SharedAdbExecutor {
+                Arc::new(self.clone())
+            }
+            fn server_version(&self) -> Result<u32, AdbError> {
+                unimplemented!()
+            }
+            fn client_version(&self) -> Result<String, AdbError> {
+                unimplemented!()
+            }
+            fn start_server(&self) -> Result<(), AdbError> {
+                unimplemented!()
+            }
+            fn kill_server(&self) -> Result<(), AdbError> {
+                unimplemented!()
+            }
+            fn server_port(&self) -> u16 {
+                unimplemented!()
+            }
+            fn stopped_by_user(&self) -> bool {
+                false
+            }
+            fn set_stopped_by_user(&self, _: bool) {}
+        }
+
+        // Each device waits for a slot, then polls, like `polling_loop`
+        async fn peak_concurrency(batch_size: u32) -> usize {
+            let executor = SlowExecutor::default();
+            let adb: SharedAdbExecutor = Arc::new(executor.clone());
+            let limiter = Arc::new(PollLimiter::new());
+            let info_cache = Arc::new(RwLock::new(HashMap::new()));
+            let config = HealthPollingConfig {
+                batch_size,
+                ..HealthPollingConfig::default()
+            };
+            let polls: Vec<_> = ["usb1", "usb2"]
+                .into_iter()
+                .map(|device_id| {
+                    let (adb, limiter, info_cache, config) = (
+                        adb.clone(),
+                        limiter.clone(),
+                        info_cache.clone(),
+                        config.clone(),
+                    );
+                    tokio::spawn(async move {
+                        let semaphore = limiter.semaphore(config.batch_size);
+                        let _permit = semaphore.acquire_owned().await.unwrap();
+                        let _ = HealthPollingService::poll_single_device(
+                            &adb,
+                            &info_cache,
+                            device_id,
+                            ConnectionType::Usb,
+                            &config,
+                        )
+                        .await;
+                    })
+                })
+                .collect();
+            for poll in polls {
+                poll.await.unwrap();
+            }
+            executor.peak.load(Ordering::SeqCst)
+        }
+
+        assert_eq!(peak_concurrency(2).await, 2);
+        assert_eq!(peak_concurrency(1).await, 1);
+    }
+
+    #[test]
+    fn test_limiter_follows_batch_size() {
+        let limiter = PollLimiter::new();
+
+        let two = limiter.semaphore(2);
+        assert_eq!(two.available_permits(), 2);
+        assert!(Arc::ptr_eq(&two, &limiter.semaphore(2)));
+
+        // Resizing hands out a new semaphore; zero still allows one poll
+        assert_eq!(limiter.semaphore(4).available_permits(), 4);
+        assert_eq!(limiter.semaphore(0).available_permits(), 1);
+    }
 }
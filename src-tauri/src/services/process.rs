@@ -0,0 +1,211 @@
+//! Child Process Timeouts
+//!
+//! Runs `adb` / `scrcpy` probes with a hard deadline. When the deadline passes
+//! the child is killed and reaped, and the caller gets
+//! `ProcessError::Timeout` instead of a hung task.
+
+use std::fmt;
+use std::io::Read;
+use std::process::{Child, Command, Output, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How often a running child is checked for exit
+const WAIT_SLICE: Duration = Duration::from_millis(10);
+
+/// Errors from running a child process with a deadline
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProcessError {
+    /// The program could not be started (missing binary, permissions)
+    Spawn { program: String, message: String },
+    /// The program did not exit in time and was killed
+    Timeout { program: String, timeout: Duration },
+}
+
+impl fmt::Display for ProcessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProcessError::Spawn { program, message } => {
+                write!(f, "Failed to execute {}: {}", program, message)
+            }
+            ProcessError::Timeout { program, timeout } => write!(
+                f,
+                "{} timed out after {}ms and was killed",
+                program,
+                timeout.as_millis()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ProcessError {}
+
+fn program_name(program: &std::ffi::OsStr) -> String {
+    std::path::Path::new(program)
+        .file_name()
+        .unwrap_or(program)
+        .to_string_lossy()
+        .to_string()
+}
+
+/// Drain a pipe on a helper thread so a chatty child can't fill it and stall
+fn drain<R: Read + Send + 'static>(pipe: Option<R>) -> Option<thread::JoinHandle<Vec<u8>>> {
+    pipe.map(|mut pipe| {
+        thread::spawn(move || {
+            let mut buffer = Vec::new();
+            let _ = pipe.read_to_end(&mut buffer);
+            buffer
+        })
+    })
+}
+
+fn join(handle: Option<thread::JoinHandle<Vec<u8>>>) -> Vec<u8> {
+    handle.and_then(|h| h.join().ok()).unwrap_or_default()
+}
+
+/// Wait for `child`, killing it once `timeout` has elapsed
+fn wait_or_kill(
+    child: &mut Child,
+    timeout: Duration,
+) -> std::io::Result<Option<std::process::ExitStatus>> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Some(status));
+        }
+        let now = Instant::now();
+        if now >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Ok(None);
+        }
+        thread::sleep(WAIT_SLICE.min(deadline - now));
+    }
+}
+
+/// Run a command to completion (blocking), capturing stdout and stderr
+pub fn output_with_timeout(
+    command: &mut Command,
+    timeout: Duration,
+) -> Result<Output, ProcessError> {
+    let program = program_name(command.get_program());
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| ProcessError::Spawn {
+            program: program.clone(),
+            message: e.to_string(),
+        })?;
+
+    let stdout = drain(child.stdout.take());
+    let stderr = drain(child.stderr.take());
+
+    match wait_or_kill(&mut child, timeout) {
+        Ok(Some(status)) => Ok(Output {
+            status,
+            stdout: join(stdout),
+            stderr: join(stderr),
+        }),
+        // Readers are left behind: a grandchild may still hold the pipes open
+        Ok(None) => Err(ProcessError::Timeout { program, timeout }),
+        Err(e) => Err(ProcessError::Spawn {
+            program,
+            message: e.to_string(),
+        }),
+    }
+}
+
+/// Async variant for `tokio::process::Command`; the child is killed when
+/// the deadline passes
+pub async fn output_with_timeout_async(
+    command: &mut tokio::process::Command,
+    timeout: Duration,
+) -> Result<Output, ProcessError> {
+    let program = program_name(command.as_std().get_program());
+    let child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| ProcessError::Spawn {
+            program: program.clone(),
+            message: e.to_string(),
+        })?;
+
+    // Dropping the timed-out future drops the child, which kills it
+    match tokio::time::timeout(timeout, child.wait_with_output()).await {
+        Ok(result) => result.map_err(|e| ProcessError::Spawn {
+            program,
+            message: e.to_string(),
+        }),
+        Err(_) => Err(ProcessError::Timeout { program, timeout }),
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fast_command_returns_output() {
+        let output = output_with_timeout(
+            Command::new("sh").args(["-c", "echo out; echo err >&2"]),
+            Duration::from_secs(5),
+        )
+        .unwrap();
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "out\n");
+        assert_eq!(String::from_utf8_lossy(&output.stderr), "err\n");
+    }
+
+    #[test]
+    fn slow_command_is_killed() {
+        let marker =
+            std::env::temp_dir().join(format!("scrcpy-gui-timeout-{}", std::process::id()));
+        let _ = std::fs::remove_file(&marker);
+        let script = format!("sleep 1; touch '{}'", marker.display());
+
+        let start = Instant::now();
+        let result = output_with_timeout(
+            Command::new("sh").args(["-c", &script]),
+            Duration::from_millis(200),
+        );
+        assert!(start.elapsed() < Duration::from_millis(900));
+        assert_eq!(
+            result,
+            Err(ProcessError::Timeout {
+                program: "sh".to_string(),
+                timeout: Duration::from_millis(200),
+            })
+        );
+
+        // The shell was killed before it could run `touch`
+        thread::sleep(Duration::from_millis(1200));
+        assert!(!marker.exists());
+    }
+
+    #[test]
+    fn missing_program_is_spawn_error() {
+        let result = output_with_timeout(
+            &mut Command::new("/nonexistent/adb"),
+            Duration::from_secs(1),
+        );
+        assert!(matches!(result, Err(ProcessError::Spawn { ref program, .. }) if program == "adb"));
+    }
+
+    #[tokio::test]
+    async fn async_slow_command_times_out() {
+        let start = Instant::now();
+        let result = output_with_timeout_async(
+            tokio::process::Command::new("sleep").arg("5"),
+            Duration::from_millis(200),
+        )
+        .await;
+        assert!(start.elapsed() < Duration::from_secs(2));
+        let error = result.unwrap_err();
+        assert!(error.to_string().contains("timed out after 200ms"));
+    }
+}
@@ -0,0 +1,375 @@
+//! QR Code Wireless Pairing
+//!
+//! Implements Android 11+ "Pair device with QR code":
+//! 1. Generate a random service name and password
+//! 2. Render the `WIFI:T:ADB;S:<name>;P:<password>;;` payload as an SVG QR code
+//! 3. Watch `adb mdns services` until the phone advertises a matching
+//!    `_adb-tls-pairing` service, then pair with the password
+//! 4. Connect to the phone's `_adb-tls-connect` service and register it
+
+use crate::commands::connection::{connect_address, parse_pair_output};
+use crate::commands::device::{load_registry, register_device_internal, sync_polling, DeviceInfo};
+use crate::error::{AppError, AppResult};
+use crate::services::adb_client::run_blocking;
+use crate::services::adb_executor::SharedAdbExecutor;
+use crate::services::mdns::{parse_mdns_services, MdnsService, MdnsServiceType};
+use base64::Engine;
+use chrono::Utc;
+use qrcode::render::svg;
+use qrcode::QrCode;
+use rand::Rng;
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+/// How often `adb mdns services` is checked while a session is active
+const MDNS_POLL_INTERVAL: Duration = Duration::from_millis(1000);
+
+/// How long the phone has to scan the code and pair
+const SESSION_TIMEOUT_MS: u64 = 120_000;
+
+const SERVICE_NAME_PREFIX: &str = "scrcpy-gui-";
+const ALPHANUMERIC: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+
+/// QR pairing session handed to the UI
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QrPairingSession {
+    pub service_name: String,
+    pub password: String,
+    /// Raw `WIFI:T:ADB;...` payload encoded in the QR code
+    pub qr_payload: String,
+    /// `data:image/svg+xml;base64,...` URI ready for an `<img>` tag
+    pub qr_image: String,
+    /// Unix timestamp ms after which the session is abandoned
+    pub expires_at: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QrPairingStatus {
+    /// Waiting for the phone to scan the code
+    Waiting,
+    /// Pairing service found, handshake in progress
+    Pairing,
+    /// Paired, waiting for the connect service
+    Connecting,
+    /// Paired, connected and registered
+    Paired,
+    Failed,
+    Expired,
+    Cancelled,
+}
+
+/// Payload of the `qr-pairing-status` event
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QrPairingStatusEvent {
+    pub service_name: String,
+    pub status: QrPairingStatus,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub device: Option<DeviceInfo>,
+}
+
+fn random_string(len: usize) -> String {
+    let mut rng = rand::thread_rng();
+    (0..len)
+        .map(|_| ALPHANUMERIC[rng.gen_range(0..ALPHANUMERIC.len())] as char)
+        .collect()
+}
+
+/// Build the QR payload understood by Android's wireless debugging scanner
+pub fn qr_payload(service_name: &str, password: &str) -> String {
+    format!("WIFI:T:ADB;S:{};P:{};;", service_name, password)
+}
+
+/// Render a payload as an SVG QR code wrapped in a base64 data URI
+pub fn render_qr_data_uri(payload: &str) -> AppResult<String> {
+    let code = QrCode::new(payload.as_bytes())
+        .map_err(|e| AppError::internal(format!("Failed to encode QR code: {}", e)))?;
+    let svg = code
+        .render::<svg::Color>()
+        .min_dimensions(256, 256)
+        .quiet_zone(true)
+        .build();
+    Ok(format!(
+        "data:image/svg+xml;base64,{}",
+        base64::engine::general_purpose::STANDARD.encode(svg)
+    ))
+}
+
+/// Find the pairing service advertised for our session
+fn find_pairing_service<'a>(
+    services: &'a [MdnsService],
+    service_name: &str,
+) -> Option<&'a MdnsService> {
+    services
+        .iter()
+        .find(|s| s.service_type == MdnsServiceType::TlsPairing && s.instance_name == service_name)
+}
+
+/// Find the connect service of a freshly paired phone (same IP as pairing)
+fn find_connect_service<'a>(services: &'a [MdnsService], ip: &str) -> Option<&'a MdnsService> {
+    services
+        .iter()
+        .find(|s| s.service_type == MdnsServiceType::TlsConnect && s.ip == ip)
+}
+
+pub struct QrPairingService {
+    is_active: Arc<AtomicBool>,
+    app_handle: AppHandle,
+    adb: SharedAdbExecutor,
+}
+
+impl QrPairingService {
+    pub fn new(app_handle: AppHandle, adb: SharedAdbExecutor) -> Self {
+        Self {
+            is_active: Arc::new(AtomicBool::new(false)),
+            app_handle,
+            adb,
+        }
+    }
+
+    /// Start a new session, cancelling any previous one
+    pub fn start_session(&mut self) -> AppResult<QrPairingSession> {
+        self.cancel_session();
+
+        let service_name = format!("{}{}", SERVICE_NAME_PREFIX, random_string(10));
+        let password = random_string(12);
+        let payload = qr_payload(&service_name, &password);
+        let session = QrPairingSession {
+            qr_image: render_qr_data_uri(&payload)?,
+            qr_payload: payload,
+            service_name,
+            password,
+            expires_at: Utc::now().timestamp_millis() as u64 + SESSION_TIMEOUT_MS,
+        };
+
+        let is_active = Arc::new(AtomicBool::new(true));
+        self.is_active = is_active.clone();
+
+        let app_handle = self.app_handle.clone();
+        let adb = self.adb.clone();
+        let session_clone = session.clone();
+        tauri::async_runtime::spawn(async move {
+            Self::watch_session(app_handle, adb, session_clone, is_active).await
+        });
+
+        Ok(session)
+    }
+
+    /// Cancel the active session, if any
+    pub fn cancel_session(&mut self) {
+        self.is_active.store(false, Ordering::SeqCst);
+    }
+
+    /// Background task driving one session to completion
+    async fn watch_session(
+        app_handle: AppHandle,
+        adb: SharedAdbExecutor,
+        session: QrPairingSession,
+        is_active: Arc<AtomicBool>,
+    ) {
+        let emit = |status: QrPairingStatus, message: String, device: Option<DeviceInfo>| {
+            let _ = app_handle.emit(
+                "qr-pairing-status",
+                QrPairingStatusEvent {
+                    service_name: session.service_name.clone(),
+                    status,
+                    message,
+                    device,
+                },
+            );
+        };
+
+        emit(
+            QrPairingStatus::Waiting,
+            "Scan the QR code from Wireless debugging > Pair device with QR code".to_string(),
+            None,
+        );
+
+        let mut paired_ip: Option<String> = None;
+        loop {
+            if !is_active.load(Ordering::SeqCst) {
+                emit(
+                    QrPairingStatus::Cancelled,
+                    "Pairing cancelled".to_string(),
+                    None,
+                );
+                return;
+            }
+            if Utc::now().timestamp_millis() as u64 > session.expires_at {
+                is_active.store(false, Ordering::SeqCst);
+                emit(
+                    QrPairingStatus::Expired,
+                    "QR code expired before a device paired".to_string(),
+                    None,
+                );
+                return;
+            }
+
+            let executor = adb.clone();
+            let services =
+                run_blocking(move || executor.mdns_services().map_err(|e| e.to_string()))
+                    .await
+                    .map(|output| parse_mdns_services(&output))
+                    .unwrap_or_default();
+
+            match &paired_ip {
+                None => {
+                    if let Some(service) = find_pairing_service(&services, &session.service_name) {
+                        emit(
+                            QrPairingStatus::Pairing,
+                            format!("Pairing with {}", service.address()),
+                            None,
+                        );
+                        let address = service.address();
+                        let password = session.password.clone();
+                        let executor = adb.clone();
+                        let output = run_blocking(move || {
+                            executor
+                                .pair(&address, &password)
+                                .map_err(|e| e.to_string())
+                        })
+                        .await;
+                        let parsed = match output {
+                            Ok(output) => parse_pair_output(&output),
+                            Err(e) => parse_pair_output(&e),
+                        };
+                        match parsed {
+                            Ok(_) => {
+                                emit(
+                                    QrPairingStatus::Connecting,
+                                    "Paired, waiting for the device to become connectable"
+                                        .to_string(),
+                                    None,
+                                );
+                                paired_ip = Some(service.ip.clone());
+                            }
+                            Err((_, message)) => {
+                                is_active.store(false, Ordering::SeqCst);
+                                emit(QrPairingStatus::Failed, message, None);
+                                return;
+                            }
+                        }
+                    }
+                }
+                Some(ip) => {
+                    if let Some(service) = find_connect_service(&services, ip) {
+                        let result = Self::connect_and_register(&app_handle, &adb, service).await;
+                        is_active.store(false, Ordering::SeqCst);
+                        match result {
+                            Ok(device) => emit(
+                                QrPairingStatus::Paired,
+                                format!("Paired and connected to {}", device.serial),
+                                Some(device),
+                            ),
+                            Err(message) => emit(QrPairingStatus::Failed, message, None),
+                        }
+                        return;
+                    }
+                }
+            }
+
+            tokio::time::sleep(MDNS_POLL_INTERVAL).await;
+        }
+    }
+
+    /// Connect to the discovered service and add it to the registry
+    async fn connect_and_register(
+        app_handle: &AppHandle,
+        adb: &SharedAdbExecutor,
+        service: &MdnsService,
+    ) -> Result<DeviceInfo, String> {
+        let serial = service.address();
+        connect_address(adb, serial.clone())
+            .await
+            .map_err(|e| format!("Paired, but connecting failed: {}", e))?;
+
+        let app_data_dir = app_handle
+            .path()
+            .app_data_dir()
+            .map_err(|e| format!("Failed to resolve app data dir: {}", e))?;
+        let device = register_device_internal(adb, &serial, &app_data_dir)
+            .await
+            .map_err(|e| format!("Paired and connected, but registration failed: {}", e))?;
+        sync_polling(app_handle, &load_registry(&app_data_dir));
+        Ok(device)
+    }
+}
+
+impl Drop for QrPairingService {
+    fn drop(&mut self) {
+        self.cancel_session();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn service(name: &str, service_type: MdnsServiceType, ip: &str, port: u16) -> MdnsService {
+        MdnsService {
+            instance_name: name.to_string(),
+            service_type,
+            ip: ip.to_string(),
+            port,
+        }
+    }
+
+    #[test]
+    fn test_qr_payload_format() {
+        assert_eq!(
+            qr_payload("scrcpy-gui-abc", "s3cret"),
+            "WIFI:T:ADB;S:scrcpy-gui-abc;P:s3cret;;"
+        );
+    }
+
+    #[test]
+    fn test_random_string_is_alphanumeric() {
+        let value = random_string(12);
+        assert_eq!(value.len(), 12);
+        assert!(value.chars().all(|c| c.is_ascii_alphanumeric()));
+        assert_ne!(value, random_string(12));
+    }
+
+    #[test]
+    fn test_render_qr_data_uri() {
+        let uri = render_qr_data_uri("WIFI:T:ADB;S:scrcpy-gui-abc;P:s3cret;;").unwrap();
+        let encoded = uri.strip_prefix("data:image/svg+xml;base64,").unwrap();
+        let svg = base64::engine::general_purpose::STANDARD
+            .decode(encoded)
+            .unwrap();
+        assert!(String::from_utf8(svg).unwrap().contains("<svg"));
+    }
+
+    #[test]
+    fn test_find_pairing_service_matches_name_and_type() {
+        let services = vec![
+            service(
+                "scrcpy-gui-other",
+                MdnsServiceType::TlsPairing,
+                "10.0.0.2",
+                1,
+            ),
+            service("scrcpy-gui-abc", MdnsServiceType::TlsConnect, "10.0.0.3", 2),
+            service("scrcpy-gui-abc", MdnsServiceType::TlsPairing, "10.0.0.4", 3),
+        ];
+        let found = find_pairing_service(&services, "scrcpy-gui-abc").unwrap();
+        assert_eq!(found.address(), "10.0.0.4:3");
+        assert!(find_pairing_service(&services, "scrcpy-gui-missing").is_none());
+    }
+
+    #[test]
+    fn test_find_connect_service_matches_ip() {
+        let services = vec![
+            service("adb-A", MdnsServiceType::TlsPairing, "10.0.0.4", 3),
+            service("adb-A", MdnsServiceType::TlsConnect, "10.0.0.4", 41234),
+        ];
+        let found = find_connect_service(&services, "10.0.0.4").unwrap();
+        assert_eq!(found.port, 41234);
+    }
+}
@@ -0,0 +1,681 @@
+//! USB Permission Diagnostics (Linux)
+//!
+//! Explains why ADB reports a USB device as `no permissions` or
+//! `unauthorized`. Android devices are found under `/sys/bus/usb/devices`,
+//! their `/dev/bus/usb` nodes are probed for read/write access, group
+//! membership and installed udev rules are checked, and a ready-to-install
+//! udev rule is generated for the devices that are not covered.
+//!
+//! Every input path is configurable through `DiagnosticPaths` so the checks
+//! can run against a fake sysfs tree in tests.
+
+use crate::commands::device::{AdbDevice, AdbDeviceState};
+use serde::Serialize;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+/// Vendor IDs of common Android device manufacturers. Several also make
+/// non-Android hardware, so a vendor match alone never makes a device count.
+const ANDROID_VENDOR_IDS: &[(&str, &str)] = &[
+    ("0502", "Acer"),
+    ("0b05", "ASUS"),
+    ("413c", "Dell"),
+    ("0489", "Foxconn"),
+    ("18d1", "Google"),
+    ("0bb4", "HTC"),
+    ("12d1", "Huawei"),
+    ("17ef", "Lenovo"),
+    ("1004", "LG"),
+    ("0e8d", "MediaTek"),
+    ("22b8", "Motorola"),
+    ("0955", "NVIDIA"),
+    ("2a70", "OnePlus"),
+    ("22d9", "OPPO"),
+    ("05c6", "Qualcomm"),
+    ("04e8", "Samsung"),
+    ("0fce", "Sony"),
+    ("2d95", "vivo"),
+    ("2717", "Xiaomi"),
+    ("19d2", "ZTE"),
+    ("1949", "Amazon"),
+];
+
+/// USB interface class/subclass/protocol triple of the ADB interface
+const ADB_INTERFACE: (&str, &str, &str) = ("ff", "42", "01");
+
+/// Group conventionally used for Android devices on Debian/Ubuntu
+const ANDROID_GROUP: &str = "plugdev";
+
+/// Where the generated rule should be installed
+const SUGGESTED_RULE_PATH: &str = "/etc/udev/rules.d/51-android.rules";
+
+/// Filesystem locations inspected by the diagnostics
+#[derive(Debug, Clone)]
+pub struct DiagnosticPaths {
+    pub sysfs_usb: PathBuf,
+    pub dev_usb: PathBuf,
+    pub group_file: PathBuf,
+    pub udev_rule_dirs: Vec<PathBuf>,
+}
+
+impl Default for DiagnosticPaths {
+    fn default() -> Self {
+        Self {
+            sysfs_usb: PathBuf::from("/sys/bus/usb/devices"),
+            dev_usb: PathBuf::from("/dev/bus/usb"),
+            group_file: PathBuf::from("/etc/group"),
+            udev_rule_dirs: vec![
+                PathBuf::from("/etc/udev/rules.d"),
+                PathBuf::from("/lib/udev/rules.d"),
+                PathBuf::from("/usr/lib/udev/rules.d"),
+            ],
+        }
+    }
+}
+
+/// An Android-looking USB device found in sysfs
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct UsbDeviceInfo {
+    /// sysfs name such as `1-4`; matches the `usb:` field of `adb devices -l`
+    pub sysfs_name: String,
+    pub vendor_id: String,
+    pub product_id: String,
+    pub manufacturer: Option<String>,
+    pub product: Option<String>,
+    pub serial: Option<String>,
+    /// Whether an interface with the ADB class triple is exposed
+    pub has_adb_interface: bool,
+    /// `/dev/bus/usb/BBB/DDD`
+    pub dev_node: Option<String>,
+    /// `Some(false)` when opening the node failed with "permission denied"
+    pub accessible: Option<bool>,
+    /// ADB's view of the device, matched by USB path
+    pub adb_serial: Option<String>,
+    pub adb_state: Option<AdbDeviceState>,
+}
+
+/// An existing udev rule line that mentions a vendor ID
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct UdevRuleMatch {
+    pub path: String,
+    pub line_number: usize,
+    pub line: String,
+    pub vendor_id: String,
+}
+
+/// A udev rule ready to be written to `path`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct UdevRuleSuggestion {
+    pub path: String,
+    pub contents: String,
+    pub install_commands: Vec<String>,
+}
+
+/// Result of `diagnose_usb_permissions`
+#[derive(Debug, Clone, Serialize)]
+pub struct UsbDiagnostics {
+    pub user: String,
+    /// Groups of the running session (`id -Gn`)
+    pub session_groups: Vec<String>,
+    /// Groups listing the user in the group file; may include groups the
+    /// session has not picked up yet
+    pub configured_groups: Vec<String>,
+    pub devices: Vec<UsbDeviceInfo>,
+    pub udev_rules: Vec<UdevRuleMatch>,
+    pub suggested_rule: Option<UdevRuleSuggestion>,
+    /// Human-readable findings, most important first
+    pub explanations: Vec<String>,
+}
+
+fn read_attr(dir: &Path, name: &str) -> Option<String> {
+    fs::read_to_string(dir.join(name))
+        .ok()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+}
+
+/// Manufacturer name for a known Android vendor ID
+pub fn android_vendor_name(vendor_id: &str) -> Option<&'static str> {
+    ANDROID_VENDOR_IDS
+        .iter()
+        .find(|(id, _)| id.eq_ignore_ascii_case(vendor_id))
+        .map(|(_, name)| *name)
+}
+
+/// Whether any interface directory (`1-4:1.0`, ...) carries the ADB triple
+fn has_adb_interface(device_dir: &Path) -> bool {
+    let Ok(entries) = fs::read_dir(device_dir) else {
+        return false;
+    };
+    entries.flatten().any(|entry| {
+        let path = entry.path();
+        entry.file_name().to_string_lossy().contains(':')
+            && read_attr(&path, "bInterfaceClass").as_deref() == Some(ADB_INTERFACE.0)
+            && read_attr(&path, "bInterfaceSubClass").as_deref() == Some(ADB_INTERFACE.1)
+            && read_attr(&path, "bInterfaceProtocol").as_deref() == Some(ADB_INTERFACE.2)
+    })
+}
+
+/// Probe a device node for the read/write access libusb needs
+fn check_access(node: &Path) -> Option<bool> {
+    match fs::OpenOptions::new().read(true).write(true).open(node) {
+        Ok(_) => Some(true),
+        Err(e) if e.kind() == ErrorKind::PermissionDenied => Some(false),
+        Err(_) => None,
+    }
+}
+
+/// List USB devices that expose an ADB interface, plus known-vendor devices
+/// at a USB path ADB itself reports as `no permissions`
+pub fn scan_usb_devices(
+    sysfs_usb: &Path,
+    dev_usb: &Path,
+    no_permission_paths: &[&str],
+) -> Vec<UsbDeviceInfo> {
+    let Ok(entries) = fs::read_dir(sysfs_usb) else {
+        return Vec::new();
+    };
+
+    let mut devices: Vec<UsbDeviceInfo> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let dir = entry.path();
+            let sysfs_name = entry.file_name().to_string_lossy().to_string();
+            let vendor_id = read_attr(&dir, "idVendor")?.to_lowercase();
+            let has_adb = has_adb_interface(&dir);
+            let claimed_by_adb = no_permission_paths.contains(&sysfs_name.as_str());
+            let is_android =
+                has_adb || (claimed_by_adb && android_vendor_name(&vendor_id).is_some());
+            if !is_android {
+                return None;
+            }
+
+            let busnum = read_attr(&dir, "busnum").and_then(|s| s.parse::<u32>().ok());
+            let devnum = read_attr(&dir, "devnum").and_then(|s| s.parse::<u32>().ok());
+            let node = busnum.zip(devnum).map(|(bus, dev)| {
+                dev_usb
+                    .join(format!("{:03}", bus))
+                    .join(format!("{:03}", dev))
+            });
+
+            Some(UsbDeviceInfo {
+                sysfs_name,
+                product_id: read_attr(&dir, "idProduct")
+                    .unwrap_or_default()
+                    .to_lowercase(),
+                manufacturer: read_attr(&dir, "manufacturer"),
+                product: read_attr(&dir, "product"),
+                serial: read_attr(&dir, "serial"),
+                has_adb_interface: has_adb,
+                accessible: node.as_deref().and_then(check_access),
+                dev_node: node.map(|n| n.to_string_lossy().to_string()),
+                adb_serial: None,
+                adb_state: None,
+                vendor_id,
+            })
+        })
+        .collect();
+
+    devices.sort_by(|a, b| a.sysfs_name.cmp(&b.sysfs_name));
+    devices
+}
+
+/// Groups that list `user` as a member in `/etc/group` format
+pub fn parse_group_file(contents: &str, user: &str) -> Vec<String> {
+    contents
+        .lines()
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .filter_map(|line| {
+            let mut fields = line.split(':');
+            let name = fields.next()?;
+            let members = fields.nth(2)?;
+            members
+                .split(',')
+                .any(|m| m.trim() == user)
+                .then(|| name.to_string())
+        })
+        .collect()
+}
+
+/// Whether a group with this name exists in `/etc/group` format
+fn group_exists(contents: &str, group: &str) -> bool {
+    contents
+        .lines()
+        .any(|line| line.split(':').next() == Some(group))
+}
+
+/// Existing rule lines mentioning any of the vendor IDs in `ATTR{idVendor}`
+pub fn find_udev_rules(dirs: &[PathBuf], vendor_ids: &[String]) -> Vec<UdevRuleMatch> {
+    let mut matches = Vec::new();
+    for dir in dirs {
+        let Ok(entries) = fs::read_dir(dir) else {
+            continue;
+        };
+        let mut files: Vec<PathBuf> = entries
+            .flatten()
+            .map(|e| e.path())
+            .filter(|p| p.extension().is_some_and(|ext| ext == "rules"))
+            .collect();
+        files.sort();
+
+        for file in files {
+            let Ok(contents) = fs::read_to_string(&file) else {
+                continue;
+            };
+            for (index, line) in contents.lines().enumerate() {
+                let trimmed = line.trim();
+                if trimmed.starts_with('#') || !trimmed.contains("idVendor") {
+                    continue;
+                }
+                let lower = trimmed.to_lowercase();
+                for vendor_id in vendor_ids {
+                    if lower.contains(&format!("\"{}\"", vendor_id)) {
+                        matches.push(UdevRuleMatch {
+                            path: file.to_string_lossy().to_string(),
+                            line_number: index + 1,
+                            line: trimmed.to_string(),
+                            vendor_id: vendor_id.clone(),
+                        });
+                    }
+                }
+            }
+        }
+    }
+    matches
+}
+
+/// Build a udev rule granting access to the given devices. Each line matches
+/// vendor and product ID, leaving other hardware of the same vendor alone.
+///
+/// With `group` the node is `0660` and owned by that group; without it the
+/// node is world-writable. `uaccess` additionally hands the device to the
+/// logged-in seat user on systemd systems.
+pub fn generate_udev_rule(devices: &[&UsbDeviceInfo], group: Option<&str>) -> UdevRuleSuggestion {
+    let mut contents = String::from(
+        "# Android devices for adb/scrcpy (generated by scrcpy-gui)\n\
+         # Install to /etc/udev/rules.d/ and replug the device.\n",
+    );
+    let access = match group {
+        Some(group) => format!("MODE=\"0660\", GROUP=\"{}\"", group),
+        None => "MODE=\"0666\"".to_string(),
+    };
+    let mut written: Vec<(&str, &str)> = Vec::new();
+    for device in devices {
+        let ids = (device.vendor_id.as_str(), device.product_id.as_str());
+        if written.contains(&ids) {
+            continue;
+        }
+        written.push(ids);
+        contents.push_str(&format!(
+            "# {}\nSUBSYSTEM==\"usb\", ATTR{{idVendor}}==\"{}\", ATTR{{idProduct}}==\"{}\", {}, \
+             TAG+=\"uaccess\"\n",
+            device_label(device),
+            ids.0,
+            ids.1,
+            access
+        ));
+    }
+
+    let mut install_commands = vec![
+        format!(
+            "sudo tee {} > /dev/null << 'EOF'\n{}EOF",
+            SUGGESTED_RULE_PATH, contents
+        ),
+        format!("sudo chmod 644 {}", SUGGESTED_RULE_PATH),
+        "sudo udevadm control --reload-rules".to_string(),
+        "sudo udevadm trigger --subsystem-match=usb".to_string(),
+    ];
+    if let Some(group) = group {
+        install_commands.push(format!("sudo usermod -aG {} \"$USER\"", group));
+    }
+    install_commands.push("adb kill-server".to_string());
+
+    UdevRuleSuggestion {
+        path: SUGGESTED_RULE_PATH.to_string(),
+        contents,
+        install_commands,
+    }
+}
+
+/// Attach ADB serial/state to the sysfs device with the same USB path
+fn match_adb_devices(devices: &mut [UsbDeviceInfo], adb_devices: &[AdbDevice]) {
+    for device in devices.iter_mut() {
+        if let Some(adb) = adb_devices
+            .iter()
+            .find(|a| a.usb.as_deref() == Some(device.sysfs_name.as_str()))
+        {
+            device.adb_serial = Some(adb.serial.clone());
+            device.adb_state = Some(adb.state);
+        }
+    }
+}
+
+fn device_label(device: &UsbDeviceInfo) -> String {
+    let name = device
+        .product
+        .clone()
+        .or_else(|| device.manufacturer.clone())
+        .or_else(|| android_vendor_name(&device.vendor_id).map(str::to_string))
+        .unwrap_or_else(|| "USB device".to_string());
+    format!(
+        "{} ({}:{} at {})",
+        name, device.vendor_id, device.product_id, device.sysfs_name
+    )
+}
+
+/// Run all checks and explain the findings.
+///
+/// `session_groups` is the output of `id -Gn`; everything else is read from
+/// `paths`.
+pub(crate) fn diagnose(
+    paths: &DiagnosticPaths,
+    user: &str,
+    session_groups: Vec<String>,
+    adb_devices: &[AdbDevice],
+) -> UsbDiagnostics {
+    let group_contents = fs::read_to_string(&paths.group_file).unwrap_or_default();
+    let configured_groups = parse_group_file(&group_contents, user);
+
+    let no_permission_paths: Vec<&str> = adb_devices
+        .iter()
+        .filter(|a| a.state == AdbDeviceState::NoPermissions)
+        .filter_map(|a| a.usb.as_deref())
+        .collect();
+    let mut devices = scan_usb_devices(&paths.sysfs_usb, &paths.dev_usb, &no_permission_paths);
+    match_adb_devices(&mut devices, adb_devices);
+
+    let mut vendor_ids: Vec<String> = devices.iter().map(|d| d.vendor_id.clone()).collect();
+    vendor_ids.sort();
+    vendor_ids.dedup();
+    let udev_rules = find_udev_rules(&paths.udev_rule_dirs, &vendor_ids);
+
+    let mut explanations = Vec::new();
+    if devices.is_empty() {
+        explanations.push(
+            "No Android USB device found. Check the cable, pick \"File transfer\" in the \
+             USB mode prompt, and make sure USB debugging is enabled in Developer options."
+                .to_string(),
+        );
+    }
+
+    let blocked: Vec<&UsbDeviceInfo> = devices
+        .iter()
+        .filter(|d| {
+            d.accessible == Some(false) || d.adb_state == Some(AdbDeviceState::NoPermissions)
+        })
+        .collect();
+    for device in &blocked {
+        explanations.push(format!(
+            "{} is not writable by {}; adb cannot open {} and reports \"no permissions\".",
+            device_label(device),
+            user,
+            device.dev_node.as_deref().unwrap_or("its device node")
+        ));
+    }
+
+    let uncovered: Vec<String> = vendor_ids
+        .iter()
+        .filter(|id| !udev_rules.iter().any(|r| &r.vendor_id == *id))
+        .cloned()
+        .collect();
+    for vendor_id in &uncovered {
+        explanations.push(format!(
+            "No udev rule mentions vendor {} ({}); the device node keeps the default \
+             root-only permissions.",
+            vendor_id,
+            android_vendor_name(vendor_id).unwrap_or("unknown vendor")
+        ));
+    }
+    if !blocked.is_empty() && uncovered.is_empty() && !udev_rules.is_empty() {
+        explanations.push(
+            "A udev rule exists but the device is still not accessible. Reload the rules \
+             (udevadm control --reload-rules && udevadm trigger) and replug the device."
+                .to_string(),
+        );
+    }
+
+    let in_session = session_groups.iter().any(|g| g == ANDROID_GROUP);
+    let in_config = configured_groups.iter().any(|g| g == ANDROID_GROUP);
+    if in_config && !in_session {
+        explanations.push(format!(
+            "{} was added to the {} group but this session predates it; log out and back \
+             in (or reboot) for the membership to apply.",
+            user, ANDROID_GROUP
+        ));
+    }
+
+    for adb in adb_devices
+        .iter()
+        .filter(|a| a.state == AdbDeviceState::Unauthorized)
+    {
+        explanations.push(format!(
+            "{} is unauthorized: unlock the phone and accept the \"Allow USB debugging\" \
+             prompt. If no prompt appears, use \"Revoke USB debugging authorizations\" in \
+             Developer options, replug, and run adb kill-server.",
+            adb.serial
+        ));
+    }
+
+    let needs_rule: Vec<&UsbDeviceInfo> = devices
+        .iter()
+        .filter(|d| uncovered.contains(&d.vendor_id) || blocked.contains(d))
+        .collect();
+    let suggested_rule = (!needs_rule.is_empty()).then(|| {
+        let group = group_exists(&group_contents, ANDROID_GROUP).then_some(ANDROID_GROUP);
+        generate_udev_rule(&needs_rule, group)
+    });
+    if suggested_rule.is_some() {
+        explanations.push(format!(
+            "Install the suggested rule to {}, reload udev, replug the device and restart \
+             the ADB server.",
+            SUGGESTED_RULE_PATH
+        ));
+    }
+
+    UsbDiagnostics {
+        user: user.to_string(),
+        session_groups,
+        configured_groups,
+        devices,
+        udev_rules,
+        suggested_rule,
+        explanations,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct FakeRoot(PathBuf);
+
+    impl FakeRoot {
+        fn new(name: &str) -> Self {
+            let root = std::env::temp_dir().join(format!(
+                "scrcpy-gui-usbdiag-{}-{}",
+                name,
+                std::process::id()
+            ));
+            let _ = fs::remove_dir_all(&root);
+            fs::create_dir_all(&root).unwrap();
+            Self(root)
+        }
+
+        fn paths(&self) -> DiagnosticPaths {
+            DiagnosticPaths {
+                sysfs_usb: self.0.join("sys"),
+                dev_usb: self.0.join("dev"),
+                group_file: self.0.join("group"),
+                udev_rule_dirs: vec![self.0.join("rules.d")],
+            }
+        }
+
+        fn write(&self, relative: &str, contents: &str) {
+            let path = self.0.join(relative);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+
+        fn add_device(&self, name: &str, vendor: &str, adb: bool) {
+            self.write(&format!("sys/{}/idVendor", name), &format!("{}\n", vendor));
+            self.write(&format!("sys/{}/idProduct", name), "4ee7\n");
+            self.write(&format!("sys/{}/product", name), "Pixel 7\n");
+            self.write(&format!("sys/{}/busnum", name), "1\n");
+            self.write(&format!("sys/{}/devnum", name), "12\n");
+            if adb {
+                let iface = format!("sys/{}/{}:1.0", name, name);
+                self.write(&format!("{}/bInterfaceClass", iface), "ff\n");
+                self.write(&format!("{}/bInterfaceSubClass", iface), "42\n");
+                self.write(&format!("{}/bInterfaceProtocol", iface), "01\n");
+            }
+        }
+    }
+
+    impl Drop for FakeRoot {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn scan_finds_adb_interfaces_and_known_vendors() {
+        let root = FakeRoot::new("scan");
+        root.add_device("1-4", "18d1", true);
+        root.add_device("1-5", "abcd", true);
+        root.add_device("1-6", "04e8", false);
+        root.add_device("1-7", "413c", false); // a Dell keyboard
+        root.add_device("2-1", "046d", false); // a mouse
+        root.write("sys/usb1/bDeviceClass", "09\n"); // root hub without idVendor
+
+        let paths = root.paths();
+        // A known vendor only counts where ADB reports "no permissions"
+        let devices = scan_usb_devices(&paths.sysfs_usb, &paths.dev_usb, &["1-6", "2-1"]);
+        let names: Vec<&str> = devices.iter().map(|d| d.sysfs_name.as_str()).collect();
+        assert_eq!(names, vec!["1-4", "1-5", "1-6"]);
+        assert!(devices[0].has_adb_interface);
+        assert!(!devices[2].has_adb_interface);
+        assert_eq!(devices[0].product.as_deref(), Some("Pixel 7"));
+        assert!(devices[0]
+            .dev_node
+            .as_deref()
+            .unwrap()
+            .ends_with("dev/001/012"));
+        // Node does not exist in the fake tree
+        assert_eq!(devices[0].accessible, None);
+    }
+
+    #[test]
+    fn parse_group_file_lists_memberships() {
+        let group = "root:x:0:\nplugdev:x:46:alice,bob\n# comment:x:1:alice\nadbusers:x:1001:bob\n";
+        assert_eq!(parse_group_file(group, "alice"), vec!["plugdev"]);
+        assert_eq!(parse_group_file(group, "bob"), vec!["plugdev", "adbusers"]);
+        assert!(parse_group_file(group, "carol").is_empty());
+        assert!(group_exists(group, "plugdev"));
+        assert!(!group_exists(group, "wheel"));
+    }
+
+    #[test]
+    fn find_udev_rules_matches_vendor_case_insensitively() {
+        let root = FakeRoot::new("rules");
+        root.write(
+            "rules.d/51-android.rules",
+            "# ATTR{idVendor}==\"04e8\"\nSUBSYSTEM==\"usb\", ATTR{idVendor}==\"18D1\", MODE=\"0666\"\n",
+        );
+        root.write("rules.d/README", "ATTR{idVendor}==\"04e8\"\n");
+
+        let matches = find_udev_rules(
+            &root.paths().udev_rule_dirs,
+            &["18d1".to_string(), "04e8".to_string()],
+        );
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].vendor_id, "18d1");
+        assert_eq!(matches[0].line_number, 2);
+    }
+
+    #[test]
+    fn generate_udev_rule_with_and_without_group() {
+        let device = |vendor: &str, product: &str| UsbDeviceInfo {
+            sysfs_name: "1-4".to_string(),
+            vendor_id: vendor.to_string(),
+            product_id: product.to_string(),
+            manufacturer: None,
+            product: None,
+            serial: None,
+            has_adb_interface: true,
+            dev_node: None,
+            accessible: None,
+            adb_serial: None,
+            adb_state: None,
+        };
+        let pixel = device("18d1", "4ee7");
+        let rule = generate_udev_rule(&[&pixel, &pixel], Some("plugdev"));
+        assert_eq!(rule.path, "/etc/udev/rules.d/51-android.rules");
+        assert_eq!(rule.contents.matches("SUBSYSTEM").count(), 1);
+        assert!(rule.contents.contains(
+            "SUBSYSTEM==\"usb\", ATTR{idVendor}==\"18d1\", ATTR{idProduct}==\"4ee7\", MODE=\"0660\", GROUP=\"plugdev\", TAG+=\"uaccess\""
+        ));
+        assert!(rule.contents.contains("# Google (18d1:4ee7 at 1-4)"));
+        assert!(rule
+            .install_commands
+            .iter()
+            .any(|c| c == "sudo udevadm control --reload-rules"));
+        assert_eq!(rule.install_commands.last().unwrap(), "adb kill-server");
+
+        let rule = generate_udev_rule(&[&device("abcd", "0001")], None);
+        assert!(rule
+            .contents
+            .contains("ATTR{idVendor}==\"abcd\", ATTR{idProduct}==\"0001\", MODE=\"0666\""));
+        assert!(!rule.install_commands.iter().any(|c| c.contains("usermod")));
+    }
+
+    #[test]
+    fn diagnose_explains_missing_rule_and_stale_session() {
+        let root = FakeRoot::new("diagnose");
+        root.add_device("1-4", "18d1", true);
+        root.write("group", "plugdev:x:46:alice\n");
+        let adb = vec![
+            AdbDevice {
+                serial: "0123456789ABCDEF".to_string(),
+                state: AdbDeviceState::NoPermissions,
+                usb: Some("1-4".to_string()),
+                ..Default::default()
+            },
+            AdbDevice {
+                serial: "R58M123".to_string(),
+                state: AdbDeviceState::Unauthorized,
+                ..Default::default()
+            },
+        ];
+
+        let report = diagnose(&root.paths(), "alice", vec!["alice".to_string()], &adb);
+        assert_eq!(report.configured_groups, vec!["plugdev"]);
+        assert_eq!(
+            report.devices[0].adb_serial.as_deref(),
+            Some("0123456789ABCDEF")
+        );
+        assert_eq!(
+            report.devices[0].adb_state,
+            Some(AdbDeviceState::NoPermissions)
+        );
+        assert!(report.udev_rules.is_empty());
+
+        let rule = report.suggested_rule.expect("rule suggested");
+        assert!(rule.contents.contains("GROUP=\"plugdev\""));
+        assert!(rule.contents.contains("ATTR{idProduct}==\"4ee7\""));
+
+        let text = report.explanations.join("\n");
+        assert!(text.contains("No udev rule mentions vendor 18d1"));
+        assert!(text.contains("log out and back in"));
+        assert!(text.contains("R58M123 is unauthorized"));
+    }
+
+    #[test]
+    fn diagnose_without_devices_suggests_nothing() {
+        let root = FakeRoot::new("empty");
+        let report = diagnose(&root.paths(), "alice", Vec::new(), &[]);
+        assert!(report.devices.is_empty());
+        assert!(report.suggested_rule.is_none());
+        assert!(report.explanations[0].starts_with("No Android USB device found"));
+    }
+}
@@ -35,6 +35,18 @@ pub enum ConnectionType {
     Wireless,
 }
 
+impl ConnectionType {
+    /// Guess the transport from a serial alone: `ip:port` and mDNS
+    /// (`adb-<serial>-<id>._adb-tls-connect._tcp`) serials are wireless
+    pub fn from_serial(serial: &str) -> Self {
+        if serial.contains(':') || serial.contains("._adb-tls-connect.") {
+            ConnectionType::Wireless
+        } else {
+            ConnectionType::Usb
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum QualityLevel {
@@ -50,17 +62,43 @@ pub enum HealthUpdateReason {
     Poll,
     Retry,
     ManualRefresh,
+    /// No answer, or data older than `stale_threshold`; last snapshot kept
+    Stale,
+    /// No answer for `offline_threshold`
+    Offline,
+    /// The device answered with an error retrying won't fix
+    Error,
 }
 
+/// Android `BatteryManager.BATTERY_HEALTH_*`, plus `Warm` reported by some
+/// OEM builds
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
-#[serde(rename_all = "lowercase")]
+#[serde(rename_all = "snake_case")]
 pub enum BatteryHealth {
+    Unknown,
     Good,
-    Warm,
     Overheat,
+    Dead,
+    OverVoltage,
+    UnspecifiedFailure,
+    Cold,
+    Warm,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// What the device is drawing power from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChargerSource {
+    Ac,
+    Usb,
+    Wireless,
+    Dock,
+    /// Running on battery
+    Unplugged,
+}
+
+/// Error category shared by health events and command errors (`AppError`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum ErrorCode {
     Offline,
@@ -68,6 +106,43 @@ pub enum ErrorCode {
     PermissionDenied,
     AdbError,
     ParseError,
+    /// The `adb` binary could not be started
+    AdbMissing,
+    /// No ADB server answering on its port
+    AdbServerUnavailable,
+    /// The device hasn't accepted this computer's RSA key
+    DeviceUnauthorized,
+    /// No such device attached or registered
+    DeviceNotFound,
+    /// The `scrcpy` binary could not be started
+    ScrcpyMissing,
+    /// A mirroring session (or another exclusive background task) is
+    /// already running
+    SessionExists,
+    /// No running session to act on
+    SessionNotFound,
+    /// A command argument or config value was rejected
+    InvalidInput,
+    /// Reading or writing a local file failed
+    Io,
+    /// Not available on this platform or ADB version
+    Unsupported,
+    Internal,
+}
+
+impl ErrorCode {
+    /// Whether the same operation may succeed if simply tried again later
+    pub fn is_transient(self) -> bool {
+        matches!(
+            self,
+            ErrorCode::Offline
+                | ErrorCode::Timeout
+                | ErrorCode::AdbError
+                | ErrorCode::ParseError
+                | ErrorCode::AdbServerUnavailable
+                | ErrorCode::DeviceNotFound
+        )
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -91,6 +166,40 @@ pub struct BatteryInfo {
     pub is_charging: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub health: Option<BatteryHealth>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub charge_counter: Option<u64>, // Remaining charge, µAh
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current_now: Option<i64>, // µA, positive while charging
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub charger: Option<ChargerSource>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub voltage: Option<u32>, // Millivolts
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_charging_current: Option<u32>, // µA the charger offers
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum EstimateSource {
+    /// Instantaneous current draw against the battery capacity
+    Current,
+    /// Change of the charge counter over recent polls
+    ChargeCounter,
+    /// Change of the battery percentage over recent polls
+    Level,
+}
+
+/// Smoothed charge/drain rate and the time it leaves until empty or full
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatteryEstimate {
+    /// %/hour; positive while charging, negative while draining
+    pub rate_per_hour: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time_to_empty_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time_to_full_ms: Option<u64>,
+    pub source: EstimateSource,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -129,6 +238,9 @@ pub struct DeviceHealth {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub battery: Option<BatteryInfo>,
 
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub battery_estimate: Option<BatteryEstimate>,
+
     #[serde(skip_serializing_if = "Option::is_none")]
     pub storage: Option<StorageInfo>,
 
@@ -157,6 +269,7 @@ impl DeviceHealth {
             last_seen: now,
             last_updated: 0,
             battery: None,
+            battery_estimate: None,
             storage: None,
             connection: None,
             device: None,
@@ -211,7 +324,7 @@ pub struct HealthPollingConfig {
     pub collect_connection_metrics: bool, // Default: true
     pub collect_device_info: bool,        // Default: true
 
-    pub batch_size: u32,    // Default: 1
+    pub batch_size: u32,    // Default: 4 devices polled at once (1-32)
     pub query_timeout: u32, // Default: 500 ms
 }
 
@@ -230,7 +343,7 @@ impl Default for HealthPollingConfig {
             collect_storage: true,
             collect_connection_metrics: true,
             collect_device_info: true,
-            batch_size: 1,
+            batch_size: 4,
             query_timeout: 500,
         }
     }
@@ -260,6 +373,9 @@ impl HealthPollingConfig {
         if self.query_timeout < 200 {
             return Err("query_timeout must be >= 200ms".to_string());
         }
+        if self.batch_size < 1 || self.batch_size > 32 {
+            return Err("batch_size must be between 1 and 32".to_string());
+        }
         Ok(())
     }
 }
@@ -284,18 +400,151 @@ pub struct ReconnectionState {
     pub started_at: u64, // Unix timestamp ms
 }
 
+// ============================================================================
+// Health History
+// ============================================================================
+
+/// One recorded poll; metrics that weren't collected are `None`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthSample {
+    pub timestamp: u64, // Unix timestamp ms
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub battery_percentage: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub battery_temperature: Option<i32>, // Celsius
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_charging: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub storage_free: Option<u64>, // Bytes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency: Option<u32>, // Milliseconds
+}
+
+/// Min/max/average of one metric over a range
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MetricStats {
+    pub min: f64,
+    pub max: f64,
+    pub avg: f64,
+    pub count: usize,
+}
+
+// ============================================================================
+// Health Alerts
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AlertMetric {
+    BatteryPercentage,
+    /// Degrees Celsius
+    BatteryTemperature,
+    /// Bytes
+    StorageFree,
+    /// Milliseconds
+    Latency,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Comparison {
+    Below,
+    Above,
+}
+
+/// One clause of an `AlertRule`; a rule matches when all of them do
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum AlertCondition {
+    /// `metric` below/above `value`. Once the rule fired, it only clears
+    /// after the metric is back past `value` by `hysteresis`.
+    Threshold {
+        metric: AlertMetric,
+        comparison: Comparison,
+        value: f64,
+        #[serde(default)]
+        hysteresis: f64,
+    },
+    Charging {
+        charging: bool,
+    },
+    /// Connection quality is `level` or worse
+    Quality {
+        level: QualityLevel,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AlertSeverity {
+    Info,
+    Warning,
+    Critical,
+}
+
+/// User-defined alert, evaluated against every poll of every device (or
+/// only `device_id`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlertRule {
+    pub id: String,
+    pub name: String,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device_id: Option<String>,
+    pub conditions: Vec<AlertCondition>,
+    pub severity: AlertSeverity,
+    /// Consecutive matching polls before firing
+    #[serde(default = "default_min_polls")]
+    pub min_polls: u32,
+    /// How long the conditions must hold before firing
+    #[serde(default)]
+    pub min_duration_ms: u64,
+    /// Minimum time between two alerts of this rule for the same device
+    #[serde(default)]
+    pub cooldown_ms: u64,
+    /// Also raise a native desktop notification
+    #[serde(default)]
+    pub notify: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_min_polls() -> u32 {
+    1
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AlertState {
+    Triggered,
+    Resolved,
+}
+
 // ============================================================================
 // Command Protocols (Tauri IPC)
 // ============================================================================
 
-#[derive(Debug, Serialize, Deserialize)]
+/// `request` argument of `start_health_polling`
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct StartHealthPollingRequest {
+    #[serde(default)]
     pub config: HealthPollingConfig,
+    #[serde(default)]
     pub devices: Vec<StartPollingDevice>,
+    /// Bare serials, for older callers; the connection type is detected
+    #[serde(default, rename = "deviceIds", alias = "device_ids")]
+    pub device_ids: Vec<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StartPollingDevice {
+    #[serde(rename = "deviceId", alias = "device_id")]
     pub device_id: String,
     #[serde(rename = "connectionType")]
     pub connection_type: ConnectionType,
@@ -308,6 +557,33 @@ pub struct HealthCommandResult {
     pub error: Option<ErrorInfo>,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthHistoryResponse {
+    pub device_id: String,
+    pub samples: Vec<HealthSample>,
+    /// Samples in the range before downsampling
+    pub total_samples: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthTrendsResponse {
+    pub device_id: String,
+    pub sample_count: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub battery_percentage: Option<MetricStats>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub battery_temperature: Option<MetricStats>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub storage_free: Option<MetricStats>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency: Option<MetricStats>,
+    /// Battery drain while not charging, in %/hour (negative if it rose)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub battery_drain_per_hour: Option<f64>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GetDeviceHealthResponse {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -321,6 +597,7 @@ pub struct GetDeviceHealthResponse {
 // ============================================================================
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct DeviceHealthUpdateEvent {
     pub device_id: String,
     pub health: DeviceHealth,
@@ -339,6 +616,19 @@ pub struct PollingErrorEvent {
     pub will_retry: bool,
 }
 
+/// `health-alert`: a rule started or stopped matching a device
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthAlertEvent {
+    pub rule_id: String,
+    pub rule_name: String,
+    pub device_id: String,
+    pub severity: AlertSeverity,
+    pub state: AlertState,
+    pub message: String,
+    pub timestamp: u64,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PollingStartedEvent {
     pub timestamp: u64,
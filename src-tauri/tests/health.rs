@@ -6,3 +6,5 @@ mod adb_provider_tests;
 mod e2e_test;
 #[path = "health/fixtures.rs"]
 mod fixtures;
+#[path = "health/telemetry_tests.rs"]
+mod telemetry_tests;
@@ -158,6 +158,11 @@ fn test_battery_info_construction() {
         temperature: Some(28),
         is_charging: Some(true),
         health: Some(BatteryHealth::Good),
+        charge_counter: None,
+        current_now: None,
+        charger: None,
+        voltage: None,
+        max_charging_current: None,
     };
 
     assert_eq!(battery.percentage, 75);
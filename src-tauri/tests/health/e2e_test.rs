@@ -1,171 +1,170 @@
 //! End-to-end polling tests with mocked ADB
 //!
-//! Uses a temporary mock adb script to simulate device responses.
+//! Device responses come from a `ScriptedExecutor` injected into the
+//! provider, so no `adb` binary or server is needed. The unix-only test runs
+//! the same fixtures through a mock `adb` script via `ProcessExecutor`.
 
 use super::fixtures::fixtures;
+use scrcpy_gui_lib::services::adb_client::{AdbError, AdbTarget};
+use scrcpy_gui_lib::services::adb_executor::AdbRequest;
+use scrcpy_gui_lib::services::{
+    AdbHealthProvider, ProcessExecutor, ScriptedExecutor, SharedAdbExecutor,
+};
+use scrcpy_gui_lib::types::{
+    ConnectionMetrics, ConnectionType, DeviceHealth, DeviceState, QualityLevel, StalenessLevel,
+};
+use std::sync::Arc;
+
+fn scripted_adb() -> ScriptedExecutor {
+    let adb = ScriptedExecutor::new();
+    adb.respond_devices(fixtures::ADB_DEVICES_OUTPUT)
+        .respond_shell("dumpsys battery", fixtures::BATTERY_OUTPUT_HIGH)
+        .respond_shell("df /data", fixtures::STORAGE_OUTPUT_GOOD)
+        .respond_shell("getprop ro.product.model", fixtures::DEVICE_MODEL_OUTPUT)
+        .respond_shell(
+            "getprop ro.build.version.release",
+            fixtures::ANDROID_VERSION_OUTPUT,
+        )
+        .respond_shell("getprop ro.build.id", fixtures::BUILD_ID_OUTPUT)
+        .respond_shell("echo ok", fixtures::LATENCY_OUTPUT);
+    adb
+}
+
+fn provider_for(adb: SharedAdbExecutor) -> AdbHealthProvider {
+    AdbHealthProvider::with_executor(500, &adb)
+}
+
+#[tokio::test]
+async fn test_polling_with_mocked_devices() {
+    let adb = scripted_adb();
+    let provider = provider_for(Arc::new(adb.clone()));
+
+    let health_a = build_health(&provider, "emulator-5554").await;
+    let health_b = build_health(&provider, "ABC123").await;
+
+    assert_eq!(health_a.state, DeviceState::Online);
+    assert_eq!(health_b.state, DeviceState::Online);
+    assert!(health_a.battery.is_some());
+    assert!(health_a.storage.is_some());
+    assert!(health_a.device.is_some());
+    assert_eq!(health_a.device.unwrap().model_name, "Pixel 6");
+
+    // Every query addressed the device it was made for
+    assert!(adb.calls().iter().any(|call| matches!(
+        call,
+        AdbRequest::Shell { target, .. } if target.to_string() == "ABC123"
+    )));
+}
+
+#[tokio::test]
+async fn test_polling_with_failing_device() {
+    let adb = scripted_adb();
+    adb.fail(
+        AdbRequest::Shell {
+            target: AdbTarget::Serial("ABC123".to_string()),
+            command: "dumpsys battery".to_string(),
+        },
+        AdbError::Timeout("dumpsys battery".to_string()),
+    );
+    let provider = provider_for(Arc::new(adb));
+
+    let healthy = build_health(&provider, "emulator-5554").await;
+    let degraded = build_health(&provider, "ABC123").await;
+
+    assert!(healthy.battery.is_some());
+    assert!(degraded.battery.is_none());
+    assert!(degraded.storage.is_some());
+}
 
 #[cfg(unix)]
-mod unix_tests {
-    use super::fixtures;
-    use scrcpy_gui_lib::services::AdbHealthProvider;
-    use scrcpy_gui_lib::types::{
-        ConnectionMetrics, ConnectionType, DeviceHealth, DeviceState, QualityLevel, StalenessLevel,
-    };
-    use std::env;
+#[tokio::test]
+async fn test_polling_through_adb_binary() {
     use std::fs;
     use std::os::unix::fs::PermissionsExt;
-    use std::path::PathBuf;
-    use std::time::{Duration, SystemTime, UNIX_EPOCH};
-
-    fn create_temp_dir() -> PathBuf {
-        let mut dir = env::temp_dir();
-        let nanos = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_nanos();
-        dir.push(format!("scrcpy-gui-adb-mock-{}", nanos));
-        fs::create_dir_all(&dir).expect("Failed to create temp dir");
-        dir
-    }
-
-    fn write_mock_adb_script(dir: &PathBuf) {
-        let script_path = dir.join("adb");
-        let script = format!(
-            r#"#!/usr/bin/env sh
 
-if [ "$1" = "devices" ]; then
-  cat <<'EOF'
-{adb_devices}
-EOF
-  exit 0
-fi
+    let dir = std::env::temp_dir().join(format!("scrcpy-gui-adb-mock-{}", std::process::id()));
+    fs::create_dir_all(&dir).expect("Failed to create temp dir");
+    let script_path = dir.join("adb");
+    let script = format!(
+        r#"#!/usr/bin/env sh
 
-if [ "$1" = "-s" ]; then
+if [ "$1" = "-s" ] || [ "$1" = "-t" ]; then
   shift 2
 fi
 
 if [ "$1" = "shell" ]; then
-  shift
-  cmd="$1"
-
-  if [ "$cmd" = "dumpsys battery" ]; then
-    cat <<'EOF'
+  case "$2" in
+    "dumpsys battery") cat <<'EOF'
 {battery}
 EOF
-    exit 0
-  fi
-
-  if [ "$cmd" = "df /data" ]; then
-    cat <<'EOF'
+      exit 0 ;;
+    "df /data") cat <<'EOF'
 {storage}
 EOF
-    exit 0
-  fi
-
-  if [ "$cmd" = "getprop ro.product.model" ]; then
-    echo "{model}"
-    exit 0
-  fi
-
-  if [ "$cmd" = "getprop ro.build.version.release" ]; then
-    echo "{version}"
-    exit 0
-  fi
-
-  if [ "$cmd" = "getprop ro.build.id" ]; then
-    echo "{build}"
-    exit 0
-  fi
-
-  if [ "$cmd" = "echo ok" ]; then
-    echo "{latency}"
-    exit 0
-  fi
+      exit 0 ;;
+    "getprop ro.product.model") echo "{model}"; exit 0 ;;
+    "getprop ro.build.version.release") echo "{version}"; exit 0 ;;
+    "getprop ro.build.id") echo "{build}"; exit 0 ;;
+    "echo ok") echo "{latency}"; exit 0 ;;
+  esac
 fi
 
 echo "unknown command" 1>&2
 exit 1
 "#,
-            adb_devices = fixtures::ADB_DEVICES_OUTPUT,
-            battery = fixtures::BATTERY_OUTPUT_HIGH,
-            storage = fixtures::STORAGE_OUTPUT_GOOD,
-            model = fixtures::DEVICE_MODEL_OUTPUT,
-            version = fixtures::ANDROID_VERSION_OUTPUT,
-            build = fixtures::BUILD_ID_OUTPUT,
-            latency = fixtures::LATENCY_OUTPUT,
-        );
-
-        fs::write(&script_path, script).expect("Failed to write mock adb");
-        let mut perms = fs::metadata(&script_path).unwrap().permissions();
-        perms.set_mode(0o755);
-        fs::set_permissions(&script_path, perms).expect("Failed to set executable permission");
-    }
-
-    #[tokio::test]
-    async fn test_polling_with_mocked_devices() {
-        let temp_dir = create_temp_dir();
-        write_mock_adb_script(&temp_dir);
-
-        let original_path = env::var("PATH").unwrap_or_default();
-        let new_path = format!("{}:{}", temp_dir.display(), original_path);
-        env::set_var("PATH", new_path);
-
-        let provider = AdbHealthProvider::new(500);
-
-        let health_a = build_health(&provider, "emulator-5554").await;
-        let health_b = build_health(&provider, "ABC123").await;
-
-        assert_eq!(health_a.state, DeviceState::Online);
-        assert_eq!(health_b.state, DeviceState::Online);
-        assert!(health_a.battery.is_some());
-        assert!(health_a.storage.is_some());
-        assert!(health_a.device.is_some());
-
-        env::set_var("PATH", original_path);
-    }
-
-    async fn build_health(provider: &AdbHealthProvider, device_id: &str) -> DeviceHealth {
-        let battery = provider.get_battery_info(device_id).ok();
-        let storage = provider.get_storage_info(device_id).ok();
-        let device = provider.get_device_info(device_id).ok();
-
-        let connection = provider.get_latency(device_id).ok().map(|latency| {
-            let quality_level = match latency {
-                0..=49 => QualityLevel::Excellent,
-                50..=99 => QualityLevel::Good,
-                100..=199 => QualityLevel::Fair,
-                _ => QualityLevel::Poor,
-            };
-
-            ConnectionMetrics {
-                connection_type: ConnectionType::Usb,
-                latency,
-                signal_strength: None,
-                quality_level,
-                estimated_bandwidth: None,
-            }
-        });
-
-        DeviceHealth {
-            device_id: device_id.to_string(),
-            state: DeviceState::Online,
-            battery,
-            storage,
-            connection,
-            device,
-            staleness: StalenessLevel::Fresh,
-            last_seen: 0,
-            last_updated: 0,
-            error_reason: None,
-        }
-    }
+        battery = fixtures::BATTERY_OUTPUT_HIGH,
+        storage = fixtures::STORAGE_OUTPUT_GOOD,
+        model = fixtures::DEVICE_MODEL_OUTPUT,
+        version = fixtures::ANDROID_VERSION_OUTPUT,
+        build = fixtures::BUILD_ID_OUTPUT,
+        latency = fixtures::LATENCY_OUTPUT,
+    );
+    fs::write(&script_path, script).expect("Failed to write mock adb");
+    fs::set_permissions(&script_path, fs::Permissions::from_mode(0o755))
+        .expect("Failed to set executable permission");
+
+    let provider = provider_for(Arc::new(ProcessExecutor::new(&script_path)));
+    let health = build_health(&provider, "emulator-5554").await;
+
+    assert!(health.battery.is_some());
+    assert!(health.storage.is_some());
+    assert_eq!(health.device.unwrap().build_number, "TP1A.220624.014");
+    let _ = fs::remove_dir_all(dir);
 }
 
-#[cfg(not(unix))]
-mod non_unix_tests {
-    #[test]
-    fn test_polling_with_mocked_devices() {
-        // Skipped on non-unix platforms due to mock adb shell script usage.
-        assert!(true);
+async fn build_health(provider: &AdbHealthProvider, device_id: &str) -> DeviceHealth {
+    let battery = provider.get_battery_info(device_id).ok();
+    let storage = provider.get_storage_info(device_id).ok();
+    let device = provider.get_device_info(device_id).ok();
+
+    let connection = provider.get_latency(device_id).ok().map(|latency| {
+        let quality_level = match latency {
+            0..=49 => QualityLevel::Excellent,
+            50..=99 => QualityLevel::Good,
+            100..=199 => QualityLevel::Fair,
+            _ => QualityLevel::Poor,
+        };
+
+        ConnectionMetrics {
+            connection_type: ConnectionType::Usb,
+            latency,
+            signal_strength: None,
+            quality_level,
+            estimated_bandwidth: None,
+        }
+    });
+
+    DeviceHealth {
+        device_id: device_id.to_string(),
+        state: DeviceState::Online,
+        battery,
+        battery_estimate: None,
+        storage,
+        connection,
+        device,
+        staleness: StalenessLevel::Fresh,
+        last_seen: 0,
+        last_updated: 0,
+        error_reason: None,
     }
 }
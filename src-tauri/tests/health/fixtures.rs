@@ -27,7 +27,7 @@ pub mod fixtures {
   Max charging current: 500000
   Max charging voltage: 5000000
   Charge counter: 123456789
-  status: 2
+  status: 3
   health: 2
   present: true
   level: 5
@@ -35,12 +35,12 @@ pub mod fixtures {
   temperature: 360
   technology: Li-ion"#;
 
-    /// Mock dumpsys battery output (critical battery)
+    /// Mock dumpsys battery output (critical battery, discharging)
     pub const BATTERY_OUTPUT_CRITICAL: &str = r#"Current Battery Service state:
   AC powered: false
   USB powered: false
   Wireless powered: false
-  status: 2
+  status: 3
   health: 2
   present: true
   level: 3
@@ -48,6 +48,58 @@ pub mod fixtures {
   temperature: 350
   technology: Li-ion"#;
 
+    /// Samsung One UI: vendor lines, mV voltage, wall charger
+    pub const BATTERY_OUTPUT_SAMSUNG: &str = r#"Current Battery Service state:
+  AC powered: true
+  USB powered: false
+  Wireless powered: false
+  Max charging current: 3000000
+  Max charging voltage: 9000000
+  Charge counter: 3875000
+  status: 2
+  health: 2
+  present: true
+  level: 78
+  scale: 100
+  voltage: 4311
+  temperature: 312
+  technology: Li-ion
+  mSecPlugTypeSummary: 1
+  LED Charging: true
+  LED Low Battery: true
+  current now: 1850
+  charge counter: 3875000
+  Adaptive Fast Charging Settings: true
+  mSavedBatteryHealth: 1"#;
+
+    /// Build printing health constant names and voltage in µV, charging on
+    /// a wireless pad in the cold
+    pub const BATTERY_OUTPUT_NAMED_COLD: &str = r#"Current Battery Service state:
+  AC powered: false
+  USB powered: false
+  Wireless powered: true
+  Dock powered: false
+  Max charging current: 0
+  Charge counter: 1520000
+  status: 2
+  health: Cold
+  present: true
+  level: 41
+  scale: 100
+  voltage: 3812000
+  temperature: -52
+  technology: Li-poly"#;
+
+    /// Failing battery on an old build without the charger/counter lines
+    pub const BATTERY_OUTPUT_OVER_VOLTAGE: &str = r#"Current Battery Service state:
+  status: 3
+  health: Over voltage
+  present: true
+  level: 100
+  scale: 100
+  voltage: 4620
+  temperature: 300"#;
+
     /// Mock df /data output (plenty of storage)
     pub const STORAGE_OUTPUT_GOOD: &str = r#"Filesystem             Size Used Avail Use% Mounted on
 /dev/block/mmcblk0p30 107520 45256 62264  42% /data"#;
@@ -0,0 +1,141 @@
+//! Fixture-driven battery and storage parsing tests
+//!
+//! Feeds the `dumpsys battery` and `df /data` fixtures, including OEM
+//! variants, through `AdbHealthProvider` with a `ScriptedExecutor`.
+
+use super::fixtures::fixtures;
+use scrcpy_gui_lib::services::{AdbHealthProvider, ScriptedExecutor, SharedAdbExecutor};
+use scrcpy_gui_lib::types::{thresholds, BatteryHealth, BatteryInfo, ChargerSource, StorageInfo};
+use std::sync::Arc;
+
+fn provider_answering(command: &str, output: &str) -> AdbHealthProvider {
+    let adb = ScriptedExecutor::new();
+    adb.respond_shell(command, output);
+    AdbHealthProvider::with_executor(500, &(Arc::new(adb) as SharedAdbExecutor))
+}
+
+fn battery(output: &str) -> BatteryInfo {
+    provider_answering("dumpsys battery", output)
+        .get_battery_info("ABC123")
+        .unwrap()
+}
+
+fn storage(output: &str) -> StorageInfo {
+    provider_answering("df /data", output)
+        .get_storage_info("ABC123")
+        .unwrap()
+}
+
+#[test]
+fn test_aosp_battery_fixtures() {
+    let high = battery(fixtures::BATTERY_OUTPUT_HIGH);
+    assert_eq!(high.percentage, 85);
+    assert_eq!(high.temperature, Some(28));
+    assert_eq!(high.health, Some(BatteryHealth::Good));
+    assert_eq!(high.charger, Some(ChargerSource::Usb));
+    assert_eq!(high.is_charging, Some(true));
+    assert_eq!(high.charge_counter, Some(2_816_840_000));
+    assert_eq!(high.max_charging_current, Some(500_000));
+    // "Max charging voltage" is not the battery voltage
+    assert_eq!(high.voltage, None);
+
+    let low = battery(fixtures::BATTERY_OUTPUT_LOW);
+    assert_eq!(low.percentage, 5);
+    assert_eq!(low.temperature, Some(36));
+    assert_eq!(low.charger, Some(ChargerSource::Unplugged));
+    assert_eq!(low.is_charging, Some(false));
+    assert_eq!(
+        thresholds::battery_warning_level(low.percentage),
+        "critical"
+    );
+
+    let critical = battery(fixtures::BATTERY_OUTPUT_CRITICAL);
+    assert_eq!(critical.percentage, 3);
+    assert_eq!(critical.is_charging, Some(false));
+    assert_eq!(critical.charge_counter, None);
+    assert_eq!(critical.max_charging_current, None);
+    assert_eq!(
+        thresholds::battery_warning_level(critical.percentage),
+        "critical"
+    );
+}
+
+#[test]
+fn test_oem_battery_variants() {
+    let samsung = battery(fixtures::BATTERY_OUTPUT_SAMSUNG);
+    assert_eq!(samsung.percentage, 78);
+    assert_eq!(samsung.charger, Some(ChargerSource::Ac));
+    assert_eq!(samsung.is_charging, Some(true));
+    assert_eq!(samsung.voltage, Some(4311));
+    assert_eq!(samsung.charge_counter, Some(3_875_000));
+    assert_eq!(samsung.max_charging_current, Some(3_000_000));
+    // Not confused by mSavedBatteryHealth
+    assert_eq!(samsung.health, Some(BatteryHealth::Good));
+
+    let cold = battery(fixtures::BATTERY_OUTPUT_NAMED_COLD);
+    assert_eq!(cold.health, Some(BatteryHealth::Cold));
+    assert_eq!(cold.charger, Some(ChargerSource::Wireless));
+    assert_eq!(cold.is_charging, Some(true));
+    assert_eq!(cold.voltage, Some(3812)); // reported in µV
+    assert_eq!(cold.temperature, Some(-5));
+
+    let failing = battery(fixtures::BATTERY_OUTPUT_OVER_VOLTAGE);
+    assert_eq!(failing.health, Some(BatteryHealth::OverVoltage));
+    assert_eq!(failing.voltage, Some(4620));
+    assert_eq!(failing.is_charging, Some(false));
+    // Old builds don't report the charger or counters at all
+    assert_eq!(failing.charger, None);
+    assert_eq!(failing.charge_counter, None);
+}
+
+#[test]
+fn test_every_android_health_constant() {
+    let cases = [
+        ("1", BatteryHealth::Unknown),
+        ("2", BatteryHealth::Good),
+        ("3", BatteryHealth::Overheat),
+        ("4", BatteryHealth::Dead),
+        ("5", BatteryHealth::OverVoltage),
+        ("6", BatteryHealth::UnspecifiedFailure),
+        ("7", BatteryHealth::Cold),
+        ("Unspecified failure", BatteryHealth::UnspecifiedFailure),
+        ("UNSPECIFIED_FAILURE", BatteryHealth::UnspecifiedFailure),
+        ("Over-voltage", BatteryHealth::OverVoltage),
+        ("Dead", BatteryHealth::Dead),
+        ("Warm", BatteryHealth::Warm),
+        ("42", BatteryHealth::Unknown),
+    ];
+    for (reported, expected) in cases {
+        let output = format!("  health: {}\n  level: 50", reported);
+        assert_eq!(
+            battery(&output).health,
+            Some(expected),
+            "health: {}",
+            reported
+        );
+    }
+}
+
+#[test]
+fn test_battery_health_wire_format() {
+    let json = serde_json::to_value(BatteryHealth::UnspecifiedFailure).unwrap();
+    assert_eq!(json, "unspecified_failure");
+    let json = serde_json::to_value(BatteryHealth::OverVoltage).unwrap();
+    assert_eq!(json, "over_voltage");
+    let json = serde_json::to_value(ChargerSource::Ac).unwrap();
+    assert_eq!(json, "ac");
+}
+
+#[test]
+fn test_storage_fixtures() {
+    let good = storage(fixtures::STORAGE_OUTPUT_GOOD);
+    let low = storage(fixtures::STORAGE_OUTPUT_LOW);
+    let critical = storage(fixtures::STORAGE_OUTPUT_CRITICAL);
+
+    assert_eq!(low.total, 107_520 * 1024);
+    assert_eq!(low.free, 2_264 * 1024);
+    assert_eq!(critical.free, 1_264 * 1024);
+    assert!(good.free > low.free && low.free > critical.free);
+    assert_eq!(thresholds::storage_warning_level(low.free), "critical");
+    assert_eq!(thresholds::storage_warning_level(critical.free), "critical");
+}
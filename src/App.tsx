@@ -26,6 +26,7 @@ import {
   togglePresetFavorite,
 } from "./hooks/useDeviceSettings";
 import { buildArgs } from "./utils/command-builder";
+import { getErrorMessage, hasErrorCode } from "./utils/error-messages";
 import { useScrcpyVersion } from "./hooks/useScrcpyVersion";
 
 import Sidebar, { type Tab } from "./components/Sidebar";
@@ -344,7 +345,7 @@ function App() {
         "INFO",
       );
     } catch (e) {
-      addLog(`Failed to check dependencies: ${e}`, "ERROR");
+      addLog(`Failed to check dependencies: ${getErrorMessage(e)}`, "ERROR");
     }
   }
 
@@ -367,19 +368,21 @@ function App() {
         try {
           // Try to start health polling (may fail if already running, which is OK)
           await invoke("start_health_polling", {
-            device_ids: connectedDevices,
+            request: { deviceIds: connectedDevices },
           });
           addLog(`Health polling started for ${connectedDevices.length} device(s)`, "INFO");
         } catch (pollErr) {
           // Only log as info since polling might already be running
-          const errString = String(pollErr);
-          if (!errString.includes("already running")) {
-            addLog(`Warning: Health polling issue: ${pollErr}`, "WARN");
+          if (!hasErrorCode(pollErr, "session_exists")) {
+            addLog(
+              `Warning: Health polling issue: ${getErrorMessage(pollErr)}`,
+              "WARN",
+            );
           }
         }
       }
     } catch (e) {
-      addLog(`Failed to list devices: ${e}`, "ERROR");
+      addLog(`Failed to list devices: ${getErrorMessage(e)}`, "ERROR");
     } finally {
       setRefreshing(false);
       // Mark initialization complete once devices are first loaded
@@ -393,7 +396,7 @@ function App() {
       const devs: Device[] = await invoke("list_adb_devices");
       setAvailableUsbDevices(devs);
     } catch (e) {
-      addLog(`Failed to list ADB devices: ${e}`, "ERROR");
+      addLog(`Failed to list ADB devices: ${getErrorMessage(e)}`, "ERROR");
     } finally {
       setUsbRefreshing(false);
     }
@@ -422,7 +425,7 @@ function App() {
       await listDevices();
       return true;
     } catch (e) {
-      addLog(`Failed to add device ${serial}: ${e}`, "ERROR");
+      addLog(`Failed to add device ${serial}: ${getErrorMessage(e)}`, "ERROR");
       return false;
     }
   }
@@ -450,7 +453,7 @@ function App() {
         // Wait a bit before retrying
         await new Promise((resolve) => setTimeout(resolve, 500));
       } catch (e) {
-        addLog(`Error checking for device: ${e}`, "WARN");
+        addLog(`Error checking for device: ${getErrorMessage(e)}`, "WARN");
       }
     }
 
@@ -520,7 +523,7 @@ function App() {
               return;
             }
           } catch (e) {
-            addLog(`Failed to update wireless connection: ${e}`, "ERROR");
+            addLog(`Failed to update wireless connection: ${getErrorMessage(e)}`, "ERROR");
             setLoading(false);
             return;
           }
@@ -535,7 +538,7 @@ function App() {
       await invoke("test_device", { serial: actualSerial });
       addLog(`Device test passed for: ${actualSerial}`, "SUCCESS");
     } catch (e) {
-      addLog(`Device test failed: ${e}`, "ERROR");
+      addLog(`Device test failed: ${getErrorMessage(e)}`, "ERROR");
       setLoading(false);
       return;
     }
@@ -551,7 +554,7 @@ function App() {
         "SUCCESS",
       );
     } catch (e) {
-      addLog(`Failed to start scrcpy: ${e}`, "ERROR");
+      addLog(`Failed to start scrcpy: ${getErrorMessage(e)}`, "ERROR");
     } finally {
       setLoading(false);
     }
@@ -563,7 +566,7 @@ function App() {
       setActiveDevices((prev) => prev.filter((s) => s !== serial));
       addLog(`Scrcpy stopped for device: ${serial}`, "SUCCESS");
     } catch (e) {
-      addLog(`Failed to stop scrcpy: ${e}`, "ERROR");
+      addLog(`Failed to stop scrcpy: ${getErrorMessage(e)}`, "ERROR");
     }
   }
 
@@ -582,7 +585,7 @@ function App() {
       );
       addLog(`Device forgotten: ${serial}`, "SUCCESS");
     } catch (e) {
-      addLog(`Failed to forget device: ${e}`, "ERROR");
+      addLog(`Failed to forget device: ${getErrorMessage(e)}`, "ERROR");
     }
   }
 
@@ -608,7 +611,7 @@ function App() {
       );
       return await registerDevice(`${deviceIp.trim()}:${devicePort}`, name);
     } catch (error) {
-      addLog(`Failed to connect to wireless device: ${error}`, "ERROR");
+      addLog(`Failed to connect to wireless device: ${getErrorMessage(error)}`, "ERROR");
       throw error;
     } finally {
       setWirelessConnecting(false);
@@ -728,7 +731,7 @@ function App() {
 
       return true;
     } catch (error) {
-      addLog(`Failed to update wireless connection: ${error}`, "ERROR");
+      addLog(`Failed to update wireless connection: ${getErrorMessage(error)}`, "ERROR");
       return false;
     } finally {
       setWirelessConnecting(false);
@@ -757,7 +760,7 @@ function App() {
       );
       listDevices();
     } catch (error) {
-      addLog(`Failed to disconnect wireless device: ${error}`, "ERROR");
+      addLog(`Failed to disconnect wireless device: ${getErrorMessage(error)}`, "ERROR");
     } finally {
       setWirelessConnecting(false);
     }
@@ -869,7 +872,7 @@ function App() {
         addLog(`Successfully exported ${presets.length} presets`, "SUCCESS");
       }
     } catch (error) {
-      addLog(`Failed to export presets: ${error}`, "ERROR");
+      addLog(`Failed to export presets: ${getErrorMessage(error)}`, "ERROR");
     }
   }, [presets]);
 
@@ -908,7 +911,7 @@ function App() {
       savePresetsToStorage(mergedPresets);
       addLog(`Successfully imported ${validPresets.length} presets`, "SUCCESS");
     } catch (error) {
-      addLog(`Failed to import presets: ${error}`, "ERROR");
+      addLog(`Failed to import presets: ${getErrorMessage(error)}`, "ERROR");
     }
   }, [presets]);
 
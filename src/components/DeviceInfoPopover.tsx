@@ -18,6 +18,7 @@ import {
   getBatteryDisplay,
   getStorageDisplay,
 } from "../utils/health-warnings";
+import { getErrorMessage } from "../utils/error-messages";
 import { ConnectionQualityIndicator } from "./ConnectionQualityIndicator";
 import "./DeviceInfoPopover.css";
 
@@ -53,7 +54,7 @@ export const DeviceInfoPopover: React.FC<DeviceInfoPopoverProps> = ({
           setError(null);
         })
         .catch((err) => {
-          const errMsg = err instanceof Error ? err.message : String(err);
+          const errMsg = getErrorMessage(err);
           setError(errMsg);
           console.error("Failed to fetch device health:", err);
         })
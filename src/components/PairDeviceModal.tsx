@@ -1,5 +1,6 @@
 import { useState, useRef, useEffect, useCallback } from "react";
 import type { Device } from "../types/device";
+import { getErrorMessage } from "../utils/error-messages";
 
 type ConnectionStatus = "idle" | "connecting" | "success" | "error";
 
@@ -255,7 +256,7 @@ export default function PairDeviceModal({
                     setTimeout(() => onClose(), 1500);
                   } catch (e) {
                     setConnectionStatus("error");
-                    setConnectionMessage(`Add failed: ${e}`);
+                    setConnectionMessage(`Add failed: ${getErrorMessage(e)}`);
                   }
                 }}
               >
@@ -144,7 +144,9 @@ describe("useHealthPolling", () => {
       expect(mockInvoke).toHaveBeenCalledWith(
         "start_health_polling",
         expect.objectContaining({
-          deviceIds: ["device-1", "device-2"],
+          request: expect.objectContaining({
+            deviceIds: ["device-1", "device-2"],
+          }),
         }),
       );
     });
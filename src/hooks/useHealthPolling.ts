@@ -18,6 +18,7 @@ import { useState, useEffect, useCallback } from "react";
 import { listen, UnlistenFn } from "@tauri-apps/api/event";
 import { invoke } from "@tauri-apps/api/core";
 import { HealthPollingConfig, PollingErrorEvent } from "../types/health";
+import { getErrorMessage } from "../utils/error-messages";
 
 interface UseHealthPollingParams {
   enabled: boolean;
@@ -55,8 +56,7 @@ export function useHealthPolling(
       const resp = await invoke<{ success: boolean; message?: string }>(
         "start_health_polling",
         {
-          deviceIds,
-          config,
+          request: { deviceIds, config },
         },
       );
 
@@ -67,7 +67,7 @@ export function useHealthPolling(
         setError(resp.message || "Failed to start polling");
       }
     } catch (err) {
-      const errMsg = err instanceof Error ? err.message : String(err);
+      const errMsg = getErrorMessage(err);
       setError(errMsg);
       console.error("Failed to start health polling:", err);
     } finally {
@@ -94,7 +94,7 @@ export function useHealthPolling(
         setError(resp.message || "Failed to stop polling");
       }
     } catch (err) {
-      const errMsg = err instanceof Error ? err.message : String(err);
+      const errMsg = getErrorMessage(err);
       setError(errMsg);
       console.error("Failed to stop health polling:", err);
     } finally {
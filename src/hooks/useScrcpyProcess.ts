@@ -3,6 +3,7 @@ import { invoke } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
 import type { DeviceSettings, LogEntry } from "../types/settings";
 import { buildArgs } from "../utils/command-builder";
+import { getErrorMessage } from "../utils/error-messages";
 
 interface ScrcpyProcessState {
   activeDevices: string[];
@@ -34,7 +35,7 @@ export function useScrcpyProcess(): ScrcpyProcessState {
         await invoke("test_device", { serial });
         addLog(`Device test passed for: ${serial}`, "SUCCESS");
       } catch (e) {
-        addLog(`Device test failed: ${e}`, "ERROR");
+        addLog(`Device test failed: ${getErrorMessage(e)}`, "ERROR");
         setLoading(false);
         return;
       }
@@ -49,7 +50,7 @@ export function useScrcpyProcess(): ScrcpyProcessState {
           "SUCCESS",
         );
       } catch (e) {
-        addLog(`Failed to start scrcpy: ${e}`, "ERROR");
+        addLog(`Failed to start scrcpy: ${getErrorMessage(e)}`, "ERROR");
       } finally {
         setLoading(false);
       }
@@ -64,7 +65,7 @@ export function useScrcpyProcess(): ScrcpyProcessState {
         setActiveDevices((prev) => prev.filter((s) => s !== serial));
         addLog(`Scrcpy stopped for device: ${serial}`, "SUCCESS");
       } catch (e) {
-        addLog(`Failed to stop scrcpy: ${e}`, "ERROR");
+        addLog(`Failed to stop scrcpy: ${getErrorMessage(e)}`, "ERROR");
       }
     },
     [],
@@ -10,6 +10,7 @@ import {
   hasGamepad,
   hasVirtualDisplay,
 } from "../types/scrcpy";
+import { getErrorMessage } from "../utils/error-messages";
 
 interface ScrcpyVersionState {
   version: ScrcpyVersion | null;
@@ -49,7 +50,7 @@ export function useScrcpyVersion(): ScrcpyVersionState {
         }
       } catch (e) {
         if (!cancelled) {
-          setError(String(e));
+          setError(getErrorMessage(e));
         }
       } finally {
         if (!cancelled) {
@@ -3,6 +3,16 @@ export type DeviceStatus =
   | "device"
   | "offline"
   | "unauthorized"
+  | "authorizing"
+  | "connecting"
+  | "no_permissions"
+  | "bootloader"
+  | "recovery"
+  | "rescue"
+  | "sideload"
+  | "host"
+  | "detached"
+  | "unknown"
   | "disconnected";
 
 /** Device information returned from the persistent registry */
@@ -15,6 +25,31 @@ export interface Device {
   is_wireless: boolean;
   last_seen: string | null;
   first_seen: string;
+  /** Serial of the other transport of the same phone (USB <-> wireless) */
+  linked_serial?: string | null;
+  /** Retry `adb connect` automatically when this wireless device drops */
+  auto_reconnect?: boolean;
+  /** `ro.serialno`, shared by every transport of the same phone */
+  hardware_serial?: string | null;
+  /** USB port path from `adb devices -l`, e.g. `1-2.3` */
+  usb_path?: string | null;
+  product?: string | null;
+  device_codename?: string | null;
+  /** ADB transport id, only set while attached */
+  transport_id?: number | null;
+  /** Extra text ADB appended to the state, e.g. the "no permissions" reason */
+  status_detail?: string | null;
+}
+
+/** One physical phone grouped across its USB and wireless transports */
+export interface LogicalDevice {
+  id: string;
+  hardware_serial: string | null;
+  model: string | null;
+  android_version: string | null;
+  /** Best transport to use right now, null if none is online */
+  preferred_serial: string | null;
+  transports: Device[];
 }
 
 /** Device health information */
@@ -15,14 +15,41 @@ export type DeviceState = "online" | "offline" | "connecting" | "error";
 export type StalenessLevel = "fresh" | "stale" | "offline";
 export type ConnectionType = "usb" | "wireless";
 export type QualityLevel = "excellent" | "good" | "fair" | "poor";
-export type HealthUpdateReason = "poll" | "retry" | "manual_refresh";
-export type BatteryHealth = "good" | "warm" | "overheat";
+export type HealthUpdateReason =
+  | "poll"
+  | "retry"
+  | "manual_refresh"
+  | "stale"
+  | "offline"
+  | "error";
+export type BatteryHealth =
+  | "unknown"
+  | "good"
+  | "overheat"
+  | "dead"
+  | "over_voltage"
+  | "unspecified_failure"
+  | "cold"
+  | "warm";
+
+export type ChargerSource = "ac" | "usb" | "wireless" | "dock" | "unplugged";
 export type ErrorCode =
   | "offline"
   | "timeout"
   | "permission_denied"
   | "adb_error"
-  | "parse_error";
+  | "parse_error"
+  | "adb_missing"
+  | "adb_server_unavailable"
+  | "device_unauthorized"
+  | "device_not_found"
+  | "scrcpy_missing"
+  | "session_exists"
+  | "session_not_found"
+  | "invalid_input"
+  | "io"
+  | "unsupported"
+  | "internal";
 export type PollingStopReason = "user" | "app_shutdown" | "error";
 
 // ============================================================================
@@ -34,6 +61,18 @@ export interface BatteryInfo {
   temperature?: number; // Celsius
   isCharging?: boolean;
   health?: BatteryHealth;
+  chargeCounter?: number; // Remaining charge, µAh
+  currentNow?: number; // µA, positive while charging
+  charger?: ChargerSource;
+  voltage?: number; // Millivolts
+  maxChargingCurrent?: number; // µA the charger offers
+}
+
+export interface BatteryEstimate {
+  ratePerHour: number; // %/hour, negative while draining
+  timeToEmptyMs?: number;
+  timeToFullMs?: number;
+  source: "current" | "chargeCounter" | "level";
 }
 
 export interface StorageInfo {
@@ -65,6 +104,7 @@ export interface DeviceHealth {
 
   // Battery
   battery?: BatteryInfo;
+  batteryEstimate?: BatteryEstimate;
 
   // Storage
   storage?: StorageInfo;
@@ -106,7 +146,7 @@ export interface HealthPollingConfig {
   collectDeviceInfo: boolean; // Default: true
 
   // Performance
-  batchSize: number; // Default: 1
+  batchSize: number; // Default: 4 (1-32 devices polled at once)
   queryTimeout: number; // Default: 500
 }
 
@@ -123,7 +163,7 @@ export const DEFAULT_POLLING_CONFIG: HealthPollingConfig = {
   collectStorage: true,
   collectConnectionMetrics: true,
   collectDeviceInfo: true,
-  batchSize: 1,
+  batchSize: 4,
   queryTimeout: 500,
 };
 
@@ -136,6 +176,11 @@ export interface ErrorInfo {
   message: string;
 }
 
+/** Error every Tauri command rejects with */
+export interface BackendError extends ErrorInfo {
+  details?: string; // Raw tool output, if any
+}
+
 export interface ReconnectionState {
   deviceId: string;
   attempt: number; // 1-N
@@ -145,16 +190,38 @@ export interface ReconnectionState {
   startedAt: number; // Unix timestamp
 }
 
+// ============================================================================
+// Health History
+// ============================================================================
+
+export interface HealthSample {
+  timestamp: number; // Unix timestamp ms
+  batteryPercentage?: number;
+  batteryTemperature?: number; // Celsius
+  isCharging?: boolean;
+  storageFree?: number; // Bytes
+  latency?: number; // Milliseconds
+}
+
+export interface MetricStats {
+  min: number;
+  max: number;
+  avg: number;
+  count: number;
+}
+
 // ============================================================================
 // Command Protocols (Tauri IPC)
 // ============================================================================
 
 export interface StartHealthPollingRequest {
-  config: HealthPollingConfig;
-  devices: Array<{
+  config?: HealthPollingConfig;
+  devices?: Array<{
     deviceId: string;
     connectionType: ConnectionType;
   }>;
+  /** Bare serials; the connection type is detected by the backend */
+  deviceIds?: string[];
 }
 
 export interface HealthCommandResult {
@@ -168,6 +235,66 @@ export interface GetDeviceHealthResponse {
   cacheAge: number; // Milliseconds since last update
 }
 
+export interface HealthHistoryResponse {
+  deviceId: string;
+  samples: HealthSample[];
+  totalSamples: number; // Samples in range before downsampling
+}
+
+export interface HealthTrendsResponse {
+  deviceId: string;
+  sampleCount: number;
+  batteryPercentage?: MetricStats;
+  batteryTemperature?: MetricStats;
+  storageFree?: MetricStats;
+  latency?: MetricStats;
+  batteryDrainPerHour?: number; // %/hour while not charging
+}
+
+/** On-disk health history limits (Rust: `RetentionPolicy`) */
+export interface RetentionPolicy {
+  maxAgeHours: number; // default 168 (7 days)
+  maxSizeMb: number; // default 100
+}
+
+export type ExportFormat = "csv" | "jsonl";
+
+// ============================================================================
+// Health Alerts
+// ============================================================================
+
+export type AlertMetric =
+  | "batteryPercentage"
+  | "batteryTemperature" // °C
+  | "storageFree" // bytes
+  | "latency"; // ms
+
+export type AlertCondition =
+  | {
+      type: "threshold";
+      metric: AlertMetric;
+      comparison: "below" | "above";
+      value: number;
+      hysteresis?: number; // margin the metric must recover by to resolve
+    }
+  | { type: "charging"; charging: boolean }
+  | { type: "quality"; level: QualityLevel }; // this level or worse
+
+export type AlertSeverity = "info" | "warning" | "critical";
+
+export interface AlertRule {
+  id: string;
+  name: string;
+  enabled?: boolean; // default true
+  deviceId?: string; // all devices when omitted
+  conditions: AlertCondition[]; // all must match
+  severity: AlertSeverity;
+  minPolls?: number; // consecutive matching polls, default 1
+  minDurationMs?: number;
+  cooldownMs?: number;
+  notify?: boolean; // native desktop notification
+}
+
 // ============================================================================
 // Event Types (Tauri Events)
 // ============================================================================
@@ -188,6 +315,16 @@ export interface PollingErrorEvent {
   willRetry: boolean;
 }
 
+export interface HealthAlertEvent {
+  ruleId: string;
+  ruleName: string;
+  deviceId: string;
+  severity: AlertSeverity;
+  state: "triggered" | "resolved";
+  message: string;
+  timestamp: number;
+}
+
 export interface PollingStartedEvent {
   timestamp: number;
   config: HealthPollingConfig;
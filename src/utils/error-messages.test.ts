@@ -7,6 +7,8 @@ import {
   getFriendlyErrorMessage,
   getErrorDescription,
   getErrorMessage,
+  hasErrorCode,
+  isBackendError,
   isNetworkError,
   isPermissionError,
   ERROR_CONTEXTS,
@@ -184,6 +186,46 @@ describe("error-messages", () => {
       const result = getErrorMessage(error, "unknown");
       expect(result).toBe("Test error");
     });
+
+    it("extracts message from backend error", () => {
+      const error = {
+        code: "scrcpy_missing",
+        message: "scrcpy was not found",
+        details: "No such file or directory (os error 2)",
+      };
+      expect(getErrorMessage(error)).toBe("scrcpy was not found");
+      expect(getErrorMessage(error, "scrcpy-launch")).toBe(
+        "Scrcpy launch failed: scrcpy was not found",
+      );
+    });
+  });
+
+  describe("isBackendError", () => {
+    it("recognizes command errors by code and message", () => {
+      const error = {
+        code: "session_exists",
+        message: "Polling already running",
+      };
+      expect(isBackendError(error)).toBe(true);
+      expect(hasErrorCode(error, "session_exists")).toBe(true);
+      expect(hasErrorCode(error, "timeout")).toBe(false);
+    });
+
+    it("rejects other values", () => {
+      expect(isBackendError("Polling already running")).toBe(false);
+      expect(isBackendError(new Error("boom"))).toBe(false);
+      expect(isBackendError({ code: "timeout" })).toBe(false);
+      expect(isBackendError(null)).toBe(false);
+    });
+
+    it("has suggestions for backend-only codes", () => {
+      expect(getFriendlyErrorMessage("device_unauthorized").title).toBe(
+        "Device has not authorized this computer",
+      );
+      expect(getFriendlyErrorMessage("adb_missing").title).toBe(
+        "ADB is not installed",
+      );
+    });
   });
 
   describe("isNetworkError", () => {
@@ -196,6 +238,15 @@ describe("error-messages", () => {
       expect(isNetworkError("ENOTFOUND")).toBe(true);
     });
 
+    it("uses the code of backend errors", () => {
+      expect(
+        isNetworkError({ code: "timeout", message: "ADB request timed out" }),
+      ).toBe(true);
+      expect(
+        isNetworkError({ code: "io", message: "Network share unavailable" }),
+      ).toBe(false);
+    });
+
     it("returns false for non-network errors", () => {
       expect(isNetworkError(new Error("Permission denied"))).toBe(false);
       expect(isNetworkError("Something went wrong")).toBe(false);
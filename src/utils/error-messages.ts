@@ -5,6 +5,8 @@
  * for common device connection failures.
  */
 
+import type { BackendError } from "../types/health";
+
 /**
  * Context identifiers for different error scenarios
  */
@@ -104,6 +106,58 @@ const ERROR_SUGGESTIONS: Record<string, ErrorSuggestion> = {
     ],
   },
 
+  adb_missing: {
+    title: "ADB is not installed",
+    steps: [
+      "Install Android SDK Platform-Tools",
+      "Add the platform-tools directory to your system PATH",
+      "Restart the app after installing",
+    ],
+    docLink: "https://developer.android.com/tools/releases/platform-tools",
+  },
+
+  adb_server_unavailable: {
+    title: "ADB server is not running",
+    steps: [
+      "Restart the ADB server from the settings page",
+      "Close other Android tools that may hold port 5037",
+      "Run 'adb start-server' and check its output",
+    ],
+  },
+
+  device_unauthorized: {
+    title: "Device has not authorized this computer",
+    steps: [
+      "Unlock the device and accept the 'Allow USB debugging?' dialog",
+      "Tick 'Always allow from this computer' to skip it next time",
+      "If no dialog appears, revoke USB debugging authorizations and reconnect",
+    ],
+  },
+
+  device_not_found: {
+    title: "Device not found",
+    steps: [
+      "Check that the device is still connected",
+      "Refresh the device list",
+      "For wireless devices, make sure wireless debugging is still enabled",
+    ],
+  },
+
+  scrcpy_missing: {
+    title: "scrcpy is not installed",
+    steps: [
+      "Install scrcpy from your package manager or the official releases",
+      "Make sure the scrcpy binary is in your system PATH",
+      "Restart the app after installing",
+    ],
+    docLink: "https://github.com/Genymobile/scrcpy",
+  },
+
+  session_exists: {
+    title: "Already running",
+    steps: ["Stop the running session before starting a new one"],
+  },
+
   network_error: {
     title: "Network connectivity issue",
     steps: [
@@ -147,10 +201,32 @@ export function getErrorDescription(errorCode: string): string {
   return suggestion.title;
 }
 
+/**
+ * Check if a value is an error rejected by a Tauri command
+ */
+export function isBackendError(error: unknown): error is BackendError {
+  return (
+    typeof error === "object" &&
+    error !== null &&
+    typeof (error as BackendError).code === "string" &&
+    typeof (error as BackendError).message === "string"
+  );
+}
+
+/**
+ * Check if an error has the given backend error code
+ */
+export function hasErrorCode(
+  error: unknown,
+  code: BackendError["code"],
+): boolean {
+  return isBackendError(error) && error.code === code;
+}
+
 /**
  * Extract a user-friendly error message from any error type
  *
- * @param error - The error to extract message from (Error, string, or unknown)
+ * @param error - The error to extract message from (Error, backend error, string, or unknown)
  * @param context - Optional context describing the operation that failed
  * @returns A user-friendly error message string
  * @example
@@ -169,6 +245,8 @@ export function getErrorMessage(
 
   if (error instanceof Error) {
     message = error.message;
+  } else if (isBackendError(error)) {
+    message = error.message;
   } else if (typeof error === "string") {
     message = error;
   } else {
@@ -190,6 +268,9 @@ export function getErrorMessage(
  * @returns True if the error appears to be network-related
  */
 export function isNetworkError(error: unknown): boolean {
+  if (isBackendError(error)) {
+    return error.code === "offline" || error.code === "timeout";
+  }
   if (!(error instanceof Error) && typeof error !== "string") {
     return false;
   }
@@ -215,6 +296,12 @@ export function isNetworkError(error: unknown): boolean {
  * @returns True if the error appears to be permission-related
  */
 export function isPermissionError(error: unknown): boolean {
+  if (isBackendError(error)) {
+    return (
+      error.code === "permission_denied" ||
+      error.code === "device_unauthorized"
+    );
+  }
   if (!(error instanceof Error) && typeof error !== "string") {
     return false;
   }